@@ -0,0 +1,8 @@
+BEGIN;
+
+DROP TABLE security_events;
+DROP INDEX user_sessions_family_id_idx;
+ALTER TABLE user_sessions DROP COLUMN rotated_at;
+ALTER TABLE user_sessions DROP COLUMN family_id;
+
+COMMIT;
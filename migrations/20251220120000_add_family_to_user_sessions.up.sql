@@ -0,0 +1,18 @@
+BEGIN;
+
+ALTER TABLE user_sessions ADD COLUMN family_id UUID;
+ALTER TABLE user_sessions ADD COLUMN rotated_at TIMESTAMP;
+UPDATE user_sessions SET family_id = id;
+ALTER TABLE user_sessions ALTER COLUMN family_id SET NOT NULL;
+ALTER TABLE user_sessions ALTER COLUMN family_id SET DEFAULT uuidv7();
+CREATE INDEX user_sessions_family_id_idx ON user_sessions (family_id);
+
+CREATE TABLE security_events (
+  id UUID PRIMARY KEY DEFAULT uuidv7(),
+  kind VARCHAR(64) NOT NULL,
+  created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+  user_credential_id UUID REFERENCES user_credentials(id) ON DELETE CASCADE,
+  user_session_family_id UUID
+);
+
+COMMIT;
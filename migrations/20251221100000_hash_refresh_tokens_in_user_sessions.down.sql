@@ -0,0 +1,7 @@
+BEGIN;
+
+-- исходные токены из хэша не восстановить, поэтому все сессии закрываются
+UPDATE user_sessions SET disabled_at = CURRENT_TIMESTAMP WHERE disabled_at IS NULL;
+ALTER TABLE user_sessions RENAME COLUMN refresh_token_digest TO refresh_token;
+
+COMMIT;
@@ -0,0 +1,6 @@
+BEGIN;
+
+ALTER TABLE user_sessions RENAME COLUMN refresh_token TO refresh_token_digest;
+UPDATE user_sessions SET refresh_token_digest = encode(sha256(convert_to(refresh_token_digest, 'UTF8')), 'hex');
+
+COMMIT;
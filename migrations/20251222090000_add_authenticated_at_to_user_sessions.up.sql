@@ -0,0 +1,9 @@
+BEGIN;
+
+ALTER TABLE user_sessions ADD COLUMN authenticated_at TIMESTAMP;
+UPDATE user_sessions s SET authenticated_at = (SELECT MIN(f.created_at) FROM user_sessions f WHERE f.family_id = s.family_id);
+UPDATE user_sessions SET authenticated_at = CURRENT_TIMESTAMP WHERE authenticated_at IS NULL;
+ALTER TABLE user_sessions ALTER COLUMN authenticated_at SET NOT NULL;
+ALTER TABLE user_sessions ALTER COLUMN authenticated_at SET DEFAULT CURRENT_TIMESTAMP;
+
+COMMIT;
@@ -0,0 +1,6 @@
+BEGIN;
+
+ALTER TABLE user_sessions DROP COLUMN ip_address;
+ALTER TABLE user_sessions DROP COLUMN user_agent;
+
+COMMIT;
@@ -0,0 +1,6 @@
+BEGIN;
+
+ALTER TABLE user_sessions ADD COLUMN user_agent VARCHAR(512);
+ALTER TABLE user_sessions ADD COLUMN ip_address VARCHAR(45);
+
+COMMIT;
@@ -0,0 +1,15 @@
+BEGIN;
+
+CREATE TABLE user_credential_confirmations (
+  id UUID PRIMARY KEY DEFAULT uuidv7(),
+  token_digest CHAR(64) UNIQUE NOT NULL,
+  created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+  expires_at TIMESTAMP NOT NULL,
+  used_at TIMESTAMP,
+  user_credential_id UUID NOT NULL REFERENCES user_credentials(id) ON DELETE CASCADE
+);
+
+-- раньше confirmed_at проставлялся при первом входе, имена пользователей считаем подтверждёнными сразу
+UPDATE user_credentials SET confirmed_at = COALESCE(confirmed_at, CURRENT_TIMESTAMP) WHERE kind = 'username';
+
+COMMIT;
@@ -0,0 +1,12 @@
+BEGIN;
+
+DELETE FROM user_credential_confirmations WHERE id IN (
+  SELECT id FROM (
+    SELECT id, ROW_NUMBER() OVER (PARTITION BY token_digest ORDER BY created_at DESC) AS position FROM user_credential_confirmations
+  ) numbered WHERE position > 1
+);
+ALTER TABLE user_credential_confirmations DROP COLUMN attempts;
+DROP INDEX user_credential_confirmations_token_digest_idx;
+ALTER TABLE user_credential_confirmations ADD CONSTRAINT user_credential_confirmations_token_digest_key UNIQUE (token_digest);
+
+COMMIT;
@@ -0,0 +1,8 @@
+BEGIN;
+
+-- повторно выданный код из SMS может совпасть с предыдущим
+ALTER TABLE user_credential_confirmations DROP CONSTRAINT user_credential_confirmations_token_digest_key;
+CREATE INDEX user_credential_confirmations_token_digest_idx ON user_credential_confirmations (token_digest);
+ALTER TABLE user_credential_confirmations ADD COLUMN attempts SMALLINT NOT NULL DEFAULT 0;
+
+COMMIT;
@@ -0,0 +1,8 @@
+CREATE TABLE password_resets (
+  id UUID PRIMARY KEY DEFAULT uuidv7(),
+  token_digest CHAR(64) UNIQUE NOT NULL,
+  created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+  expires_at TIMESTAMP NOT NULL,
+  used_at TIMESTAMP,
+  user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE
+);
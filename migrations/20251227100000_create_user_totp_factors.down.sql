@@ -0,0 +1,6 @@
+BEGIN;
+
+DROP TABLE user_mfa_challenges;
+DROP TABLE user_totp_factors;
+
+COMMIT;
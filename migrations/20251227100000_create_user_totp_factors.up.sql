@@ -0,0 +1,22 @@
+BEGIN;
+
+CREATE TABLE user_totp_factors (
+  id UUID PRIMARY KEY DEFAULT uuidv7(),
+  secret VARCHAR(64) NOT NULL,
+  created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+  confirmed_at TIMESTAMP,
+  last_used_step BIGINT,
+  user_id UUID UNIQUE NOT NULL REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE TABLE user_mfa_challenges (
+  id UUID PRIMARY KEY DEFAULT uuidv7(),
+  token_digest CHAR(64) UNIQUE NOT NULL,
+  created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+  expires_at TIMESTAMP NOT NULL,
+  used_at TIMESTAMP,
+  attempts SMALLINT NOT NULL DEFAULT 0,
+  user_credential_id UUID NOT NULL REFERENCES user_credentials(id) ON DELETE CASCADE
+);
+
+COMMIT;
@@ -0,0 +1,24 @@
+BEGIN;
+
+CREATE TABLE user_passkeys (
+  id UUID PRIMARY KEY DEFAULT uuidv7(),
+  credential_id VARCHAR(1366) UNIQUE NOT NULL,
+  public_key BYTEA NOT NULL,
+  sign_count BIGINT NOT NULL DEFAULT 0,
+  name VARCHAR(255),
+  created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+  last_used_at TIMESTAMP,
+  user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE TABLE webauthn_challenges (
+  id UUID PRIMARY KEY DEFAULT uuidv7(),
+  challenge_digest CHAR(64) UNIQUE NOT NULL,
+  kind VARCHAR(32) NOT NULL,
+  created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+  expires_at TIMESTAMP NOT NULL,
+  used_at TIMESTAMP,
+  user_id UUID REFERENCES users(id) ON DELETE CASCADE
+);
+
+COMMIT;
@@ -0,0 +1,7 @@
+CREATE TABLE user_recovery_codes (
+  id UUID PRIMARY KEY DEFAULT uuidv7(),
+  code_digest VARCHAR(255) NOT NULL,
+  created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+  used_at TIMESTAMP,
+  user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE
+);
@@ -0,0 +1,8 @@
+CREATE TABLE magic_links (
+  id UUID PRIMARY KEY DEFAULT uuidv7(),
+  token_digest CHAR(64) UNIQUE NOT NULL,
+  created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+  expires_at TIMESTAMP NOT NULL,
+  used_at TIMESTAMP,
+  user_credential_id UUID NOT NULL REFERENCES user_credentials(id) ON DELETE CASCADE
+);
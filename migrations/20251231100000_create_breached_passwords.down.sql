@@ -0,0 +1 @@
+DROP TABLE breached_passwords;
@@ -0,0 +1,6 @@
+CREATE TABLE breached_passwords (
+  prefix CHAR(5) NOT NULL,
+  suffix CHAR(35) NOT NULL,
+  count BIGINT NOT NULL,
+  PRIMARY KEY (prefix, suffix)
+);
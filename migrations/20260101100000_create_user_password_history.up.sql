@@ -0,0 +1,8 @@
+CREATE TABLE user_password_history (
+  id UUID PRIMARY KEY DEFAULT uuidv7(),
+  password_digest VARCHAR(255) NOT NULL,
+  created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+  user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE INDEX user_password_history_user_id_idx ON user_password_history (user_id);
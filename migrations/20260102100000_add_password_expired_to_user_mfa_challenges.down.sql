@@ -0,0 +1 @@
+ALTER TABLE user_mfa_challenges DROP COLUMN password_expired;
@@ -0,0 +1,2 @@
+-- истёкший пароль проверяется после второго фактора, до тех пор признак хранится в вызове
+ALTER TABLE user_mfa_challenges ADD COLUMN password_expired BOOLEAN NOT NULL DEFAULT FALSE;
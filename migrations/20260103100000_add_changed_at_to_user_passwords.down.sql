@@ -0,0 +1 @@
+ALTER TABLE user_passwords DROP COLUMN changed_at;
@@ -0,0 +1,3 @@
+-- срок пароля считается от changed_at по текущей политике; в expires_in остаётся только досрочное истечение
+ALTER TABLE user_passwords ADD COLUMN changed_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP;
+UPDATE user_passwords SET expires_in = NULL WHERE expires_in > CURRENT_TIMESTAMP;
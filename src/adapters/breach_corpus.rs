@@ -0,0 +1,36 @@
+use crate::{
+    errors::AppError,
+    adapters::postgres::UserRepository,
+    app::{
+        BreachedPasswordSuffix,
+        commands::{
+            BreachedPasswordDao,
+            import_breached_passwords::parse_breached_password_range,
+        },
+    },
+};
+
+// откуда берутся диапазоны утечек, всё локально, чтобы работать без доступа в интернет
+#[derive(Clone)]
+pub enum BreachCorpus {
+    Disabled,
+    // каталог выгрузки Have I Been Pwned: файл {PREFIX}.txt на каждый диапазон
+    Files(std::path::PathBuf),
+    // таблица, заполненная командой import-breached-passwords
+    Postgres(UserRepository),
+}
+
+impl BreachedPasswordDao for BreachCorpus {
+    async fn find_breached_password_range(&self, prefix: String) -> Result<Vec<BreachedPasswordSuffix>, AppError> {
+        match self {
+            BreachCorpus::Disabled => Ok(Vec::new()),
+            BreachCorpus::Files(range_dir) => match tokio::fs::read_to_string(range_dir.join(format!("{prefix}.txt"))).await {
+                Ok(contents) => Ok(parse_breached_password_range(&contents)),
+                // диапазона нет в выгрузке - значит, и утечек по нему нет
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+                Err(_) => Err(AppError::UnknownError),
+            },
+            BreachCorpus::Postgres(repo) => repo.find_breached_password_range(prefix).await,
+        }
+    }
+}
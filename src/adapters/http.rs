@@ -0,0 +1,145 @@
+use std::sync::Arc;
+use axum::{
+    Json,
+    Router,
+    http::StatusCode,
+    middleware,
+    response::{IntoResponse, Response},
+    routing::{delete, get, post, put},
+};
+use crate::{
+    di::AppContainer,
+    errors::AppError,
+    app::commands::password_policy::PasswordWeakness,
+};
+
+pub mod admin;
+pub mod users;
+pub mod credentials;
+pub mod passwords;
+pub mod mfa;
+pub mod passkeys;
+pub mod sessions;
+pub mod tokens;
+
+pub type AppState = Arc<AppContainer>;
+
+#[derive(serde::Serialize)]
+struct ErrorBody {
+    error: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    reasons: Vec<PasswordWeakness>,
+}
+
+pub fn router(container: AppState, admin_token: String) -> Router {
+    let mut router = Router::new()
+        .route("/users", post(users::register))
+        .route("/users/{user_id}", delete(users::delete))
+        .route("/users/{user_id}/password", put(users::change_password))
+        .route("/users/{user_id}/restore", post(users::restore))
+        .route("/users/{user_id}/sessions", get(sessions::list))
+        .route("/users/{user_id}/sessions/{session_id}", delete(sessions::revoke))
+        .route("/users/{user_id}/mfa/totp", post(mfa::enroll_totp))
+        .route("/users/{user_id}/mfa/totp/confirm", post(mfa::confirm_totp))
+        .route("/users/{user_id}/mfa/recovery-codes", post(mfa::regenerate_recovery_codes))
+        .route("/users/{user_id}/passkeys", post(passkeys::register))
+        .route("/users/{user_id}/passkeys/registration-options", post(passkeys::registration_options))
+        .route("/credentials/confirm", post(credentials::confirm))
+        .route("/credentials/confirm/phone", post(credentials::confirm_phone))
+        .route("/credentials/confirm/resend", post(credentials::resend_confirmation))
+        .route("/passwords/reset-requests", post(passwords::request_reset))
+        .route("/passwords/reset", post(passwords::reset))
+        .route("/sessions", post(sessions::authenticate))
+        .route("/sessions/mfa/totp", post(mfa::verify_totp))
+        .route("/sessions/mfa/recovery-code", post(mfa::verify_recovery_code))
+        .route("/sessions/magic-link", post(sessions::redeem_magic_link))
+        .route("/sessions/magic-link/requests", post(sessions::request_magic_link))
+        .route("/sessions/passkey", post(passkeys::login))
+        .route("/sessions/passkey/options", post(passkeys::login_options))
+        .route("/sessions/refresh", post(sessions::refresh))
+        .route("/sessions/logout", post(sessions::logout))
+        .route("/sessions/logout-all", post(sessions::logout_everywhere))
+        .route("/tokens/verify", post(tokens::verify))
+        .route("/.well-known/jwks.json", get(tokens::jwks));
+
+    // без токена администратора админские маршруты не публикуются
+    if !admin_token.is_empty() {
+        let admin_router = Router::new()
+            .route("/admin/signing-keys/reload", post(admin::reload_signing_keys))
+            .route("/admin/signing-keys/{kid}/promote", post(admin::promote_signing_key))
+            .route("/admin/users/{id}/password/expire", post(admin::expire_password))
+            .route_layer(middleware::from_fn_with_state(Arc::new(admin_token), admin::authorize));
+        router = router.merge(admin_router);
+    }
+
+    router.with_state(container)
+}
+
+pub async fn serve(host: &str, port: u16, container: AppState, admin_token: String) -> std::io::Result<()> {
+    let listener = tokio::net::TcpListener::bind((host, port)).await?;
+    let app = router(container, admin_token).into_make_service_with_connect_info::<std::net::SocketAddr>();
+    axum::serve(listener, app).await
+}
+
+fn status_code(error: &AppError) -> StatusCode {
+    match error {
+        AppError::UsernameIsTaken => StatusCode::CONFLICT,
+        AppError::UnknownDatabaseError => StatusCode::INTERNAL_SERVER_ERROR,
+        AppError::WeakPassword(_) => StatusCode::UNPROCESSABLE_ENTITY,
+        AppError::UnknownError => StatusCode::INTERNAL_SERVER_ERROR,
+        AppError::LoginError => StatusCode::UNAUTHORIZED,
+        AppError::TempLocked => StatusCode::LOCKED,
+        AppError::LoginRequired => StatusCode::UNAUTHORIZED,
+        AppError::NotFound => StatusCode::NOT_FOUND,
+        AppError::AccessDenied => StatusCode::FORBIDDEN,
+        AppError::ExpiredToken => StatusCode::UNAUTHORIZED,
+        AppError::MalformedToken => StatusCode::UNAUTHORIZED,
+        AppError::InvalidTokenSignature => StatusCode::UNAUTHORIZED,
+        AppError::InvalidToken => StatusCode::UNAUTHORIZED,
+        AppError::InvalidKeyRing => StatusCode::UNPROCESSABLE_ENTITY,
+        AppError::RefreshTokenReused => StatusCode::UNAUTHORIZED,
+        AppError::TooManySessions => StatusCode::CONFLICT,
+        AppError::SessionExpired => StatusCode::UNAUTHORIZED,
+        AppError::InvalidLogin => StatusCode::UNPROCESSABLE_ENTITY,
+        AppError::UnconfirmedCredential => StatusCode::FORBIDDEN,
+        AppError::MfaAlreadyEnabled => StatusCode::CONFLICT,
+        AppError::MfaNotEnabled => StatusCode::CONFLICT,
+        AppError::InvalidPasskey => StatusCode::UNPROCESSABLE_ENTITY,
+        AppError::MalformedRecord => StatusCode::UNPROCESSABLE_ENTITY,
+        AppError::UnsupportedPasswordDigest => StatusCode::UNPROCESSABLE_ENTITY,
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let reasons = match &self {
+            AppError::WeakPassword(reasons) => reasons.clone(),
+            _ => Vec::new(),
+        };
+        let body = ErrorBody { error: self.to_string(), reasons };
+        (status_code(&self), Json(body)).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn map_app_errors_to_status_codes() {
+        // Given
+        let taken = AppError::UsernameIsTaken;
+        let locked = AppError::TempLocked;
+        let login_error = AppError::LoginError;
+
+        // When
+        let taken_response = taken.into_response();
+        let locked_response = locked.into_response();
+        let login_error_response = login_error.into_response();
+
+        // Then
+        assert_eq!(taken_response.status(), StatusCode::CONFLICT);
+        assert_eq!(locked_response.status(), StatusCode::LOCKED);
+        assert_eq!(login_error_response.status(), StatusCode::UNAUTHORIZED);
+    }
+}
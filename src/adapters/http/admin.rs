@@ -0,0 +1,60 @@
+use std::sync::Arc;
+use axum::{
+    extract::{Path, Request, State},
+    http::{StatusCode, header::AUTHORIZATION},
+    middleware::Next,
+    response::Response,
+};
+use crate::{
+    errors::AppError,
+    adapters::http::AppState,
+};
+
+pub async fn authorize(
+    State(admin_token): State<Arc<String>>,
+    request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let header = match request.headers().get(AUTHORIZATION).and_then(|value| value.to_str().ok()) {
+        Some(header) => header,
+        None => return Err(AppError::LoginRequired),
+    };
+    let is_admin = match header.strip_prefix("Bearer ") {
+        Some(token) => is_same_token(admin_token.as_bytes(), token.trim().as_bytes()),
+        None => false,
+    };
+    if !is_admin {
+        return Err(AppError::AccessDenied);
+    }
+
+    Ok(next.run(request).await)
+}
+
+// сравнение за постоянное время, чтобы токен нельзя было подобрать по таймингу
+fn is_same_token(expected: &[u8], actual: &[u8]) -> bool {
+    expected.len() == actual.len() && expected.iter().zip(actual).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+pub async fn reload_signing_keys(State(container): State<AppState>) -> Result<StatusCode, AppError> {
+    container.reload_signing_keys_command.call()?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn promote_signing_key(
+    State(container): State<AppState>,
+    Path(kid): Path<String>,
+) -> Result<StatusCode, AppError> {
+    container.promote_signing_key_command.call(kid)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn expire_password(
+    State(container): State<AppState>,
+    Path(user_id): Path<uuid::Uuid>,
+) -> Result<StatusCode, AppError> {
+    container.expire_password_command.call(user_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
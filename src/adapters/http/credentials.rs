@@ -0,0 +1,52 @@
+use axum::{
+    Json,
+    extract::State,
+    http::StatusCode,
+};
+use crate::{
+    errors::AppError,
+    adapters::http::AppState,
+};
+
+#[derive(serde::Deserialize)]
+pub struct ConfirmCredentialRequest {
+    pub token: String,
+}
+
+#[derive(serde::Deserialize)]
+pub struct ConfirmPhoneRequest {
+    pub login: String,
+    pub code: String,
+}
+
+#[derive(serde::Deserialize)]
+pub struct RequestCredentialConfirmationRequest {
+    pub login: String,
+}
+
+pub async fn confirm(
+    State(container): State<AppState>,
+    Json(request): Json<ConfirmCredentialRequest>,
+) -> Result<StatusCode, AppError> {
+    container.confirm_credential_command.call(request.token).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn confirm_phone(
+    State(container): State<AppState>,
+    Json(request): Json<ConfirmPhoneRequest>,
+) -> Result<StatusCode, AppError> {
+    container.confirm_phone_command.call(request.login, request.code).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn resend_confirmation(
+    State(container): State<AppState>,
+    Json(request): Json<RequestCredentialConfirmationRequest>,
+) -> Result<StatusCode, AppError> {
+    container.request_credential_confirmation_command.call(request.login).await?;
+
+    Ok(StatusCode::ACCEPTED)
+}
@@ -0,0 +1,91 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use crate::{
+    errors::AppError,
+    app::commands::{
+        Authentication,
+        Reauthentication,
+        RecoveryCodes,
+        TotpEnrollment,
+    },
+    adapters::http::{
+        AppState,
+        sessions::{Client, authentication_status},
+        tokens::AccessClaims,
+    },
+};
+
+#[derive(serde::Deserialize)]
+pub struct ConfirmTotpRequest {
+    pub code: String,
+}
+
+#[derive(serde::Deserialize)]
+pub struct VerifyTotpChallengeRequest {
+    pub mfa_token: String,
+    pub code: String,
+}
+
+#[derive(serde::Deserialize)]
+pub struct VerifyRecoveryCodeChallengeRequest {
+    pub mfa_token: String,
+    pub recovery_code: String,
+}
+
+pub async fn enroll_totp(
+    State(container): State<AppState>,
+    Path(user_id): Path<uuid::Uuid>,
+    claims: AccessClaims,
+) -> Result<(StatusCode, Json<TotpEnrollment>), AppError> {
+    claims.authorize(user_id)?;
+    let enrollment = container.enroll_totp_command.call(user_id).await?;
+
+    Ok((StatusCode::CREATED, Json(enrollment)))
+}
+
+pub async fn confirm_totp(
+    State(container): State<AppState>,
+    Path(user_id): Path<uuid::Uuid>,
+    claims: AccessClaims,
+    Json(request): Json<ConfirmTotpRequest>,
+) -> Result<Json<RecoveryCodes>, AppError> {
+    claims.authorize(user_id)?;
+    let recovery_codes = container.confirm_totp_command.call(user_id, request.code).await?;
+
+    Ok(Json(recovery_codes))
+}
+
+pub async fn regenerate_recovery_codes(
+    State(container): State<AppState>,
+    Path(user_id): Path<uuid::Uuid>,
+    claims: AccessClaims,
+    Json(reauthentication): Json<Reauthentication>,
+) -> Result<(StatusCode, Json<RecoveryCodes>), AppError> {
+    claims.authorize(user_id)?;
+    let recovery_codes = container.regenerate_recovery_codes_command.call(user_id, reauthentication).await?;
+
+    Ok((StatusCode::CREATED, Json(recovery_codes)))
+}
+
+pub async fn verify_totp(
+    State(container): State<AppState>,
+    Client(client): Client,
+    Json(request): Json<VerifyTotpChallengeRequest>,
+) -> Result<(StatusCode, Json<Authentication>), AppError> {
+    let authentication = container.verify_totp_challenge_command.call(request.mfa_token, request.code, client).await?;
+
+    Ok((authentication_status(&authentication), Json(authentication)))
+}
+
+pub async fn verify_recovery_code(
+    State(container): State<AppState>,
+    Client(client): Client,
+    Json(request): Json<VerifyRecoveryCodeChallengeRequest>,
+) -> Result<(StatusCode, Json<Authentication>), AppError> {
+    let authentication = container.verify_recovery_code_challenge_command.call(request.mfa_token, request.recovery_code, client).await?;
+
+    Ok((authentication_status(&authentication), Json(authentication)))
+}
@@ -0,0 +1,89 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use crate::{
+    errors::AppError,
+    app::commands::{
+        Authentication,
+        PasskeyCreationOptions,
+        PasskeyRequestOptions,
+        RecoveryCodes,
+        finish_passkey_login::PasskeyAssertionRequest,
+    },
+    adapters::http::{
+        AppState,
+        sessions::{Client, authentication_status},
+        tokens::AccessClaims,
+    },
+};
+
+#[derive(serde::Deserialize)]
+pub struct RegisterPasskeyRequest {
+    pub client_data_json: String,
+    pub attestation_object: String,
+    pub name: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+pub struct PasskeyLoginOptionsRequest {
+    pub mfa_token: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+pub struct PasskeyLoginRequest {
+    pub credential_id: String,
+    pub client_data_json: String,
+    pub authenticator_data: String,
+    pub signature: String,
+    pub mfa_token: Option<String>,
+}
+
+pub async fn registration_options(
+    State(container): State<AppState>,
+    Path(user_id): Path<uuid::Uuid>,
+    claims: AccessClaims,
+) -> Result<Json<PasskeyCreationOptions>, AppError> {
+    claims.authorize(user_id)?;
+    let options = container.start_passkey_registration_command.call(user_id).await?;
+
+    Ok(Json(options))
+}
+
+pub async fn register(
+    State(container): State<AppState>,
+    Path(user_id): Path<uuid::Uuid>,
+    claims: AccessClaims,
+    Json(request): Json<RegisterPasskeyRequest>,
+) -> Result<(StatusCode, Json<RecoveryCodes>), AppError> {
+    claims.authorize(user_id)?;
+    let recovery_codes = container.finish_passkey_registration_command.call(user_id, request.client_data_json, request.attestation_object, request.name).await?;
+
+    Ok((StatusCode::CREATED, Json(recovery_codes)))
+}
+
+pub async fn login_options(
+    State(container): State<AppState>,
+    Json(request): Json<PasskeyLoginOptionsRequest>,
+) -> Result<Json<PasskeyRequestOptions>, AppError> {
+    let options = container.start_passkey_login_command.call(request.mfa_token).await?;
+
+    Ok(Json(options))
+}
+
+pub async fn login(
+    State(container): State<AppState>,
+    Client(client): Client,
+    Json(request): Json<PasskeyLoginRequest>,
+) -> Result<(StatusCode, Json<Authentication>), AppError> {
+    let assertion = PasskeyAssertionRequest {
+        credential_id: request.credential_id,
+        client_data_json: request.client_data_json,
+        authenticator_data: request.authenticator_data,
+        signature: request.signature,
+    };
+    let authentication = container.finish_passkey_login_command.call(assertion, request.mfa_token, client).await?;
+
+    Ok((authentication_status(&authentication), Json(authentication)))
+}
@@ -0,0 +1,42 @@
+use axum::{
+    Json,
+    extract::State,
+    http::StatusCode,
+};
+use crate::{
+    errors::AppError,
+    app::commands::PasswordWarnings,
+    adapters::http::AppState,
+};
+
+#[derive(serde::Deserialize)]
+pub struct RequestPasswordResetRequest {
+    pub login: String,
+}
+
+#[derive(serde::Deserialize)]
+pub struct ResetPasswordRequest {
+    pub token: String,
+    pub new_password: String,
+}
+
+pub async fn request_reset(
+    State(container): State<AppState>,
+    Json(request): Json<RequestPasswordResetRequest>,
+) -> Result<StatusCode, AppError> {
+    // письмо отправляется в фоне: время ответа не должно зависеть от того, существует ли логин
+    tokio::spawn(async move {
+        let _ = container.request_password_reset_command.call(request.login).await;
+    });
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+pub async fn reset(
+    State(container): State<AppState>,
+    Json(request): Json<ResetPasswordRequest>,
+) -> Result<Json<PasswordWarnings>, AppError> {
+    let warnings = container.reset_password_command.call(request.token, request.new_password).await?;
+
+    Ok(Json(PasswordWarnings { warnings }))
+}
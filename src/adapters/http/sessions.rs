@@ -0,0 +1,158 @@
+use std::net::SocketAddr;
+use axum::{
+    Json,
+    extract::{ConnectInfo, FromRequestParts, Path, State},
+    http::{
+        StatusCode,
+        header::USER_AGENT,
+        request::Parts,
+    },
+};
+use crate::{
+    errors::AppError,
+    app::{
+        ActiveSession,
+        commands::{
+            Authentication,
+            Session,
+            SessionClient,
+        },
+    },
+    adapters::http::{
+        AppState,
+        tokens::AccessClaims,
+    },
+};
+
+#[derive(serde::Deserialize)]
+pub struct AuthenticateUserRequest {
+    pub login: String,
+    pub password: String,
+}
+
+#[derive(serde::Deserialize)]
+pub struct RequestMagicLinkRequest {
+    pub login: String,
+}
+
+#[derive(serde::Deserialize)]
+pub struct RedeemMagicLinkRequest {
+    pub token: String,
+}
+
+#[derive(serde::Deserialize)]
+pub struct RefreshSessionRequest {
+    pub refresh_token: String,
+}
+
+const USER_AGENT_MAX_LENGTH: usize = 512;
+
+pub struct Client(pub SessionClient);
+
+impl<S> FromRequestParts<S> for Client
+where
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let user_agent = parts.headers.get(USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.chars().take(USER_AGENT_MAX_LENGTH).collect());
+        let ip_address = parts.extensions.get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(address)| address.ip().to_string());
+
+        Ok(Self(SessionClient { user_agent, ip_address }))
+    }
+}
+
+pub async fn authenticate(
+    State(container): State<AppState>,
+    Client(client): Client,
+    Json(request): Json<AuthenticateUserRequest>,
+) -> Result<(StatusCode, Json<Authentication>), AppError> {
+    let authentication = container.authenticate_user_command.call(request.login, request.password, client).await?;
+
+    Ok((authentication_status(&authentication), Json(authentication)))
+}
+
+// без второго фактора или со старым паролем сессии ещё нет, клиент должен сделать следующий шаг
+pub fn authentication_status(authentication: &Authentication) -> StatusCode {
+    match authentication {
+        Authentication::Authenticated(_) => StatusCode::CREATED,
+        Authentication::MfaRequired(_) | Authentication::PasswordExpired(_) => StatusCode::ACCEPTED,
+    }
+}
+
+pub async fn request_magic_link(
+    State(container): State<AppState>,
+    Json(request): Json<RequestMagicLinkRequest>,
+) -> Result<StatusCode, AppError> {
+    container.request_magic_link_command.call(request.login).await?;
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+pub async fn redeem_magic_link(
+    State(container): State<AppState>,
+    Client(client): Client,
+    Json(request): Json<RedeemMagicLinkRequest>,
+) -> Result<(StatusCode, Json<Authentication>), AppError> {
+    let authentication = container.redeem_magic_link_command.call(request.token, client).await?;
+
+    Ok((authentication_status(&authentication), Json(authentication)))
+}
+
+pub async fn refresh(
+    State(container): State<AppState>,
+    Client(client): Client,
+    Json(request): Json<RefreshSessionRequest>,
+) -> Result<Json<Session>, AppError> {
+    let session = container.refresh_session_command.call(request.refresh_token, client).await?;
+
+    Ok(Json(session))
+}
+
+pub async fn logout(
+    State(container): State<AppState>,
+    Json(request): Json<RefreshSessionRequest>,
+) -> Result<StatusCode, AppError> {
+    container.destroy_session_command.call(request.refresh_token).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn logout_everywhere(
+    State(container): State<AppState>,
+    AccessClaims(claims): AccessClaims,
+) -> Result<StatusCode, AppError> {
+    let user_id = match uuid::Uuid::parse_str(&claims.sub) {
+        Ok(user_id) => user_id,
+        Err(_) => return Err(AppError::InvalidToken),
+    };
+    container.destroy_all_sessions_command.call(user_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn list(
+    State(container): State<AppState>,
+    Path(user_id): Path<uuid::Uuid>,
+    claims: AccessClaims,
+) -> Result<Json<Vec<ActiveSession>>, AppError> {
+    claims.authorize(user_id)?;
+    let sessions = container.list_sessions_query.call(user_id).await?;
+
+    Ok(Json(sessions))
+}
+
+pub async fn revoke(
+    State(container): State<AppState>,
+    Path((user_id, session_id)): Path<(uuid::Uuid, uuid::Uuid)>,
+    claims: AccessClaims,
+) -> Result<StatusCode, AppError> {
+    claims.authorize(user_id)?;
+    container.revoke_session_command.call(user_id, session_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,65 @@
+use axum::{
+    Json,
+    extract::{FromRequestParts, State},
+    http::{
+        HeaderName,
+        header::{AUTHORIZATION, CACHE_CONTROL},
+        request::Parts,
+    },
+};
+use jsonwebtoken::jwk::JwkSet;
+use crate::{
+    errors::AppError,
+    providers::Claims,
+    adapters::http::AppState,
+};
+
+#[derive(serde::Deserialize)]
+pub struct VerifyAccessTokenRequest {
+    pub access_token: String,
+}
+
+pub struct AccessClaims(pub Claims);
+
+impl AccessClaims {
+    pub fn authorize(&self, user_id: uuid::Uuid) -> Result<(), AppError> {
+        if self.0.sub == user_id.to_string() {
+            Ok(())
+        } else {
+            Err(AppError::AccessDenied)
+        }
+    }
+}
+
+impl FromRequestParts<AppState> for AccessClaims {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, container: &AppState) -> Result<Self, Self::Rejection> {
+        let header = match parts.headers.get(AUTHORIZATION).and_then(|value| value.to_str().ok()) {
+            Some(header) => header,
+            None => return Err(AppError::LoginRequired),
+        };
+        let access_token = match header.strip_prefix("Bearer ") {
+            Some(access_token) => access_token.trim(),
+            None => return Err(AppError::MalformedToken),
+        };
+        let claims = container.verify_access_token_query.call(access_token.to_string())?;
+
+        Ok(Self(claims))
+    }
+}
+
+pub async fn verify(
+    State(container): State<AppState>,
+    Json(request): Json<VerifyAccessTokenRequest>,
+) -> Result<Json<Claims>, AppError> {
+    let claims = container.verify_access_token_query.call(request.access_token)?;
+
+    Ok(Json(claims))
+}
+
+pub async fn jwks(State(container): State<AppState>) -> ([(HeaderName, &'static str); 1], Json<JwkSet>) {
+    let jwks = container.list_public_keys_query.call();
+
+    ([(CACHE_CONTROL, "public, max-age=60")], Json(jwks))
+}
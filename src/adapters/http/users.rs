@@ -0,0 +1,75 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use crate::{
+    errors::AppError,
+    app::commands::PasswordWarnings,
+    adapters::http::{
+        AppState,
+        tokens::AccessClaims,
+    },
+};
+
+#[derive(serde::Deserialize)]
+pub struct RegisterUserRequest {
+    pub login: String,
+    pub password: String,
+}
+
+#[derive(serde::Deserialize)]
+pub struct ChangePasswordRequest {
+    pub old_password: String,
+    pub new_password: String,
+}
+
+#[derive(serde::Deserialize)]
+pub struct ConfirmPasswordRequest {
+    pub password: String,
+}
+
+pub async fn register(
+    State(container): State<AppState>,
+    Json(request): Json<RegisterUserRequest>,
+) -> Result<(StatusCode, Json<PasswordWarnings>), AppError> {
+    let warnings = container.register_user_command.call(request.login, request.password).await?;
+
+    Ok((StatusCode::CREATED, Json(PasswordWarnings { warnings })))
+}
+
+pub async fn change_password(
+    State(container): State<AppState>,
+    Path(user_id): Path<uuid::Uuid>,
+    claims: AccessClaims,
+    Json(request): Json<ChangePasswordRequest>,
+) -> Result<Json<PasswordWarnings>, AppError> {
+    claims.authorize(user_id)?;
+    let warnings = container.change_password_command.call(user_id, request.old_password, request.new_password).await?;
+
+    Ok(Json(PasswordWarnings { warnings }))
+}
+
+pub async fn delete(
+    State(container): State<AppState>,
+    Path(user_id): Path<uuid::Uuid>,
+    claims: AccessClaims,
+    Json(request): Json<ConfirmPasswordRequest>,
+) -> Result<StatusCode, AppError> {
+    claims.authorize(user_id)?;
+    container.delete_user_command.call(user_id, request.password).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn restore(
+    State(container): State<AppState>,
+    Path(user_id): Path<uuid::Uuid>,
+    claims: AccessClaims,
+    Json(request): Json<ConfirmPasswordRequest>,
+) -> Result<StatusCode, AppError> {
+    claims.authorize(user_id)?;
+    container.restore_user_command.call(user_id, request.password).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -1,27 +1,53 @@
-use sqlx::FromRow;
+use sha2::Digest;
 use crate::{
     errors::AppError,
     app::{
         UserCredential,
         UserSecret,
         User,
+        ActiveSession,
+        TotpFactor,
+        UserMfaChallenge,
+        Passkey,
+        WebauthnChallenge,
+        RecoveryCode,
+        MagicLink,
+        BreachedPasswordSuffix,
         queries::{
             FindUserCredentialDao,
             FindUserSecretDao,
             FindUserDao,
+            ListSessionsDao,
         },
         commands::{
             RegisterUserDao,
+            ConfirmCredentialDao,
             AuthenticateUserDao,
             RefreshSessionDao,
+            DestroySessionDao,
             ChangePasswordDao, 
+            PasswordResetDao,
+            TotpDao,
+            MfaChallengeDao,
+            PasskeyDao,
+            RecoveryCodeDao,
+            MagicLinkDao,
+            BreachedPasswordDao,
+            ImportBreachedPasswordsDao,
+            ImportUsersDao,
+            ImportedUser,
             DeleteUserDao,
             RestoreUserDao,
+            SessionPolicy,
+            SessionEvictionPolicy,
+            SessionClient,
             refresh_session::UserSession,
         },
     },
 };
 
+const REFRESH_TOKEN_REUSE_EVENT: &str = "refresh_token_reuse";
+
 #[derive(Clone)]
 pub struct UserRepository {
     pool: sqlx::PgPool,
@@ -33,8 +59,18 @@ impl UserRepository {
     }
 }
 
+// в базе храним только SHA-256 от выданных токенов, сам токен знает лишь клиент
+fn token_digest(token: &str) -> String {
+    format!("{:x}", sha2::Sha256::digest(token.as_bytes()))
+}
+
+// короткий код привязан к учётным данным, иначе его можно было бы предъявить как токен из письма
+fn code_digest(user_credential_id: uuid::Uuid, code: &str) -> String {
+    token_digest(&format!("{user_credential_id}:{code}"))
+}
+
 impl RegisterUserDao for UserRepository {
-    async fn register_user(&self, login_type: String, login: String, password_digest: String) -> Result<(), AppError> {
+    async fn register_user(&self, login_type: String, login: String, password_digest: String, is_confirmed: bool) -> Result<uuid::Uuid, AppError> {
         let mut transaction = match self.pool.begin().await {
             Ok(transaction) => transaction,
             Err(_) => return Err(AppError::UnknownDatabaseError),
@@ -50,13 +86,18 @@ impl RegisterUserDao for UserRepository {
         //     _ => return Err(AppError::UnknownDatabaseError),
         // };
 
-        match sqlx::query("INSERT INTO user_credentials (login, user_id, kind) VALUES ($1, $2, $3)")
+        let user_credential_id: uuid::Uuid = match sqlx::query_scalar(r#"
+                INSERT INTO user_credentials (login, user_id, kind, confirmed_at) 
+                VALUES ($1, $2, $3, CASE WHEN $4 THEN CURRENT_TIMESTAMP END) 
+                RETURNING id
+            "#)
             .bind(login)
             .bind(user.id)
             .bind(login_type)
-            .execute(&mut *transaction)
+            .bind(is_confirmed)
+            .fetch_one(&mut *transaction)
             .await {
-            Ok(_) => {},
+            Ok(user_credential_id) => user_credential_id,
             Err(sqlx::Error::Database(db_err)) => {
                 if let Some(pg_err) = db_err.try_downcast_ref::<sqlx::postgres::PgDatabaseError>() {
                     match pg_err.code() {
@@ -68,7 +109,7 @@ impl RegisterUserDao for UserRepository {
                 }
             },
             Err(_) => return Err(AppError::UnknownDatabaseError),
-        }
+        };
         match sqlx::query("INSERT INTO user_passwords (password_digest, user_id) VALUES ($1, $2)")
             .bind(password_digest)
             .bind(user.id)
@@ -79,10 +120,139 @@ impl RegisterUserDao for UserRepository {
         }
 
         match transaction.commit().await {
+            Ok(_) => Ok(user_credential_id),
+            Err(_) => Err(AppError::UnknownDatabaseError),
+        }
+    }
+}
+
+impl ConfirmCredentialDao for UserRepository {
+    async fn create_credential_confirmation(&self, user_credential_id: uuid::Uuid, token: String, expires_at: chrono::NaiveDateTime) -> Result<(), AppError> {
+        let result_of_insert = sqlx::query("INSERT INTO user_credential_confirmations (token_digest, expires_at, user_credential_id) VALUES ($1, $2, $3)")
+            .bind(token_digest(&token))
+            .bind(expires_at)
+            .bind(user_credential_id)
+            .execute(&self.pool)
+            .await;
+
+        match result_of_insert {
+            Ok(_) => Ok(()),
+            Err(_) => Err(AppError::UnknownDatabaseError),
+        }
+    }
+
+    async fn create_credential_confirmation_code(&self, user_credential_id: uuid::Uuid, code: String, expires_at: chrono::NaiveDateTime) -> Result<(), AppError> {
+        let result_of_insert = sqlx::query("INSERT INTO user_credential_confirmations (token_digest, expires_at, user_credential_id) VALUES ($1, $2, $3)")
+            .bind(code_digest(user_credential_id, &code))
+            .bind(expires_at)
+            .bind(user_credential_id)
+            .execute(&self.pool)
+            .await;
+
+        match result_of_insert {
             Ok(_) => Ok(()),
             Err(_) => Err(AppError::UnknownDatabaseError),
         }
     }
+
+    async fn count_credential_confirmations_since(&self, user_credential_id: uuid::Uuid, since: chrono::NaiveDateTime) -> Result<i64, AppError> {
+        sqlx::query_scalar("SELECT COUNT(1) FROM user_credential_confirmations WHERE user_credential_id = $1 AND created_at > $2")
+            .bind(user_credential_id)
+            .bind(since)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|_| AppError::UnknownDatabaseError)
+    }
+
+    async fn confirm_credential(&self, token: String) -> Result<bool, AppError> {
+        let mut transaction = match self.pool.begin().await {
+            Ok(transaction) => transaction,
+            Err(_) => return Err(AppError::UnknownDatabaseError),
+        };
+
+        let some_user_credential_id_or_none: Option<uuid::Uuid> = sqlx::query_scalar(r#"
+                UPDATE user_credential_confirmations 
+                SET 
+                    used_at = CURRENT_TIMESTAMP 
+                WHERE 
+                    token_digest = $1 AND used_at IS NULL AND expires_at > CURRENT_TIMESTAMP
+                RETURNING user_credential_id
+            "#)
+            .bind(token_digest(&token))
+            .fetch_optional(&mut *transaction)
+            .await
+            .map_err(|_| AppError::UnknownDatabaseError)?;
+
+        let user_credential_id = match some_user_credential_id_or_none {
+            Some(user_credential_id) => user_credential_id,
+            None => return Ok(false),
+        };
+        sqlx::query("UPDATE user_credentials SET confirmed_at = COALESCE(confirmed_at, CURRENT_TIMESTAMP) WHERE id = $1")
+            .bind(user_credential_id)
+            .execute(&mut *transaction)
+            .await
+            .map_err(|_| AppError::UnknownDatabaseError)?;
+        transaction.commit().await.map_err(|_| AppError::UnknownDatabaseError)?;
+
+        Ok(true)
+    }
+
+    async fn confirm_credential_with_code(&self, user_credential_id: uuid::Uuid, code: String, max_attempts: u16) -> Result<bool, AppError> {
+        let mut transaction = match self.pool.begin().await {
+            Ok(transaction) => transaction,
+            Err(_) => return Err(AppError::UnknownDatabaseError),
+        };
+
+        // проверяется только последний выданный код, а попытки считаются по всем живым:
+        // повторная отправка не добавляет догадок
+        let confirmations = sqlx::query_as::<_, (uuid::Uuid, String, i16)>(r#"
+                SELECT 
+                    id, token_digest, attempts
+                FROM 
+                    user_credential_confirmations 
+                WHERE 
+                    user_credential_id = $1 AND used_at IS NULL AND expires_at > CURRENT_TIMESTAMP
+                ORDER BY created_at DESC
+                FOR UPDATE
+            "#)
+            .bind(user_credential_id)
+            .fetch_all(&mut *transaction)
+            .await
+            .map_err(|_| AppError::UnknownDatabaseError)?;
+
+        let attempts: i64 = confirmations.iter().map(|(_, _, attempts)| i64::from(*attempts)).sum();
+        let (confirmation_id, confirmation_digest, _) = match confirmations.into_iter().next() {
+            Some(confirmation) => confirmation,
+            None => return Ok(false),
+        };
+        if attempts >= i64::from(max_attempts) {
+            return Ok(false);
+        }
+        if confirmation_digest != code_digest(user_credential_id, &code) {
+            sqlx::query("UPDATE user_credential_confirmations SET attempts = attempts + 1 WHERE id = $1")
+                .bind(confirmation_id)
+                .execute(&mut *transaction)
+                .await
+                .map_err(|_| AppError::UnknownDatabaseError)?;
+            transaction.commit().await.map_err(|_| AppError::UnknownDatabaseError)?;
+
+            return Ok(false);
+        }
+
+        sqlx::query("UPDATE user_credential_confirmations SET used_at = CURRENT_TIMESTAMP WHERE id = $1")
+            .bind(confirmation_id)
+            .execute(&mut *transaction)
+            .await
+            .map_err(|_| AppError::UnknownDatabaseError)?;
+        sqlx::query("UPDATE user_credentials SET confirmed_at = COALESCE(confirmed_at, CURRENT_TIMESTAMP) WHERE id = $1")
+            .bind(user_credential_id)
+            .execute(&mut *transaction)
+            .await
+            .map_err(|_| AppError::UnknownDatabaseError)?;
+        transaction.commit().await.map_err(|_| AppError::UnknownDatabaseError)?;
+
+        Ok(true)
+    }
 }
 
 impl FindUserCredentialDao for UserRepository {
@@ -117,7 +287,7 @@ impl AuthenticateUserDao for UserRepository {
         }
     }
 
-    async fn create_session(&self, user_credential_id: uuid::Uuid, refresh_token: String) -> Result<(), AppError> {
+    async fn create_session(&self, user_credential_id: uuid::Uuid, refresh_token: String, session_policy: SessionPolicy, client: SessionClient) -> Result<(), AppError> {
         let mut transaction = match self.pool.begin().await {
             Ok(transaction) => transaction,
             Err(_) => return Err(AppError::UnknownDatabaseError),
@@ -126,8 +296,7 @@ impl AuthenticateUserDao for UserRepository {
                 UPDATE user_credentials 
                 SET 
                     login_attempts = 0, 
-                    locked_until = NULL,
-                    confirmed_at = COALESCE(confirmed_at, CURRENT_TIMESTAMP) 
+                    locked_until = NULL
                 WHERE id = $1
             "#)
             .bind(user_credential_id)
@@ -136,16 +305,58 @@ impl AuthenticateUserDao for UserRepository {
             Ok(_) => {},
             Err(_) => return Err(AppError::UnknownDatabaseError),
         };
-        match sqlx::query("UPDATE user_sessions SET disabled_at = CURRENT_TIMESTAMP WHERE user_credential_id = $1 AND disabled_at IS NULL")
-            .bind(user_credential_id)
-            .execute(&mut *transaction)
-            .await {
-            Ok(_) => {},
-            Err(_) => return Err(AppError::UnknownDatabaseError),
-        };
-        match sqlx::query("INSERT INTO user_sessions (refresh_token, user_credential_id) VALUES ($1, $2)")
-            .bind(refresh_token)
+        if session_policy.max_active_sessions > 0 {
+            // блокируем пользователя, чтобы параллельные входы не превысили лимит
+            let active_sessions_count: i64 = sqlx::query_scalar(r#"
+                    WITH locked_user AS (
+                        SELECT u.id FROM users u JOIN user_credentials c ON c.user_id = u.id WHERE c.id = $1 FOR UPDATE OF u
+                    )
+                    SELECT 
+                        COUNT(1) 
+                    FROM 
+                        user_sessions s JOIN user_credentials c ON c.id = s.user_credential_id 
+                    WHERE 
+                        s.disabled_at IS NULL AND c.user_id = (SELECT id FROM locked_user)
+                "#)
+                .bind(user_credential_id)
+                .fetch_one(&mut *transaction)
+                .await
+                .map_err(|_| AppError::UnknownDatabaseError)?;
+
+            let max_active_sessions = i64::from(session_policy.max_active_sessions);
+            if active_sessions_count >= max_active_sessions {
+                if let SessionEvictionPolicy::Reject = session_policy.eviction_policy {
+                    return Err(AppError::TooManySessions);
+                }
+                match sqlx::query(r#"
+                        UPDATE user_sessions 
+                        SET 
+                            disabled_at = CURRENT_TIMESTAMP 
+                        WHERE id IN (
+                            SELECT 
+                                s.id 
+                            FROM 
+                                user_sessions s JOIN user_credentials c ON c.id = s.user_credential_id 
+                            WHERE 
+                                s.disabled_at IS NULL AND c.user_id = (SELECT user_id FROM user_credentials WHERE id = $1)
+                            ORDER BY s.authenticated_at, s.created_at
+                            LIMIT $2
+                        )
+                    "#)
+                    .bind(user_credential_id)
+                    .bind(active_sessions_count - max_active_sessions + 1)
+                    .execute(&mut *transaction)
+                    .await {
+                    Ok(_) => {},
+                    Err(_) => return Err(AppError::UnknownDatabaseError),
+                };
+            }
+        }
+        match sqlx::query("INSERT INTO user_sessions (refresh_token_digest, user_credential_id, user_agent, ip_address) VALUES ($1, $2, $3, $4)")
+            .bind(token_digest(&refresh_token))
             .bind(user_credential_id)
+            .bind(client.user_agent)
+            .bind(client.ip_address)
             .execute(&mut *transaction)
             .await {
             Ok(_) => {},
@@ -160,7 +371,22 @@ impl AuthenticateUserDao for UserRepository {
 }
 
 impl RefreshSessionDao for UserRepository {
-    async fn refresh_session(&self, old_refresh_token: String, new_refresh_token: String) -> Result<Option<UserCredential>, AppError> {
+    async fn find_active_session(&self, refresh_token: String) -> Result<Option<UserSession>, AppError> {
+        sqlx::query_as::<_, UserSession>(r#"
+            SELECT 
+                user_credential_id, family_id, authenticated_at, created_at
+            FROM 
+                user_sessions
+            WHERE 
+                refresh_token_digest = $1 AND disabled_at IS NULL
+            "#)
+            .bind(token_digest(&refresh_token))
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|_| AppError::UnknownDatabaseError)
+    }
+
+    async fn refresh_session(&self, old_refresh_token: String, new_refresh_token: String, client: SessionClient) -> Result<Option<UserCredential>, AppError> {
         let mut transaction = match self.pool.begin().await {
             Ok(transaction) => transaction,
             Err(_) => return Err(AppError::UnknownDatabaseError),
@@ -169,19 +395,54 @@ impl RefreshSessionDao for UserRepository {
         let some_session_or_none = sqlx::query_as::<_, UserSession>(r#"
                 UPDATE user_sessions 
                 SET 
-                    disabled_at = CURRENT_TIMESTAMP 
+                    disabled_at = CURRENT_TIMESTAMP,
+                    rotated_at = CURRENT_TIMESTAMP
                 WHERE 
-                    refresh_token = $1 AND disabled_at IS NULL
-                RETURNING user_credential_id
+                    refresh_token_digest = $1 AND disabled_at IS NULL
+                RETURNING user_credential_id, family_id, authenticated_at, created_at
             "#)
-            .bind(old_refresh_token)
+            .bind(token_digest(&old_refresh_token))
             .fetch_optional(&mut *transaction)
             .await
             .map_err(|_| AppError::UnknownDatabaseError)?;
 
         let session = match some_session_or_none {
             Some(session) => session,
-            None => return Ok(None),
+            None => {
+                // токен уже был обменян: его предъявляет кто-то ещё, отзываем всё семейство
+                let some_rotated_session_or_none = sqlx::query_as::<_, UserSession>(r#"
+                        SELECT 
+                            user_credential_id, family_id, authenticated_at, created_at
+                        FROM 
+                            user_sessions
+                        WHERE 
+                            refresh_token_digest = $1 AND rotated_at IS NOT NULL
+                    "#)
+                    .bind(token_digest(&old_refresh_token))
+                    .fetch_optional(&mut *transaction)
+                    .await
+                    .map_err(|_| AppError::UnknownDatabaseError)?;
+
+                let rotated_session = match some_rotated_session_or_none {
+                    Some(rotated_session) => rotated_session,
+                    None => return Ok(None),
+                };
+                sqlx::query("UPDATE user_sessions SET disabled_at = CURRENT_TIMESTAMP WHERE family_id = $1 AND disabled_at IS NULL")
+                    .bind(rotated_session.family_id)
+                    .execute(&mut *transaction)
+                    .await
+                    .map_err(|_| AppError::UnknownDatabaseError)?;
+                sqlx::query("INSERT INTO security_events (kind, user_credential_id, user_session_family_id) VALUES ($1, $2, $3)")
+                    .bind(REFRESH_TOKEN_REUSE_EVENT)
+                    .bind(rotated_session.user_credential_id)
+                    .bind(rotated_session.family_id)
+                    .execute(&mut *transaction)
+                    .await
+                    .map_err(|_| AppError::UnknownDatabaseError)?;
+                transaction.commit().await.map_err(|_| AppError::UnknownDatabaseError)?;
+
+                return Err(AppError::RefreshTokenReused);
+            },
         };
         // TODO: по сессии определить credential ( + user, если понадобится больше полей в jwt)
         let some_credential_or_none = sqlx::query_as::<_, UserCredential>(r#"
@@ -202,23 +463,110 @@ impl RefreshSessionDao for UserRepository {
             None => return Ok(None),
         };
 
-        let _ = sqlx::query("INSERT INTO user_sessions (refresh_token, user_credential_id) VALUES ($1, $2)")
-            .bind(new_refresh_token)
+        sqlx::query(r#"
+                INSERT INTO user_sessions 
+                    (refresh_token_digest, user_credential_id, family_id, authenticated_at, user_agent, ip_address) 
+                VALUES ($1, $2, $3, $4, $5, $6)
+            "#)
+            .bind(token_digest(&new_refresh_token))
             .bind(session.user_credential_id)
+            .bind(session.family_id)
+            .bind(session.authenticated_at)
+            .bind(client.user_agent)
+            .bind(client.ip_address)
             .execute(&mut *transaction)
             .await
-            .map_err(|_| AppError::UnknownDatabaseError);
-        let _ = transaction.commit().await.map_err(|_| AppError::UnknownDatabaseError);
+            .map_err(|_| AppError::UnknownDatabaseError)?;
+        transaction.commit().await.map_err(|_| AppError::UnknownDatabaseError)?;
 
         Ok(Some(credential))
     }
 }
 
+impl DestroySessionDao for UserRepository {
+    async fn destroy_session(&self, refresh_token: String) -> Result<(), AppError> {
+        let result_of_update = sqlx::query("UPDATE user_sessions SET disabled_at = CURRENT_TIMESTAMP WHERE refresh_token_digest = $1 AND disabled_at IS NULL")
+            .bind(token_digest(&refresh_token))
+            .execute(&self.pool)
+            .await;
+
+        match result_of_update {
+            Ok(_) => Ok(()),
+            Err(_) => Err(AppError::UnknownDatabaseError),
+        }
+    }
+
+    async fn destroy_all_sessions(&self, user_id: uuid::Uuid) -> Result<(), AppError> {
+        let mut connection = match self.pool.acquire().await {
+            Ok(connection) => connection,
+            Err(_) => return Err(AppError::UnknownDatabaseError),
+        };
+
+        disable_all_sessions(&mut connection, user_id).await
+    }
+
+    async fn destroy_session_by_id(&self, user_id: uuid::Uuid, session_id: uuid::Uuid) -> Result<bool, AppError> {
+        let result_of_update = sqlx::query(r#"
+                UPDATE user_sessions
+                SET
+                    disabled_at = CURRENT_TIMESTAMP
+                WHERE
+                    disabled_at IS NULL AND family_id = $2 AND user_credential_id IN (SELECT id FROM user_credentials WHERE user_id = $1)
+            "#)
+            .bind(user_id)
+            .bind(session_id)
+            .execute(&self.pool)
+            .await;
+
+        match result_of_update {
+            Ok(result) => Ok(result.rows_affected() > 0),
+            Err(_) => Err(AppError::UnknownDatabaseError),
+        }
+    }
+}
+
+async fn disable_all_sessions(connection: &mut sqlx::PgConnection, user_id: uuid::Uuid) -> Result<(), AppError> {
+    let result_of_update = sqlx::query(r#"
+            UPDATE user_sessions
+            SET
+                disabled_at = CURRENT_TIMESTAMP
+            WHERE
+                disabled_at IS NULL AND user_credential_id IN (SELECT id FROM user_credentials WHERE user_id = $1)
+        "#)
+        .bind(user_id)
+        .execute(&mut *connection)
+        .await;
+
+    match result_of_update {
+        Ok(_) => Ok(()),
+        Err(_) => Err(AppError::UnknownDatabaseError),
+    }
+}
+
+impl ListSessionsDao for UserRepository {
+    async fn list_active_sessions(&self, user_id: uuid::Uuid) -> Result<Vec<ActiveSession>, AppError> {
+        // сессией для пользователя является семейство токенов, поэтому наружу отдаётся family_id
+        sqlx::query_as::<_, ActiveSession>(r#"
+            SELECT 
+                s.family_id AS id, s.authenticated_at, s.created_at AS refreshed_at, s.user_agent, s.ip_address
+            FROM 
+                user_sessions s JOIN user_credentials c ON c.id = s.user_credential_id
+            WHERE 
+                s.disabled_at IS NULL AND c.user_id = $1
+            ORDER BY s.authenticated_at DESC
+            "#)
+            .bind(user_id)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|_| AppError::UnknownDatabaseError)
+    }
+}
+
 impl FindUserSecretDao for UserRepository {
     async fn find_user_secret_by_user_id(&self, id: uuid::Uuid) -> Result<Option<UserSecret>, AppError> {
         sqlx::query_as::<_, UserSecret>(r#"
             SELECT 
-                id, password_digest, user_id, disabled_at
+                id, password_digest, user_id, disabled_at, changed_at, expires_in
             FROM 
                 user_passwords
             WHERE 
@@ -244,6 +592,646 @@ impl ChangePasswordDao for UserRepository {
             Err(_) => Err(AppError::UnknownDatabaseError),
         }
     }
+
+    async fn list_password_history(&self, user_id: uuid::Uuid, limit: usize) -> Result<Vec<String>, AppError> {
+        sqlx::query_scalar("SELECT password_digest FROM user_password_history WHERE user_id = $1 ORDER BY created_at DESC, id DESC LIMIT $2")
+            .bind(user_id)
+            .bind(i64::try_from(limit).unwrap_or(i64::MAX))
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|_| AppError::UnknownDatabaseError)
+    }
+
+    async fn change_password_digest(&self, user_secret_id: uuid::Uuid, new_password_digest: String, history_size: usize) -> Result<(), AppError> {
+        let mut transaction = match self.pool.begin().await {
+            Ok(transaction) => transaction,
+            Err(_) => return Err(AppError::UnknownDatabaseError),
+        };
+        replace_password_digest(&mut transaction, user_secret_id, new_password_digest, history_size).await?;
+
+        transaction.commit().await.map_err(|_| AppError::UnknownDatabaseError)
+    }
+
+    async fn expire_password(&self, user_id: uuid::Uuid) -> Result<bool, AppError> {
+        let mut transaction = match self.pool.begin().await {
+            Ok(transaction) => transaction,
+            Err(_) => return Err(AppError::UnknownDatabaseError),
+        };
+
+        let result_of_update = sqlx::query("UPDATE user_passwords SET expires_in = CURRENT_TIMESTAMP WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&mut *transaction)
+            .await
+            .map_err(|_| AppError::UnknownDatabaseError)?;
+        if result_of_update.rows_affected() == 0 {
+            return Ok(false);
+        }
+        disable_all_sessions(&mut transaction, user_id).await?;
+        transaction.commit().await.map_err(|_| AppError::UnknownDatabaseError)?;
+
+        Ok(true)
+    }
+
+    async fn list_user_logins(&self, user_id: uuid::Uuid) -> Result<Vec<String>, AppError> {
+        sqlx::query_scalar("SELECT login FROM user_credentials WHERE user_id = $1")
+            .bind(user_id)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|_| AppError::UnknownDatabaseError)
+    }
+}
+
+async fn replace_password_digest(
+    connection: &mut sqlx::PgConnection,
+    user_secret_id: uuid::Uuid,
+    new_password_digest: String,
+    history_size: usize,
+) -> Result<(), AppError> {
+    // текущий пароль тоже входит в history_size, поэтому в истории держим на один меньше
+    let kept_in_history = i64::try_from(history_size.saturating_sub(1)).unwrap_or(i64::MAX);
+    let some_user_id_or_none: Option<uuid::Uuid> = sqlx::query_scalar(r#"
+            INSERT INTO user_password_history (password_digest, user_id)
+            SELECT password_digest, user_id FROM user_passwords WHERE id = $1 AND password_digest IS NOT NULL AND $2 > 0
+            RETURNING user_id
+        "#)
+        .bind(user_secret_id)
+        .bind(kept_in_history)
+        .fetch_optional(&mut *connection)
+        .await
+        .map_err(|_| AppError::UnknownDatabaseError)?;
+    if let Some(user_id) = some_user_id_or_none {
+        sqlx::query(r#"
+                DELETE FROM user_password_history
+                WHERE 
+                    user_id = $1
+                    AND id NOT IN (
+                        SELECT id FROM user_password_history WHERE user_id = $1 ORDER BY created_at DESC, id DESC LIMIT $2
+                    )
+            "#)
+            .bind(user_id)
+            .bind(kept_in_history)
+            .execute(&mut *connection)
+            .await
+            .map_err(|_| AppError::UnknownDatabaseError)?;
+    }
+    // новый пароль снимает и досрочное истечение
+    sqlx::query("UPDATE user_passwords SET password_digest = $1, changed_at = CURRENT_TIMESTAMP, expires_in = NULL WHERE id = $2")
+        .bind(new_password_digest)
+        .bind(user_secret_id)
+        .execute(&mut *connection)
+        .await
+        .map_err(|_| AppError::UnknownDatabaseError)?;
+
+    Ok(())
+}
+
+impl PasswordResetDao for UserRepository {
+    async fn find_confirmed_credential_login(&self, user_id: uuid::Uuid, kind: String) -> Result<Option<String>, AppError> {
+        sqlx::query_scalar(r#"
+            SELECT 
+                login
+            FROM 
+                user_credentials
+            WHERE 
+                user_id = $1 AND kind = $2 AND confirmed_at IS NOT NULL
+            ORDER BY confirmed_at
+            LIMIT 1
+            "#)
+            .bind(user_id)
+            .bind(kind)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|_| AppError::UnknownDatabaseError)
+    }
+
+    async fn create_password_reset(&self, user_id: uuid::Uuid, token: String, expires_at: chrono::NaiveDateTime) -> Result<(), AppError> {
+        let result_of_insert = sqlx::query("INSERT INTO password_resets (token_digest, expires_at, user_id) VALUES ($1, $2, $3)")
+            .bind(token_digest(&token))
+            .bind(expires_at)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await;
+
+        match result_of_insert {
+            Ok(_) => Ok(()),
+            Err(_) => Err(AppError::UnknownDatabaseError),
+        }
+    }
+
+    async fn count_password_resets_since(&self, user_id: uuid::Uuid, since: chrono::NaiveDateTime) -> Result<i64, AppError> {
+        sqlx::query_scalar("SELECT COUNT(1) FROM password_resets WHERE user_id = $1 AND created_at > $2")
+            .bind(user_id)
+            .bind(since)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|_| AppError::UnknownDatabaseError)
+    }
+
+    async fn find_password_reset(&self, token: String) -> Result<Option<uuid::Uuid>, AppError> {
+        sqlx::query_scalar("SELECT user_id FROM password_resets WHERE token_digest = $1 AND used_at IS NULL AND expires_at > CURRENT_TIMESTAMP")
+            .bind(token_digest(&token))
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|_| AppError::UnknownDatabaseError)
+    }
+
+    async fn reset_password(
+        &self,
+        token: String,
+        user_secret_id: uuid::Uuid,
+        new_password_digest: String,
+        history_size: usize,
+    ) -> Result<bool, AppError> {
+        let mut transaction = match self.pool.begin().await {
+            Ok(transaction) => transaction,
+            Err(_) => return Err(AppError::UnknownDatabaseError),
+        };
+
+        let some_user_id_or_none: Option<uuid::Uuid> = sqlx::query_scalar(r#"
+                UPDATE password_resets 
+                SET 
+                    used_at = CURRENT_TIMESTAMP 
+                WHERE 
+                    token_digest = $1 AND used_at IS NULL AND expires_at > CURRENT_TIMESTAMP
+                RETURNING user_id
+            "#)
+            .bind(token_digest(&token))
+            .fetch_optional(&mut *transaction)
+            .await
+            .map_err(|_| AppError::UnknownDatabaseError)?;
+
+        let user_id = match some_user_id_or_none {
+            Some(user_id) => user_id,
+            None => return Ok(false),
+        };
+        // остальные выданные ссылки на сброс тоже гасим
+        sqlx::query("UPDATE password_resets SET used_at = CURRENT_TIMESTAMP WHERE user_id = $1 AND used_at IS NULL")
+            .bind(user_id)
+            .execute(&mut *transaction)
+            .await
+            .map_err(|_| AppError::UnknownDatabaseError)?;
+        replace_password_digest(&mut transaction, user_secret_id, new_password_digest, history_size).await?;
+        disable_all_sessions(&mut transaction, user_id).await?;
+        transaction.commit().await.map_err(|_| AppError::UnknownDatabaseError)?;
+
+        Ok(true)
+    }
+}
+
+impl TotpDao for UserRepository {
+    async fn find_user_login(&self, user_id: uuid::Uuid) -> Result<Option<String>, AppError> {
+        sqlx::query_scalar("SELECT login FROM user_credentials WHERE user_id = $1 ORDER BY id LIMIT 1")
+            .bind(user_id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|_| AppError::UnknownDatabaseError)
+    }
+
+    async fn find_totp_factor(&self, user_id: uuid::Uuid) -> Result<Option<TotpFactor>, AppError> {
+        sqlx::query_as::<_, TotpFactor>("SELECT id, secret, confirmed_at FROM user_totp_factors WHERE user_id = $1")
+            .bind(user_id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|_| AppError::UnknownDatabaseError)
+    }
+
+    async fn create_totp_factor(&self, user_id: uuid::Uuid, secret: String) -> Result<(), AppError> {
+        let mut transaction = match self.pool.begin().await {
+            Ok(transaction) => transaction,
+            Err(_) => return Err(AppError::UnknownDatabaseError),
+        };
+
+        sqlx::query("DELETE FROM user_totp_factors WHERE user_id = $1 AND confirmed_at IS NULL")
+            .bind(user_id)
+            .execute(&mut *transaction)
+            .await
+            .map_err(|_| AppError::UnknownDatabaseError)?;
+        let result_of_insert = sqlx::query("INSERT INTO user_totp_factors (secret, user_id) VALUES ($1, $2) ON CONFLICT (user_id) DO NOTHING")
+            .bind(secret)
+            .bind(user_id)
+            .execute(&mut *transaction)
+            .await
+            .map_err(|_| AppError::UnknownDatabaseError)?;
+        if result_of_insert.rows_affected() == 0 {
+            return Err(AppError::MfaAlreadyEnabled);
+        }
+        transaction.commit().await.map_err(|_| AppError::UnknownDatabaseError)
+    }
+
+    async fn confirm_totp_factor(&self, factor_id: uuid::Uuid, step: u64) -> Result<bool, AppError> {
+        let step = i64::try_from(step).map_err(|_| AppError::UnknownError)?;
+        let result_of_update = sqlx::query(r#"
+                UPDATE user_totp_factors 
+                SET 
+                    confirmed_at = CURRENT_TIMESTAMP, 
+                    last_used_step = $2 
+                WHERE 
+                    id = $1 AND confirmed_at IS NULL
+            "#)
+            .bind(factor_id)
+            .bind(step)
+            .execute(&self.pool)
+            .await;
+
+        match result_of_update {
+            Ok(result) => Ok(result.rows_affected() > 0),
+            Err(_) => Err(AppError::UnknownDatabaseError),
+        }
+    }
+
+    async fn use_totp_step(&self, factor_id: uuid::Uuid, step: u64) -> Result<bool, AppError> {
+        let step = i64::try_from(step).map_err(|_| AppError::UnknownError)?;
+        let result_of_update = sqlx::query(r#"
+                UPDATE user_totp_factors 
+                SET 
+                    last_used_step = $2 
+                WHERE 
+                    id = $1 AND (last_used_step IS NULL OR last_used_step < $2)
+            "#)
+            .bind(factor_id)
+            .bind(step)
+            .execute(&self.pool)
+            .await;
+
+        match result_of_update {
+            Ok(result) => Ok(result.rows_affected() > 0),
+            Err(_) => Err(AppError::UnknownDatabaseError),
+        }
+    }
+}
+
+impl MfaChallengeDao for UserRepository {
+    async fn create_mfa_challenge(&self, user_credential_id: uuid::Uuid, token: String, expires_at: chrono::NaiveDateTime, password_expired: bool) -> Result<(), AppError> {
+        let result_of_insert = sqlx::query("INSERT INTO user_mfa_challenges (token_digest, expires_at, user_credential_id, password_expired) VALUES ($1, $2, $3, $4)")
+            .bind(token_digest(&token))
+            .bind(expires_at)
+            .bind(user_credential_id)
+            .bind(password_expired)
+            .execute(&self.pool)
+            .await;
+
+        match result_of_insert {
+            Ok(_) => Ok(()),
+            Err(_) => Err(AppError::UnknownDatabaseError),
+        }
+    }
+
+    async fn find_mfa_challenge(&self, token: String, max_attempts: u16) -> Result<Option<UserMfaChallenge>, AppError> {
+        let max_attempts = i16::try_from(max_attempts).map_err(|_| AppError::UnknownError)?;
+        sqlx::query_as::<_, UserMfaChallenge>(r#"
+            SELECT 
+                umc.id, umc.user_credential_id, uc.user_id, umc.password_expired
+            FROM 
+                user_mfa_challenges umc
+                JOIN user_credentials uc ON uc.id = umc.user_credential_id
+            WHERE 
+                umc.token_digest = $1 
+                AND umc.used_at IS NULL 
+                AND umc.expires_at > CURRENT_TIMESTAMP 
+                AND umc.attempts < $2
+            "#)
+            .bind(token_digest(&token))
+            .bind(max_attempts)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|_| AppError::UnknownDatabaseError)
+    }
+
+    async fn fail_mfa_challenge(&self, challenge_id: uuid::Uuid) -> Result<(), AppError> {
+        let result_of_update = sqlx::query("UPDATE user_mfa_challenges SET attempts = attempts + 1 WHERE id = $1")
+            .bind(challenge_id)
+            .execute(&self.pool)
+            .await;
+
+        match result_of_update {
+            Ok(_) => Ok(()),
+            Err(_) => Err(AppError::UnknownDatabaseError),
+        }
+    }
+
+    async fn use_mfa_challenge(&self, challenge_id: uuid::Uuid) -> Result<bool, AppError> {
+        let result_of_update = sqlx::query("UPDATE user_mfa_challenges SET used_at = CURRENT_TIMESTAMP WHERE id = $1 AND used_at IS NULL")
+            .bind(challenge_id)
+            .execute(&self.pool)
+            .await;
+
+        match result_of_update {
+            Ok(result) => Ok(result.rows_affected() > 0),
+            Err(_) => Err(AppError::UnknownDatabaseError),
+        }
+    }
+}
+
+impl PasskeyDao for UserRepository {
+    async fn list_passkey_credential_ids(&self, user_id: uuid::Uuid) -> Result<Vec<String>, AppError> {
+        sqlx::query_scalar("SELECT credential_id FROM user_passkeys WHERE user_id = $1 ORDER BY id")
+            .bind(user_id)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|_| AppError::UnknownDatabaseError)
+    }
+
+    async fn create_webauthn_challenge(&self, user_id: Option<uuid::Uuid>, kind: String, challenge: String, expires_at: chrono::NaiveDateTime) -> Result<(), AppError> {
+        let result_of_insert = sqlx::query("INSERT INTO webauthn_challenges (challenge_digest, kind, expires_at, user_id) VALUES ($1, $2, $3, $4)")
+            .bind(token_digest(&challenge))
+            .bind(kind)
+            .bind(expires_at)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await;
+
+        match result_of_insert {
+            Ok(_) => Ok(()),
+            Err(_) => Err(AppError::UnknownDatabaseError),
+        }
+    }
+
+    async fn use_webauthn_challenge(&self, kind: String, challenge: String) -> Result<Option<WebauthnChallenge>, AppError> {
+        sqlx::query_as::<_, WebauthnChallenge>(r#"
+            UPDATE webauthn_challenges 
+            SET 
+                used_at = CURRENT_TIMESTAMP 
+            WHERE 
+                challenge_digest = $1 AND kind = $2 AND used_at IS NULL AND expires_at > CURRENT_TIMESTAMP
+            RETURNING id, user_id
+            "#)
+            .bind(token_digest(&challenge))
+            .bind(kind)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|_| AppError::UnknownDatabaseError)
+    }
+
+    async fn create_passkey(&self, user_id: uuid::Uuid, credential_id: String, public_key: Vec<u8>, sign_count: u32, name: Option<String>) -> Result<bool, AppError> {
+        let result_of_insert = sqlx::query(r#"
+                INSERT INTO user_passkeys (credential_id, public_key, sign_count, name, user_id) 
+                VALUES ($1, $2, $3, $4, $5) 
+                ON CONFLICT (credential_id) DO NOTHING
+            "#)
+            .bind(credential_id)
+            .bind(public_key)
+            .bind(i64::from(sign_count))
+            .bind(name)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await;
+
+        match result_of_insert {
+            Ok(result) => Ok(result.rows_affected() > 0),
+            Err(_) => Err(AppError::UnknownDatabaseError),
+        }
+    }
+
+    // сессия по ключу привязывается к первой подтверждённой учётной записи, а без подтверждённых - к самой ранней (uuidv7 растёт со временем)
+    async fn find_passkey(&self, credential_id: String) -> Result<Option<Passkey>, AppError> {
+        sqlx::query_as::<_, Passkey>(r#"
+            SELECT 
+                up.id, up.user_id, up.public_key, up.sign_count,
+                (
+                    SELECT uc.id FROM user_credentials uc 
+                    WHERE uc.user_id = up.user_id 
+                    ORDER BY uc.confirmed_at IS NULL, uc.confirmed_at, uc.id 
+                    LIMIT 1
+                ) AS user_credential_id
+            FROM 
+                user_passkeys up
+                JOIN users u ON u.id = up.user_id
+            WHERE 
+                up.credential_id = $1 AND u.deleted_at IS NULL
+            "#)
+            .bind(credential_id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|_| AppError::UnknownDatabaseError)
+    }
+
+    async fn update_passkey_sign_count(&self, passkey_id: uuid::Uuid, old_sign_count: i64, new_sign_count: u32) -> Result<bool, AppError> {
+        let result_of_update = sqlx::query(r#"
+                UPDATE user_passkeys 
+                SET 
+                    sign_count = $3, 
+                    last_used_at = CURRENT_TIMESTAMP 
+                WHERE 
+                    id = $1 AND sign_count = $2
+            "#)
+            .bind(passkey_id)
+            .bind(old_sign_count)
+            .bind(i64::from(new_sign_count))
+            .execute(&self.pool)
+            .await;
+
+        match result_of_update {
+            Ok(result) => Ok(result.rows_affected() > 0),
+            Err(_) => Err(AppError::UnknownDatabaseError),
+        }
+    }
+}
+
+impl RecoveryCodeDao for UserRepository {
+    async fn replace_recovery_codes(&self, user_id: uuid::Uuid, code_digests: Vec<String>) -> Result<(), AppError> {
+        let mut transaction = match self.pool.begin().await {
+            Ok(transaction) => transaction,
+            Err(_) => return Err(AppError::UnknownDatabaseError),
+        };
+
+        sqlx::query("DELETE FROM user_recovery_codes WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&mut *transaction)
+            .await
+            .map_err(|_| AppError::UnknownDatabaseError)?;
+        sqlx::query("INSERT INTO user_recovery_codes (code_digest, user_id) SELECT UNNEST($1::VARCHAR[]), $2")
+            .bind(code_digests)
+            .bind(user_id)
+            .execute(&mut *transaction)
+            .await
+            .map_err(|_| AppError::UnknownDatabaseError)?;
+        transaction.commit().await.map_err(|_| AppError::UnknownDatabaseError)
+    }
+
+    async fn list_recovery_codes(&self, user_id: uuid::Uuid) -> Result<Vec<RecoveryCode>, AppError> {
+        sqlx::query_as::<_, RecoveryCode>("SELECT id, code_digest FROM user_recovery_codes WHERE user_id = $1 AND used_at IS NULL ORDER BY id")
+            .bind(user_id)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|_| AppError::UnknownDatabaseError)
+    }
+
+    async fn use_recovery_code(&self, recovery_code_id: uuid::Uuid) -> Result<bool, AppError> {
+        let result_of_update = sqlx::query("UPDATE user_recovery_codes SET used_at = CURRENT_TIMESTAMP WHERE id = $1 AND used_at IS NULL")
+            .bind(recovery_code_id)
+            .execute(&self.pool)
+            .await;
+
+        match result_of_update {
+            Ok(result) => Ok(result.rows_affected() > 0),
+            Err(_) => Err(AppError::UnknownDatabaseError),
+        }
+    }
+}
+
+impl MagicLinkDao for UserRepository {
+    async fn create_magic_link(&self, user_credential_id: uuid::Uuid, token: String, expires_at: chrono::NaiveDateTime) -> Result<(), AppError> {
+        let result_of_insert = sqlx::query("INSERT INTO magic_links (token_digest, expires_at, user_credential_id) VALUES ($1, $2, $3)")
+            .bind(token_digest(&token))
+            .bind(expires_at)
+            .bind(user_credential_id)
+            .execute(&self.pool)
+            .await;
+
+        match result_of_insert {
+            Ok(_) => Ok(()),
+            Err(_) => Err(AppError::UnknownDatabaseError),
+        }
+    }
+
+    async fn use_magic_link(&self, token: String) -> Result<Option<MagicLink>, AppError> {
+        sqlx::query_as::<_, MagicLink>(r#"
+            UPDATE magic_links ml
+            SET 
+                used_at = CURRENT_TIMESTAMP 
+            FROM 
+                user_credentials uc
+                JOIN users u ON u.id = uc.user_id
+            WHERE 
+                uc.id = ml.user_credential_id
+                AND ml.token_digest = $1 
+                AND ml.used_at IS NULL 
+                AND ml.expires_at > CURRENT_TIMESTAMP
+                AND uc.confirmed_at IS NOT NULL
+                AND u.deleted_at IS NULL
+            RETURNING ml.id, ml.user_credential_id, uc.user_id
+            "#)
+            .bind(token_digest(&token))
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|_| AppError::UnknownDatabaseError)
+    }
+}
+
+impl BreachedPasswordDao for UserRepository {
+    async fn find_breached_password_range(&self, prefix: String) -> Result<Vec<BreachedPasswordSuffix>, AppError> {
+        sqlx::query_as::<_, BreachedPasswordSuffix>("SELECT suffix, count FROM breached_passwords WHERE prefix = $1")
+            .bind(prefix)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|_| AppError::UnknownDatabaseError)
+    }
+}
+
+impl ImportBreachedPasswordsDao for UserRepository {
+    async fn replace_breached_password_range(&self, prefix: String, suffixes: Vec<BreachedPasswordSuffix>) -> Result<(), AppError> {
+        let mut transaction = match self.pool.begin().await {
+            Ok(transaction) => transaction,
+            Err(_) => return Err(AppError::UnknownDatabaseError),
+        };
+
+        sqlx::query("DELETE FROM breached_passwords WHERE prefix = $1")
+            .bind(&prefix)
+            .execute(&mut *transaction)
+            .await
+            .map_err(|_| AppError::UnknownDatabaseError)?;
+        let (suffixes, counts): (Vec<String>, Vec<i64>) = suffixes.into_iter().map(|entry| (entry.suffix, entry.count)).unzip();
+        sqlx::query(r#"
+            INSERT INTO breached_passwords (prefix, suffix, count)
+            SELECT $1, suffix, count FROM UNNEST($2::TEXT[], $3::BIGINT[]) AS range(suffix, count)
+            ON CONFLICT (prefix, suffix) DO UPDATE SET count = EXCLUDED.count
+            "#)
+            .bind(&prefix)
+            .bind(suffixes)
+            .bind(counts)
+            .execute(&mut *transaction)
+            .await
+            .map_err(|_| AppError::UnknownDatabaseError)?;
+
+        transaction.commit().await.map_err(|_| AppError::UnknownDatabaseError)
+    }
+}
+
+impl ImportUsersDao for UserRepository {
+    // пачка вставляется одним запросом; строки, чей логин уже занят, пропускаются и не создают пользователя
+    async fn import_users(&self, users: Vec<ImportedUser>, dry_run: bool) -> Result<Vec<Result<(), AppError>>, AppError> {
+        let users_count = users.len();
+        let mut logins = Vec::with_capacity(users_count);
+        let mut kinds = Vec::with_capacity(users_count);
+        let mut confirmed = Vec::with_capacity(users_count);
+        let mut first_names = Vec::with_capacity(users_count);
+        let mut middle_names = Vec::with_capacity(users_count);
+        let mut last_names = Vec::with_capacity(users_count);
+        let mut birthdates = Vec::with_capacity(users_count);
+        let mut genders = Vec::with_capacity(users_count);
+        let mut password_digests = Vec::with_capacity(users_count);
+        for user in users {
+            logins.push(user.login);
+            kinds.push(user.kind);
+            confirmed.push(user.confirmed.unwrap_or(false));
+            first_names.push(user.first_name);
+            middle_names.push(user.middle_name);
+            last_names.push(user.last_name);
+            birthdates.push(user.birthdate);
+            genders.push(user.gender);
+            password_digests.push(user.password_digest);
+        }
+
+        let mut transaction = match self.pool.begin().await {
+            Ok(transaction) => transaction,
+            Err(_) => return Err(AppError::UnknownDatabaseError),
+        };
+
+        // проверка внешних ключей откладывается до конца запроса, поэтому учётная запись вставляется раньше пользователя
+        let imported_positions: Vec<i64> = sqlx::query_scalar(r#"
+                WITH imported AS MATERIALIZED (
+                    SELECT uuidv7() AS user_id, imported.*
+                    FROM UNNEST($1::text[], $2::text[], $3::bool[], $4::text[], $5::text[], $6::text[], $7::date[], $8::text[]::genders[], $9::text[])
+                        WITH ORDINALITY AS imported(login, kind, confirmed, first_name, middle_name, last_name, birthdate, gender, password_digest, position)
+                ),
+                inserted_credentials AS (
+                    INSERT INTO user_credentials (login, user_id, kind, confirmed_at)
+                    SELECT login, user_id, kind, CASE WHEN confirmed THEN CURRENT_TIMESTAMP END FROM imported
+                    ON CONFLICT ((LOWER(TRIM(login)))) DO NOTHING
+                    RETURNING user_id
+                ),
+                inserted_users AS (
+                    INSERT INTO users (id, first_name, middle_name, last_name, birthdate, gender)
+                    SELECT user_id, first_name, middle_name, last_name, birthdate, gender FROM imported
+                    WHERE user_id IN (SELECT user_id FROM inserted_credentials)
+                ),
+                inserted_passwords AS (
+                    INSERT INTO user_passwords (password_digest, user_id)
+                    SELECT password_digest, user_id FROM imported
+                    WHERE user_id IN (SELECT user_id FROM inserted_credentials)
+                )
+                SELECT position FROM imported WHERE user_id IN (SELECT user_id FROM inserted_credentials)
+            "#)
+            .bind(logins)
+            .bind(kinds)
+            .bind(confirmed)
+            .bind(first_names)
+            .bind(middle_names)
+            .bind(last_names)
+            .bind(birthdates)
+            .bind(genders)
+            .bind(password_digests)
+            .fetch_all(&mut *transaction)
+            .await
+            .map_err(|_| AppError::UnknownDatabaseError)?;
+
+        let result_of_end = if dry_run { transaction.rollback().await } else { transaction.commit().await };
+        if result_of_end.is_err() {
+            return Err(AppError::UnknownDatabaseError);
+        }
+
+        // позиции в UNNEST ... WITH ORDINALITY начинаются с единицы
+        let mut results: Vec<Result<(), AppError>> = (0..users_count).map(|_| Err(AppError::UsernameIsTaken)).collect();
+        for position in imported_positions {
+            let index = usize::try_from(position - 1).map_err(|_| AppError::UnknownDatabaseError)?;
+            match results.get_mut(index) {
+                Some(result) => *result = Ok(()),
+                None => return Err(AppError::UnknownDatabaseError),
+            }
+        }
+
+        Ok(results)
+    }
 }
 
 impl DeleteUserDao for UserRepository {
@@ -19,6 +19,68 @@ pub struct UserSecret {
     #[sqlx(try_from = "uuid::Uuid")]
     pub user_id: String,
     pub password_digest: String,
+    pub changed_at: chrono::NaiveDateTime,
+    // досрочное истечение, назначенное администратором
+    pub expires_in: Option<chrono::NaiveDateTime>,
+}
+
+#[derive(sqlx::FromRow, serde::Serialize)]
+pub struct ActiveSession {
+    pub id: uuid::Uuid,
+    pub authenticated_at: chrono::NaiveDateTime,
+    pub refreshed_at: chrono::NaiveDateTime,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+}
+
+#[derive(sqlx::FromRow)]
+pub struct TotpFactor {
+    pub id: uuid::Uuid,
+    pub secret: String,
+    pub confirmed_at: Option<chrono::NaiveDateTime>,
+}
+
+#[derive(sqlx::FromRow)]
+pub struct UserMfaChallenge {
+    pub id: uuid::Uuid,
+    pub user_credential_id: uuid::Uuid,
+    pub user_id: uuid::Uuid,
+    pub password_expired: bool,
+}
+
+#[derive(sqlx::FromRow)]
+pub struct Passkey {
+    pub id: uuid::Uuid,
+    pub user_id: uuid::Uuid,
+    // беспарольный вход оформляем на основной логин пользователя
+    pub user_credential_id: uuid::Uuid,
+    pub public_key: Vec<u8>,
+    pub sign_count: i64,
+}
+
+#[derive(sqlx::FromRow)]
+pub struct WebauthnChallenge {
+    pub id: uuid::Uuid,
+    pub user_id: Option<uuid::Uuid>,
+}
+
+#[derive(sqlx::FromRow)]
+pub struct RecoveryCode {
+    pub id: uuid::Uuid,
+    pub code_digest: String,
+}
+
+#[derive(sqlx::FromRow)]
+pub struct MagicLink {
+    pub id: uuid::Uuid,
+    pub user_credential_id: uuid::Uuid,
+    pub user_id: uuid::Uuid,
+}
+
+#[derive(sqlx::FromRow)]
+pub struct BreachedPasswordSuffix {
+    pub suffix: String,
+    pub count: i64,
 }
 
 #[derive(sqlx::FromRow)]
@@ -1,5 +1,16 @@
+use validator::ValidateEmail;
 use crate::errors::AppError;
-use crate::app::UserCredential;
+use crate::app::{
+    UserCredential,
+    TotpFactor,
+    UserMfaChallenge,
+    Passkey,
+    WebauthnChallenge,
+    RecoveryCode,
+    MagicLink,
+    BreachedPasswordSuffix,
+};
+use crate::app::commands::refresh_session::UserSession;
 
 pub mod register_user;
 pub mod authenticate_user;
@@ -8,36 +19,324 @@ pub mod change_password;
 pub mod delete_user;
 pub mod restore_user;
 pub mod destroy_session;
+pub mod destroy_all_sessions;
+pub mod revoke_session;
+pub mod confirm_credential;
+pub mod request_credential_confirmation;
+pub mod confirm_phone;
+pub mod request_password_reset;
+pub mod reset_password;
+pub mod enroll_totp;
+pub mod confirm_totp;
+pub mod verify_totp_challenge;
+pub mod start_passkey_registration;
+pub mod finish_passkey_registration;
+pub mod start_passkey_login;
+pub mod finish_passkey_login;
+pub mod regenerate_recovery_codes;
+pub mod verify_recovery_code_challenge;
+pub mod request_magic_link;
+pub mod password_policy;
+pub mod import_breached_passwords;
+pub mod import_users;
+pub mod expire_password;
+pub mod redeem_magic_link;
+pub mod reload_signing_keys;
+pub mod promote_signing_key;
 
 pub const LOGIN_ATTEMPTS_BEFORE_FIRST_LOCKING: u16 = 5;
 pub const LOGIN_ATTEMPTS_AFTER_FIRST_LOCKING: u16 = 3;
 pub const LOCKING_IN_MINUTES: i64 = 3;
+pub const CREDENTIAL_CONFIRMATION_TTL_IN_HOURS: i64 = 24;
+pub const PHONE_CONFIRMATION_TTL_IN_MINUTES: i64 = 10;
+pub const PHONE_CONFIRMATION_MAX_ATTEMPTS: u16 = 5;
+pub const CREDENTIAL_CONFIRMATION_RESEND_COOLDOWN_IN_SECONDS: i64 = 60;
+pub const CREDENTIAL_CONFIRMATIONS_PER_HOUR: i64 = 5;
+pub const PASSWORD_RESET_TTL_IN_MINUTES: i64 = 30;
+pub const PASSWORD_RESET_RESEND_COOLDOWN_IN_SECONDS: i64 = 60;
+pub const PASSWORD_RESETS_PER_HOUR: i64 = 5;
+pub const PASSWORD_CHANGE_TOKEN_TTL_IN_MINUTES: i64 = 10;
+pub const MAGIC_LINK_TTL_IN_MINUTES: i64 = 15;
+pub const MFA_CHALLENGE_TTL_IN_MINUTES: i64 = 5;
+pub const MFA_CHALLENGE_MAX_ATTEMPTS: u16 = 5;
+pub const WEBAUTHN_CHALLENGE_TTL_IN_MINUTES: i64 = 5;
+pub const RECOVERY_CODES_COUNT: usize = 10;
 
+pub const USERNAME_CREDENTIAL_KIND: &str = "username";
+pub const EMAIL_CREDENTIAL_KIND: &str = "email";
+pub const PHONE_CREDENTIAL_KIND: &str = "phone";
+
+pub const TOTP_MFA_METHOD: &str = "totp";
+pub const WEBAUTHN_MFA_METHOD: &str = "webauthn";
+pub const RECOVERY_CODE_MFA_METHOD: &str = "recovery_code";
+
+pub const PASSKEY_REGISTRATION_CHALLENGE_KIND: &str = "registration";
+pub const PASSKEY_LOGIN_CHALLENGE_KIND: &str = "login";
+
+// вид учётных данных и логин в каноническом виде: телефон в E.164, почта и имя в нижнем регистре
+pub fn normalize_login(login: &str) -> Option<(&'static str, String)> {
+    let login = login.trim().to_lowercase();
+    if login.starts_with('+') {
+        let phone: String = login.chars().filter(|c| !matches!(c, ' ' | '-' | '(' | ')' | '.')).collect();
+        let digits = &phone[1..];
+        let is_e164 = (7..=15).contains(&digits.len()) && !digits.starts_with('0') && digits.chars().all(|c| c.is_ascii_digit());
+        return if is_e164 { Some((PHONE_CREDENTIAL_KIND, phone)) } else { None };
+    }
+    if login.contains('@') {
+        return if login.validate_email() { Some((EMAIL_CREDENTIAL_KIND, login)) } else { None };
+    }
+
+    Some((USERNAME_CREDENTIAL_KIND, login))
+}
+
+#[derive(Clone, Copy, Debug, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SessionEvictionPolicy {
+    OldestFirst,
+    Reject,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct SessionPolicy {
+    // 0 - без ограничений
+    pub max_active_sessions: u32,
+    pub eviction_policy: SessionEvictionPolicy,
+    // 0 - сессия не истекает
+    pub idle_timeout_in_days: u32,
+    pub absolute_lifetime_in_days: u32,
+}
+
+impl Default for SessionPolicy {
+    fn default() -> Self {
+        Self {
+            max_active_sessions: 0,
+            eviction_policy: SessionEvictionPolicy::OldestFirst,
+            idle_timeout_in_days: 0,
+            absolute_lifetime_in_days: 0,
+        }
+    }
+}
+
+// откуда пришёл клиент, показывается в списке сессий
+#[derive(Clone, Debug, Default)]
+pub struct SessionClient {
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+}
+
+#[derive(serde::Serialize)]
 pub struct Session {
     pub user_id: uuid::Uuid,
     pub access_token: String,
     pub refresh_token: String,
 }
 
+// второй шаг входа: токен обменивается на сессию вместе с кодом второго фактора
+#[derive(serde::Serialize)]
+pub struct MfaChallenge {
+    pub mfa_token: String,
+    pub methods: Vec<&'static str>,
+}
+
+// пароль истёк: вместо сессии выдаётся токен, которым можно только сменить пароль через /passwords/reset
+#[derive(serde::Serialize)]
+pub struct PasswordChangeRequired {
+    pub password_change_token: String,
+}
+
+#[derive(serde::Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum Authentication {
+    Authenticated(Session),
+    MfaRequired(MfaChallenge),
+    PasswordExpired(PasswordChangeRequired),
+}
+
+// ответ на принятый новый пароль
+#[derive(serde::Serialize)]
+pub struct PasswordWarnings {
+    pub warnings: Vec<password_policy::PasswordWarning>,
+}
+
+// повторное подтверждение личности перед действием, которое нельзя доверить одному токену доступа
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Reauthentication {
+    Password(String),
+    TotpCode(String),
+}
+
+// показываются один раз, в базе остаются только хеши
+#[derive(serde::Serialize)]
+pub struct RecoveryCodes {
+    pub recovery_codes: Vec<String>,
+}
+
+#[derive(serde::Serialize)]
+pub struct TotpEnrollment {
+    pub secret: String,
+    pub uri: String,
+}
+
+// параметры для navigator.credentials.create() и get() в формате WebAuthn
+#[derive(serde::Serialize)]
+pub struct PasskeyDescriptor {
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub id: String,
+}
+
+#[derive(serde::Serialize)]
+pub struct PasskeyRelyingParty {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PasskeyUser {
+    pub id: String,
+    pub name: String,
+    pub display_name: String,
+}
+
+#[derive(serde::Serialize)]
+pub struct PasskeyAlgorithm {
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub alg: i64,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PasskeyCreationOptions {
+    pub challenge: String,
+    pub rp: PasskeyRelyingParty,
+    pub user: PasskeyUser,
+    pub pub_key_cred_params: Vec<PasskeyAlgorithm>,
+    pub timeout: i64,
+    pub exclude_credentials: Vec<PasskeyDescriptor>,
+    pub attestation: &'static str,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PasskeyRequestOptions {
+    pub challenge: String,
+    pub rp_id: String,
+    pub timeout: i64,
+    pub allow_credentials: Vec<PasskeyDescriptor>,
+    pub user_verification: &'static str,
+}
+
+// строка выгрузки из другой системы: пароль переносится готовым дайджестом, профиль - как есть
+#[derive(Clone, Debug, serde::Deserialize)]
+pub struct ImportedUser {
+    pub login: String,
+    pub kind: Option<String>,
+    // пустая ячейка CSV - то же, что отсутствующее поле: не подтверждён
+    pub confirmed: Option<bool>,
+    pub first_name: Option<String>,
+    pub middle_name: Option<String>,
+    pub last_name: Option<String>,
+    pub birthdate: Option<chrono::NaiveDate>,
+    pub gender: Option<String>,
+    pub password_digest: String,
+}
+
 pub trait RegisterUserDao {
-    fn register_user(&self, login_type: String, login: String, password_digest: String) -> impl std::future::Future<Output = Result<(), AppError>> + Send;
+    fn register_user(&self, login_type: String, login: String, password_digest: String, is_confirmed: bool) -> impl std::future::Future<Output = Result<uuid::Uuid, AppError>> + Send;
+}
+
+pub trait ConfirmCredentialDao {
+    fn create_credential_confirmation(&self, user_credential_id: uuid::Uuid, token: String, expires_at: chrono::NaiveDateTime) -> impl std::future::Future<Output = Result<(), AppError>> + Send;
+    fn create_credential_confirmation_code(&self, user_credential_id: uuid::Uuid, code: String, expires_at: chrono::NaiveDateTime) -> impl std::future::Future<Output = Result<(), AppError>> + Send;
+    fn count_credential_confirmations_since(&self, user_credential_id: uuid::Uuid, since: chrono::NaiveDateTime) -> impl std::future::Future<Output = Result<i64, AppError>> + Send;
+    fn confirm_credential(&self, token: String) -> impl std::future::Future<Output = Result<bool, AppError>> + Send;
+    fn confirm_credential_with_code(&self, user_credential_id: uuid::Uuid, code: String, max_attempts: u16) -> impl std::future::Future<Output = Result<bool, AppError>> + Send;
 }
 
 pub trait AuthenticateUserDao {
     fn update_failure_login(&self, id: uuid::Uuid, actual_failure_login_attempts: u16, locked_until: Option<chrono::NaiveDateTime>) -> impl std::future::Future<Output = Result<(), AppError>> + Send;
-    fn create_session(&self, user_credential_id: uuid::Uuid, refresh_token: String) -> impl std::future::Future<Output = Result<(), AppError>> + Send;
+    fn create_session(&self, user_credential_id: uuid::Uuid, refresh_token: String, session_policy: SessionPolicy, client: SessionClient) -> impl std::future::Future<Output = Result<(), AppError>> + Send;
 }
 
 pub trait RefreshSessionDao {
-    fn refresh_session(&self, old_refresh_token: String, new_refresh_token: String) -> impl std::future::Future<Output = Result<Option<UserCredential>, AppError>> + Send;
+    fn find_active_session(&self, refresh_token: String) -> impl std::future::Future<Output = Result<Option<UserSession>, AppError>> + Send;
+    fn refresh_session(&self, old_refresh_token: String, new_refresh_token: String, client: SessionClient) -> impl std::future::Future<Output = Result<Option<UserCredential>, AppError>> + Send;
 }
 
 pub trait DestroySessionDao {
     fn destroy_session(&self, refresh_token: String) -> impl std::future::Future<Output = Result<(), AppError>> + Send;
+    fn destroy_all_sessions(&self, user_id: uuid::Uuid) -> impl std::future::Future<Output = Result<(), AppError>> + Send;
+    fn destroy_session_by_id(&self, user_id: uuid::Uuid, session_id: uuid::Uuid) -> impl std::future::Future<Output = Result<bool, AppError>> + Send;
 }
 
 pub trait ChangePasswordDao {
     fn upgrade_password_digest(&self, user_secret_id: uuid::Uuid, new_password_digest: String) -> impl std::future::Future<Output = Result<(), AppError>> + Send;
+    fn list_user_logins(&self, user_id: uuid::Uuid) -> impl std::future::Future<Output = Result<Vec<String>, AppError>> + Send;
+    fn list_password_history(&self, user_id: uuid::Uuid, limit: usize) -> impl std::future::Future<Output = Result<Vec<String>, AppError>> + Send;
+    // в отличие от upgrade_password_digest, прежний пароль уходит в историю
+    fn change_password_digest(&self, user_secret_id: uuid::Uuid, new_password_digest: String, history_size: usize) -> impl std::future::Future<Output = Result<(), AppError>> + Send;
+    // вместе с истечением отзываются сессии, иначе они продолжат обновляться со старым паролем
+    fn expire_password(&self, user_id: uuid::Uuid) -> impl std::future::Future<Output = Result<bool, AppError>> + Send;
+}
+
+pub trait PasswordResetDao {
+    fn find_confirmed_credential_login(&self, user_id: uuid::Uuid, kind: String) -> impl std::future::Future<Output = Result<Option<String>, AppError>> + Send;
+    fn create_password_reset(&self, user_id: uuid::Uuid, token: String, expires_at: chrono::NaiveDateTime) -> impl std::future::Future<Output = Result<(), AppError>> + Send;
+    fn count_password_resets_since(&self, user_id: uuid::Uuid, since: chrono::NaiveDateTime) -> impl std::future::Future<Output = Result<i64, AppError>> + Send;
+    fn find_password_reset(&self, token: String) -> impl std::future::Future<Output = Result<Option<uuid::Uuid>, AppError>> + Send;
+    // ссылка гасится, пароль меняется и сессии отзываются вместе, иначе сбой посередине оставит старые сессии
+    fn reset_password(&self, token: String, user_secret_id: uuid::Uuid, new_password_digest: String, history_size: usize) -> impl std::future::Future<Output = Result<bool, AppError>> + Send;
+}
+
+pub trait TotpDao {
+    fn find_user_login(&self, user_id: uuid::Uuid) -> impl std::future::Future<Output = Result<Option<String>, AppError>> + Send;
+    fn find_totp_factor(&self, user_id: uuid::Uuid) -> impl std::future::Future<Output = Result<Option<TotpFactor>, AppError>> + Send;
+    fn create_totp_factor(&self, user_id: uuid::Uuid, secret: String) -> impl std::future::Future<Output = Result<(), AppError>> + Send;
+    fn confirm_totp_factor(&self, factor_id: uuid::Uuid, step: u64) -> impl std::future::Future<Output = Result<bool, AppError>> + Send;
+    fn use_totp_step(&self, factor_id: uuid::Uuid, step: u64) -> impl std::future::Future<Output = Result<bool, AppError>> + Send;
+}
+
+pub trait MfaChallengeDao {
+    fn create_mfa_challenge(&self, user_credential_id: uuid::Uuid, token: String, expires_at: chrono::NaiveDateTime, password_expired: bool) -> impl std::future::Future<Output = Result<(), AppError>> + Send;
+    fn find_mfa_challenge(&self, token: String, max_attempts: u16) -> impl std::future::Future<Output = Result<Option<UserMfaChallenge>, AppError>> + Send;
+    fn fail_mfa_challenge(&self, challenge_id: uuid::Uuid) -> impl std::future::Future<Output = Result<(), AppError>> + Send;
+    fn use_mfa_challenge(&self, challenge_id: uuid::Uuid) -> impl std::future::Future<Output = Result<bool, AppError>> + Send;
+}
+
+pub trait PasskeyDao {
+    fn list_passkey_credential_ids(&self, user_id: uuid::Uuid) -> impl std::future::Future<Output = Result<Vec<String>, AppError>> + Send;
+    fn create_webauthn_challenge(&self, user_id: Option<uuid::Uuid>, kind: String, challenge: String, expires_at: chrono::NaiveDateTime) -> impl std::future::Future<Output = Result<(), AppError>> + Send;
+    fn use_webauthn_challenge(&self, kind: String, challenge: String) -> impl std::future::Future<Output = Result<Option<WebauthnChallenge>, AppError>> + Send;
+    fn create_passkey(&self, user_id: uuid::Uuid, credential_id: String, public_key: Vec<u8>, sign_count: u32, name: Option<String>) -> impl std::future::Future<Output = Result<bool, AppError>> + Send;
+    fn find_passkey(&self, credential_id: String) -> impl std::future::Future<Output = Result<Option<Passkey>, AppError>> + Send;
+    fn update_passkey_sign_count(&self, passkey_id: uuid::Uuid, old_sign_count: i64, new_sign_count: u32) -> impl std::future::Future<Output = Result<bool, AppError>> + Send;
+}
+
+pub trait RecoveryCodeDao {
+    fn replace_recovery_codes(&self, user_id: uuid::Uuid, code_digests: Vec<String>) -> impl std::future::Future<Output = Result<(), AppError>> + Send;
+    fn list_recovery_codes(&self, user_id: uuid::Uuid) -> impl std::future::Future<Output = Result<Vec<RecoveryCode>, AppError>> + Send;
+    fn use_recovery_code(&self, recovery_code_id: uuid::Uuid) -> impl std::future::Future<Output = Result<bool, AppError>> + Send;
+}
+
+pub trait MagicLinkDao {
+    fn create_magic_link(&self, user_credential_id: uuid::Uuid, token: String, expires_at: chrono::NaiveDateTime) -> impl std::future::Future<Output = Result<(), AppError>> + Send;
+    fn use_magic_link(&self, token: String) -> impl std::future::Future<Output = Result<Option<MagicLink>, AppError>> + Send;
+}
+
+pub trait BreachedPasswordDao {
+    fn find_breached_password_range(&self, prefix: String) -> impl std::future::Future<Output = Result<Vec<BreachedPasswordSuffix>, AppError>> + Send;
+}
+
+pub trait ImportBreachedPasswordsDao {
+    fn replace_breached_password_range(&self, prefix: String, suffixes: Vec<BreachedPasswordSuffix>) -> impl std::future::Future<Output = Result<(), AppError>> + Send;
+}
+
+pub trait ImportUsersDao {
+    // результат по каждой строке в исходном порядке; в пробном режиме изменения откатываются
+    fn import_users(&self, users: Vec<ImportedUser>, dry_run: bool) -> impl std::future::Future<Output = Result<Vec<Result<(), AppError>>, AppError>> + Send;
 }
 
 pub trait DeleteUserDao {
@@ -48,3 +347,48 @@ pub trait RestoreUserDao {
     fn restore_user_by_id(&self, user_id: uuid::Uuid) -> impl std::future::Future<Output = Result<(), AppError>> + Send;
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_phone_login() {
+        // Given
+        let login = " +1 (555) 010-9999 ";
+
+        // When
+        let normalized = normalize_login(login);
+
+        // Then
+        assert_eq!(normalized, Some((PHONE_CREDENTIAL_KIND, "+15550109999".to_owned())));
+    }
+
+    #[test]
+    fn normalize_invalid_phone_login() {
+        // Given
+        let logins = ["+0 555 010 9999", "+1 555 CALL NOW", "+123", "+1234567890123456"];
+
+        // When
+        let normalized: Vec<_> = logins.iter().map(|login| normalize_login(login)).collect();
+
+        // Then
+        assert!(normalized.iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn normalize_email_and_username_logins() {
+        // Given
+        let email = " User0@Example.com";
+        let username = "User0 ";
+
+        // When
+        let normalized_email = normalize_login(email);
+        let normalized_username = normalize_login(username);
+
+        // Then
+        assert_eq!(normalized_email, Some((EMAIL_CREDENTIAL_KIND, "user0@example.com".to_owned())));
+        assert_eq!(normalized_username, Some((USERNAME_CREDENTIAL_KIND, "user0".to_owned())));
+        assert_eq!(normalize_login("user0@"), None);
+    }
+}
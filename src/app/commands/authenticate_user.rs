@@ -7,6 +7,7 @@ use crate::{
         TokenEncoderProvider,
     },
     app::{
+        UserMfaChallenge,
         queries::{
             FindUserCredentialDao,
             FindUserSecretDao,
@@ -15,9 +16,26 @@ use crate::{
             LOGIN_ATTEMPTS_BEFORE_FIRST_LOCKING,
             LOGIN_ATTEMPTS_AFTER_FIRST_LOCKING,
             LOCKING_IN_MINUTES,
+            MFA_CHALLENGE_TTL_IN_MINUTES,
+            PASSWORD_CHANGE_TOKEN_TTL_IN_MINUTES,
+            TOTP_MFA_METHOD,
+            WEBAUTHN_MFA_METHOD,
+            RECOVERY_CODE_MFA_METHOD,
+            Authentication,
+            MfaChallenge,
+            PasswordChangeRequired,
             Session,
+            SessionPolicy,
+            SessionClient,
             AuthenticateUserDao,
             ChangePasswordDao,
+            PasswordResetDao,
+            TotpDao,
+            MfaChallengeDao,
+            PasskeyDao,
+            RecoveryCodeDao,
+            normalize_login,
+            password_policy::PasswordPolicy,
         },
     },
 };
@@ -28,13 +46,15 @@ where
     V: HashVerifierProvider,
     I: IdProvider,
     T: TokenEncoderProvider,
-    A: FindUserCredentialDao + FindUserSecretDao + AuthenticateUserDao + ChangePasswordDao,
+    A: FindUserCredentialDao + FindUserSecretDao + AuthenticateUserDao + ChangePasswordDao + PasswordResetDao + TotpDao + MfaChallengeDao + PasskeyDao + RecoveryCodeDao,
 {
     hash_func_provider: H,
     hash_verifier_provider: V,
     refresh_token_generator: I,
     access_token_provider: T,
     repo: A,
+    session_policy: SessionPolicy,
+    password_policy: PasswordPolicy,
 }
 
 impl<H, V, I, T, A> AuthenticateUserCommand<H, V, I, T, A>
@@ -43,29 +63,32 @@ where
     V: HashVerifierProvider,
     I: IdProvider,
     T: TokenEncoderProvider,
-    A: FindUserCredentialDao + FindUserSecretDao + AuthenticateUserDao + ChangePasswordDao,
+    A: FindUserCredentialDao + FindUserSecretDao + AuthenticateUserDao + ChangePasswordDao + PasswordResetDao + TotpDao + MfaChallengeDao + PasskeyDao + RecoveryCodeDao,
 {
-    pub fn new(hash_func_provider: H, hash_verifier_provider: V, refresh_token_generator: I, access_token_provider: T, repo: A) -> Self {
+    pub fn new(hash_func_provider: H, hash_verifier_provider: V, refresh_token_generator: I, access_token_provider: T, repo: A, session_policy: SessionPolicy, password_policy: PasswordPolicy) -> Self {
         Self {
             hash_func_provider,
             hash_verifier_provider,
             refresh_token_generator,
             access_token_provider,
             repo,
+            session_policy,
+            password_policy,
         }
     }
 
-    pub async fn call(&self, login: String, password: String) -> Result<Session, AppError> {
-        // let credentail = match self.repo.find_user_credential_by_login(login.trim().to_lowercase()).await {
-        //     Ok(some_or_none) => match some_or_none {
-        //         Some(credentail) => credentail,
-        //         None => return Err(AppError::LoginError),
-        //     },
-        //     Err(_) => {
-        //         return Err(AppError::UnknownDatabaseError);
-        //     },
-        // };
-
+    pub async fn call(&self, login: String, password: String, client: SessionClient) -> Result<Authentication, AppError> {
+        // логины, заведённые до проверки формата, ищем как есть
+        let login = normalize_login(&login).map_or_else(|| login.trim().to_lowercase(), |(_, login)| login);
+        let credentail = match self.repo.find_user_credential_by_login(login).await {
+            Ok(some_or_none) => match some_or_none {
+                Some(credentail) => credentail,
+                None => return Err(AppError::LoginError),
+            },
+            Err(_) => {
+                return Err(AppError::UnknownDatabaseError);
+            },
+        };
 
         let is_locked = match credentail.locked_until {
             Some(locked_until) => locked_until > chrono::Utc::now().naive_local(),
@@ -74,16 +97,13 @@ where
 
         if is_locked { return Err(AppError::TempLocked) };
 
-        println!("11111111111");
-        // let secret = match self.repo.find_user_secret_by_user_id(credentail.user_id).await {
-        //     Ok(some_or_none) => match some_or_none {
-        //         Some(secret) => secret,
-        //         None => return Err(AppError::LoginError),
-        //     },
-        //     Err(_) => return Err(AppError::UnknownDatabaseError),
-        // };
-        let secret = self.repo.find_user_secret_by_user_id(credentail.user_id).await?;
-        println!("22222222222");
+        let secret = match self.repo.find_user_secret_by_user_id(credentail.user_id).await {
+            Ok(some_or_none) => match some_or_none {
+                Some(secret) => secret,
+                None => return Err(AppError::LoginError),
+            },
+            Err(_) => return Err(AppError::UnknownDatabaseError),
+        };
 
         let password_confirmation = self.hash_verifier_provider.provide(password.clone(), secret.password_digest);
         let is_password_correct = password_confirmation.is_confirmed;
@@ -107,6 +127,8 @@ where
             return Err(AppError::LoginError);
         }
 
+        if credentail.confirmed_at.is_none() { return Err(AppError::UnconfirmedCredential) };
+
         if password_confirmation.need_upgrade {
             let password_digest = match self.hash_func_provider.provide(password) {
                 Some(hash) => hash,
@@ -118,86 +140,251 @@ where
             self.repo.upgrade_password_digest(secret.id, password_digest).await?;
         }
 
-        let refresh_token = match self.refresh_token_generator.provide() {
+        let is_password_expired = secret.expires_in.is_some_and(|expires_in| expires_in <= chrono::Utc::now().naive_local())
+            || self.password_policy.is_password_expired(secret.changed_at);
+
+        complete_authentication(
+            &self.refresh_token_generator,
+            &self.access_token_provider,
+            &self.repo,
+            credentail.id,
+            credentail.user_id,
+            is_password_expired,
+            self.session_policy,
+            client,
+        ).await
+    }
+}
+
+// первый фактор пройден: либо запрос второго фактора, если он подключён, либо сразу сессия или смена пароля
+#[allow(clippy::too_many_arguments)]
+pub async fn complete_authentication<I, T, A>(
+    refresh_token_generator: &I,
+    access_token_provider: &T,
+    repo: &A,
+    user_credential_id: uuid::Uuid,
+    user_id: uuid::Uuid,
+    is_password_expired: bool,
+    session_policy: SessionPolicy,
+    client: SessionClient,
+) -> Result<Authentication, AppError>
+where
+    I: IdProvider,
+    T: TokenEncoderProvider,
+    A: AuthenticateUserDao + PasswordResetDao + TotpDao + MfaChallengeDao + PasskeyDao + RecoveryCodeDao,
+{
+    let mut methods = Vec::new();
+    if repo.find_totp_factor(user_id).await?.is_some_and(|factor| factor.confirmed_at.is_some()) {
+        methods.push(TOTP_MFA_METHOD);
+    }
+    if !repo.list_passkey_credential_ids(user_id).await?.is_empty() {
+        methods.push(WEBAUTHN_MFA_METHOD);
+    }
+    if !methods.is_empty() && !repo.list_recovery_codes(user_id).await?.is_empty() {
+        methods.push(RECOVERY_CODE_MFA_METHOD);
+    }
+    if !methods.is_empty() {
+        let mfa_token = match refresh_token_generator.provide() {
             Some(token) => token,
             None => return Err(AppError::UnknownError),
         };
-
-        let access_token = match self.access_token_provider.provide(secret.user_id) {
-            Some(token) => token,
+        let expires_at = match chrono::Utc::now().naive_local().checked_add_signed(chrono::Duration::minutes(MFA_CHALLENGE_TTL_IN_MINUTES)) {
+            Some(expires_at) => expires_at,
             None => return Err(AppError::UnknownError),
         };
+        // об истёкшем пароле узнает только тот, кто прошёл и второй фактор
+        repo.create_mfa_challenge(user_credential_id, mfa_token.clone(), expires_at, is_password_expired).await?;
 
-        let user_id = credentail.user_id;
-        match self.repo.create_session(credentail.id, refresh_token.clone()).await {
-            Ok(_) => Ok(Session { user_id, refresh_token, access_token }),
-            Err(_) => Err(AppError::UnknownDatabaseError),
-        }
+        return Ok(Authentication::MfaRequired(MfaChallenge { mfa_token, methods }));
+    }
+    if is_password_expired {
+        return require_password_change(refresh_token_generator, repo, user_id).await;
+    }
+
+    let session = issue_session(refresh_token_generator, access_token_provider, repo, user_credential_id, user_id, session_policy, client).await?;
+
+    Ok(Authentication::Authenticated(session))
+}
+
+// второй фактор пройден, вызов уже погашен
+pub async fn complete_mfa_challenge<I, T, A>(
+    refresh_token_generator: &I,
+    access_token_provider: &T,
+    repo: &A,
+    challenge: &UserMfaChallenge,
+    session_policy: SessionPolicy,
+    client: SessionClient,
+) -> Result<Authentication, AppError>
+where
+    I: IdProvider,
+    T: TokenEncoderProvider,
+    A: AuthenticateUserDao + PasswordResetDao,
+{
+    if challenge.password_expired {
+        return require_password_change(refresh_token_generator, repo, challenge.user_id).await;
+    }
+    let session = issue_session(refresh_token_generator, access_token_provider, repo, challenge.user_credential_id, challenge.user_id, session_policy, client).await?;
+
+    Ok(Authentication::Authenticated(session))
+}
+
+// истёкший пароль годится только для его смены, сессию не выдаём
+async fn require_password_change<I, A>(refresh_token_generator: &I, repo: &A, user_id: uuid::Uuid) -> Result<Authentication, AppError>
+where
+    I: IdProvider,
+    A: PasswordResetDao,
+{
+    let password_change_token = match refresh_token_generator.provide() {
+        Some(token) => token,
+        None => return Err(AppError::UnknownError),
+    };
+    let expires_at = match chrono::Utc::now().naive_local().checked_add_signed(chrono::Duration::minutes(PASSWORD_CHANGE_TOKEN_TTL_IN_MINUTES)) {
+        Some(expires_at) => expires_at,
+        None => return Err(AppError::UnknownError),
+    };
+    repo.create_password_reset(user_id, password_change_token.clone(), expires_at).await?;
+
+    Ok(Authentication::PasswordExpired(PasswordChangeRequired { password_change_token }))
+}
+
+// единственное место, где выдаётся сессия: после пароля, второго фактора и других способов входа
+pub async fn issue_session<I, T, A>(
+    refresh_token_generator: &I,
+    access_token_provider: &T,
+    repo: &A,
+    user_credential_id: uuid::Uuid,
+    user_id: uuid::Uuid,
+    session_policy: SessionPolicy,
+    client: SessionClient,
+) -> Result<Session, AppError>
+where
+    I: IdProvider,
+    T: TokenEncoderProvider,
+    A: AuthenticateUserDao,
+{
+    let refresh_token = match refresh_token_generator.provide() {
+        Some(token) => token,
+        None => return Err(AppError::UnknownError),
+    };
+
+    let access_token = match access_token_provider.provide(user_id.to_string()) {
+        Some(token) => token,
+        None => return Err(AppError::UnknownError),
+    };
+
+    match repo.create_session(user_credential_id, refresh_token.clone(), session_policy, client).await {
+        Ok(_) => Ok(Session { user_id, refresh_token, access_token }),
+        Err(AppError::TooManySessions) => Err(AppError::TooManySessions),
+        Err(_) => Err(AppError::UnknownDatabaseError),
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use testcontainers_modules::{
-        postgres,
-        testcontainers::{
-            ImageExt,
-            runners::AsyncRunner,
-        },
-    };
     use crate::{
-        di,
-        providers,
-        adapters,
+        di::testing::{self, TestApp, TestSettings},
+        app::commands::{
+            SessionPolicy,
+            SessionEvictionPolicy,
+            SessionClient,
+        },
     };
 
     #[tokio::test]
     async fn first_authenticate_user_command() {
         // Given
-        let postgres_container = postgres::Postgres::default()
-            .with_tag("18.1-alpine")
-            .start()
+        let TestApp { container, db_pool, postgres: _postgres } = TestApp::start().await;
+        container.register_user_command.call("username0".to_string(), "Qwerty123!".to_string()).await.unwrap();
+
+        // When
+        let initial_user_sessions_count: i64 = sqlx::query_scalar("SELECT COUNT(1) FROM user_sessions").fetch_one(&db_pool).await.unwrap();
+
+        let Authentication::Authenticated(session) = container.authenticate_user_command.call("username0".to_string(), "Qwerty123!".to_string(), SessionClient::default()).await.unwrap() else { panic!("MFA is not enabled") };
+
+        let final_user_sessions_count: i64 = sqlx::query_scalar("SELECT COUNT(1) FROM user_sessions").fetch_one(&db_pool).await.unwrap();
+        let raw_refresh_tokens_count: i64 = sqlx::query_scalar("SELECT COUNT(1) FROM user_sessions WHERE refresh_token_digest = $1")
+            .bind(session.refresh_token)
+            .fetch_one(&db_pool)
             .await
             .unwrap();
 
-        let url = &format!(
-            "postgres://postgres:postgres@{}:{}/postgres",
-            postgres_container.get_host().await.unwrap(),
-            postgres_container.get_host_port_ipv4(5432).await.unwrap()
-        );
+        // Then
+        assert_eq!(final_user_sessions_count - initial_user_sessions_count, 1);
+        assert_eq!(raw_refresh_tokens_count, 0);
+    }
+
+    #[tokio::test]
+    async fn authenticate_user_command_keeps_concurrent_sessions() {
+        // Given
+        let TestApp { container, db_pool: _, postgres: _postgres } = TestApp::start().await;
+        container.register_user_command.call("username0".to_string(), "Qwerty123!".to_string()).await.unwrap();
+
+        // When
+        let Authentication::Authenticated(laptop_session) = container.authenticate_user_command.call("username0".to_string(), "Qwerty123!".to_string(), SessionClient::default()).await.unwrap() else { panic!("MFA is not enabled") };
+        let Authentication::Authenticated(phone_session) = container.authenticate_user_command.call("username0".to_string(), "Qwerty123!".to_string(), SessionClient::default()).await.unwrap() else { panic!("MFA is not enabled") };
+
+        // Then
+        assert!(container.refresh_session_command.call(laptop_session.refresh_token, SessionClient::default()).await.is_ok());
+        assert!(container.refresh_session_command.call(phone_session.refresh_token, SessionClient::default()).await.is_ok());
+    }
 
-        let db_pool = sqlx::postgres::PgPoolOptions::new().max_connections(1).connect(url).await.unwrap();
-        sqlx::migrate!("./migrations").run(&db_pool).await.unwrap();
+    #[tokio::test]
+    async fn authenticate_user_command_evicts_oldest_session() {
+        // Given
+        let TestApp { container, db_pool: _, postgres: _postgres } = TestApp::start_with(TestSettings {
+            session_policy: SessionPolicy { max_active_sessions: 1, eviction_policy: SessionEvictionPolicy::OldestFirst, ..SessionPolicy::default() },
+            ..TestSettings::default()
+        }).await;
+        container.register_user_command.call("username0".to_string(), "Qwerty123!".to_string()).await.unwrap();
 
-        let argon2_hasher = providers::argon2_hasher::Argon2HasherProvider::new(8, 1, 1);
-        let argon2_verifier = providers::argon2_verifier::Argon2VerifierProvider::new(8, 1, 1);
+        // When
+        let Authentication::Authenticated(laptop_session) = container.authenticate_user_command.call("username0".to_string(), "Qwerty123!".to_string(), SessionClient::default()).await.unwrap() else { panic!("MFA is not enabled") };
+        let Authentication::Authenticated(phone_session) = container.authenticate_user_command.call("username0".to_string(), "Qwerty123!".to_string(), SessionClient::default()).await.unwrap() else { panic!("MFA is not enabled") };
 
-        let refresh_token_generator = providers::refresh_token_generator::RefreshTokenGeneratorProvider;
-        let jwt_encoder = providers::jwt_encoder::JwtEncoderProvider;
+        // Then
+        assert!(matches!(container.refresh_session_command.call(laptop_session.refresh_token, SessionClient::default()).await, Err(AppError::LoginRequired)));
+        assert!(container.refresh_session_command.call(phone_session.refresh_token, SessionClient::default()).await.is_ok());
+    }
 
-        let user_repo = adapters::postgres::UserRepository::new(db_pool.clone());
-        let container = di::Container::new(
-            argon2_hasher,
-            argon2_verifier,
-            refresh_token_generator,
-            jwt_encoder,
-            user_repo.clone(),
-            user_repo.clone(),
-            user_repo.clone(),
-            user_repo.clone(),
-            user_repo,
-        );
+    #[tokio::test]
+    async fn authenticate_user_command_rejects_session_over_limit() {
+        // Given
+        let TestApp { container, db_pool: _, postgres: _postgres } = TestApp::start_with(TestSettings {
+            session_policy: SessionPolicy { max_active_sessions: 1, eviction_policy: SessionEvictionPolicy::Reject, ..SessionPolicy::default() },
+            ..TestSettings::default()
+        }).await;
         container.register_user_command.call("username0".to_string(), "Qwerty123!".to_string()).await.unwrap();
 
         // When
-        let initial_user_sessions_count: i64 = sqlx::query_scalar("SELECT COUNT(1) FROM user_sessions").fetch_one(&db_pool).await.unwrap();
+        let Authentication::Authenticated(laptop_session) = container.authenticate_user_command.call("username0".to_string(), "Qwerty123!".to_string(), SessionClient::default()).await.unwrap() else { panic!("MFA is not enabled") };
+        let phone_res = container.authenticate_user_command.call("username0".to_string(), "Qwerty123!".to_string(), SessionClient::default()).await;
 
-        container.authenticate_user_command.call("username0".to_string(), "Qwerty123!".to_string()).await.unwrap();
+        // Then
+        assert!(matches!(phone_res, Err(AppError::TooManySessions)));
+        assert!(container.refresh_session_command.call(laptop_session.refresh_token, SessionClient::default()).await.is_ok());
+    }
 
-        let final_user_sessions_count: i64 = sqlx::query_scalar("SELECT COUNT(1) FROM user_sessions").fetch_one(&db_pool).await.unwrap();
+    #[tokio::test]
+    async fn authenticate_user_command_applies_max_age_to_existing_password() {
+        // Given
+        let TestApp { container, db_pool, postgres: _postgres } = TestApp::start().await;
+        container.register_user_command.call("username0".to_string(), "Qwerty123!".to_string()).await.unwrap();
+        sqlx::query("UPDATE user_passwords SET changed_at = changed_at - INTERVAL '91 days'").execute(&db_pool).await.unwrap();
+        container.register_user_command.call("username1".to_string(), "Qwerty123!".to_string()).await.unwrap();
+        let max_age_container = testing::container(&db_pool, TestSettings {
+            password_policy: PasswordPolicy { max_age_in_days: 90, ..PasswordPolicy::default() },
+            ..TestSettings::default()
+        });
+
+        // When
+        let before_policy_res = container.authenticate_user_command.call("username0".to_string(), "Qwerty123!".to_string(), SessionClient::default()).await;
+        let old_password_res = max_age_container.authenticate_user_command.call("username0".to_string(), "Qwerty123!".to_string(), SessionClient::default()).await;
+        let recent_password_res = max_age_container.authenticate_user_command.call("username1".to_string(), "Qwerty123!".to_string(), SessionClient::default()).await;
 
         // Then
-        assert_eq!(final_user_sessions_count - initial_user_sessions_count, 1);
+        assert!(matches!(before_policy_res, Ok(Authentication::Authenticated(_))));
+        assert!(matches!(old_password_res, Ok(Authentication::PasswordExpired(_))));
+        assert!(matches!(recent_password_res, Ok(Authentication::Authenticated(_))));
     }
 }
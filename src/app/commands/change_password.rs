@@ -9,38 +9,45 @@ use crate::{
             FindUserSecretDao,
         },
         commands::{
-            UserCredential,
             ChangePasswordDao,
+            BreachedPasswordDao,
+            password_policy::{PasswordPolicy, PasswordWarning, validate_new_password, ensure_not_recently_used},
         },
     },
 };
 
-pub struct ChangePasswordCommand<H, V, C>
+pub struct ChangePasswordCommand<H, V, C, B>
 where
     H: HashFuncProvider,
     V: HashVerifierProvider,
     C: FindUserSecretDao + ChangePasswordDao,
+    B: BreachedPasswordDao,
 {
     hash_func_provider: H,
     hash_verifier_provider: V,
     repo: C,
+    breach_corpus: B,
+    password_policy: PasswordPolicy,
 }
 
-impl<H, V, C> ChangePasswordCommand<H, V, C>
+impl<H, V, C, B> ChangePasswordCommand<H, V, C, B>
 where
     H: HashFuncProvider,
     V: HashVerifierProvider,
     C: FindUserSecretDao + ChangePasswordDao,
+    B: BreachedPasswordDao,
 {
-    pub fn new(hash_func_provider: H, hash_verifier_provider: V, repo: C) -> Self {
+    pub fn new(hash_func_provider: H, hash_verifier_provider: V, repo: C, breach_corpus: B, password_policy: PasswordPolicy) -> Self {
         Self {
             hash_func_provider,
             hash_verifier_provider,
             repo,
+            breach_corpus,
+            password_policy,
         }
     }
 
-    pub async fn call(&self, user_id: uuid::Uuid, old_password: String, new_password: String) -> Result<(), AppError> {
+    pub async fn call(&self, user_id: uuid::Uuid, old_password: String, new_password: String) -> Result<Vec<PasswordWarning>, AppError> {
         let secret = match self.repo.find_user_secret_by_user_id(user_id).await {
             Ok(some_or_none) => match some_or_none {
                 Some(secret) => secret,
@@ -48,12 +55,16 @@ where
             },
             Err(_) => return Err(AppError::UnknownDatabaseError),
         };
-        let password_confirmation = self.hash_verifier_provider.provide(old_password, secret.password_digest);
+        let password_confirmation = self.hash_verifier_provider.provide(old_password, secret.password_digest.clone());
         let is_password_correct = password_confirmation.is_confirmed;
 
         if !is_password_correct {
             // TODO: при 7 неудачных попытках - выкинуть пользователя
+            return Err(AppError::LoginError);
         }
+        let logins = self.repo.list_user_logins(user_id).await?;
+        let warnings = validate_new_password(&self.password_policy, &self.breach_corpus, &new_password, &logins).await?;
+        ensure_not_recently_used(&self.password_policy, &self.hash_verifier_provider, &self.repo, user_id, secret.password_digest, &new_password).await?;
 
         let new_password_digest = match self.hash_func_provider.provide(new_password) {
             Some(hash) => hash,
@@ -62,9 +73,50 @@ where
             },
         };
 
-        match self.repo.upgrade_password_digest(secret.id, new_password_digest).await {
-            Ok(_) => Ok(()),
+        match self.repo.change_password_digest(secret.id, new_password_digest, self.password_policy.history_size).await {
+            Ok(_) => Ok(warnings),
             Err(_) => return Err(AppError::UnknownDatabaseError),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        di::testing::{TestApp, TestSettings},
+        app::commands::{
+            Authentication,
+            password_policy::{PasswordPolicy, PasswordWeakness},
+            SessionClient,
+        },
+    };
+
+    #[tokio::test]
+    async fn change_password_command_rejects_recent_passwords() {
+        // Given
+        let TestApp { container, db_pool, postgres: _postgres } = TestApp::start_with(TestSettings {
+            password_policy: PasswordPolicy { history_size: 2, ..PasswordPolicy::default() },
+            ..TestSettings::default()
+        }).await;
+        container.register_user_command.call("username0".to_string(), "Qwerty123!".to_string()).await.unwrap();
+        let Authentication::Authenticated(session) = container.authenticate_user_command.call("username0".to_string(), "Qwerty123!".to_string(), SessionClient::default()).await.unwrap() else { panic!("MFA is not enabled") };
+        let user_id = session.user_id;
+
+        // When
+        let first_change_res = container.change_password_command.call(user_id, "Qwerty123!".to_string(), "Asdfgh456?".to_string()).await;
+        let same_password_res = container.change_password_command.call(user_id, "Asdfgh456?".to_string(), "Asdfgh456?".to_string()).await;
+        let previous_password_res = container.change_password_command.call(user_id, "Asdfgh456?".to_string(), "Qwerty123!".to_string()).await;
+        let second_change_res = container.change_password_command.call(user_id, "Asdfgh456?".to_string(), "Zxcvbn789#".to_string()).await;
+        let forgotten_password_res = container.change_password_command.call(user_id, "Zxcvbn789#".to_string(), "Qwerty123!".to_string()).await;
+        let history_count: i64 = sqlx::query_scalar("SELECT COUNT(1) FROM user_password_history").fetch_one(&db_pool).await.unwrap();
+
+        // Then
+        assert!(first_change_res.is_ok());
+        assert!(matches!(same_password_res, Err(AppError::WeakPassword(reasons)) if reasons == vec![PasswordWeakness::RecentlyUsed]));
+        assert!(matches!(previous_password_res, Err(AppError::WeakPassword(reasons)) if reasons == vec![PasswordWeakness::RecentlyUsed]));
+        assert!(second_change_res.is_ok());
+        assert!(forgotten_password_res.is_ok());
+        assert_eq!(history_count, 1);
+    }
+}
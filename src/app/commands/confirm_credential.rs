@@ -0,0 +1,65 @@
+use crate::{
+    errors::AppError,
+    app::commands::ConfirmCredentialDao,
+};
+
+pub struct ConfirmCredentialCommand<R>
+where
+    R: ConfirmCredentialDao,
+{
+    repo: R,
+}
+
+impl<R> ConfirmCredentialCommand<R>
+where
+    R: ConfirmCredentialDao,
+{
+    pub fn new(repo: R) -> Self {
+        Self { repo }
+    }
+
+    pub async fn call(&self, token: String) -> Result<(), AppError> {
+        match self.repo.confirm_credential(token.trim().to_string()).await? {
+            true => Ok(()),
+            false => Err(AppError::InvalidToken),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        di::testing::{TestApp, TestSettings},
+        app::commands::{
+            SessionClient,
+        },
+    };
+
+    #[tokio::test]
+    async fn confirm_credential_command() {
+        // Given
+        let outbox_path = std::env::temp_dir().join(format!("auth-confirm-credential-{}.txt", std::process::id()));
+        let _ = std::fs::remove_file(&outbox_path);
+
+        let TestApp { container, db_pool: _, postgres: _postgres } = TestApp::start_with(TestSettings {
+            mailer_outbox_path: outbox_path.clone(),
+            ..TestSettings::default()
+        }).await;
+        container.register_user_command.call("User0@Example.com".to_string(), "Qwerty123!".to_string()).await.unwrap();
+        let unconfirmed_res = container.authenticate_user_command.call("user0@example.com".to_string(), "Qwerty123!".to_string(), SessionClient::default()).await;
+        let outbox = std::fs::read_to_string(&outbox_path).unwrap();
+        let token = outbox.lines().find_map(|line| line.strip_prefix("Your confirmation code: ")).unwrap().to_string();
+
+        // When
+        let first_res = container.confirm_credential_command.call(token.clone()).await;
+        let second_res = container.confirm_credential_command.call(token).await;
+        let confirmed_res = container.authenticate_user_command.call("user0@example.com".to_string(), "Qwerty123!".to_string(), SessionClient::default()).await;
+
+        // Then
+        assert!(matches!(unconfirmed_res, Err(AppError::UnconfirmedCredential)));
+        assert!(first_res.is_ok());
+        assert!(matches!(second_res, Err(AppError::InvalidToken)));
+        assert!(confirmed_res.is_ok());
+    }
+}
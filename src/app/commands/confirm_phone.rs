@@ -0,0 +1,119 @@
+use crate::{
+    errors::AppError,
+    app::{
+        queries::FindUserCredentialDao,
+        commands::{
+            PHONE_CREDENTIAL_KIND,
+            PHONE_CONFIRMATION_MAX_ATTEMPTS,
+            ConfirmCredentialDao,
+            normalize_login,
+        },
+    },
+};
+
+pub struct ConfirmPhoneCommand<R>
+where
+    R: FindUserCredentialDao + ConfirmCredentialDao,
+{
+    repo: R,
+}
+
+impl<R> ConfirmPhoneCommand<R>
+where
+    R: FindUserCredentialDao + ConfirmCredentialDao,
+{
+    pub fn new(repo: R) -> Self {
+        Self { repo }
+    }
+
+    pub async fn call(&self, login: String, code: String) -> Result<(), AppError> {
+        let login = match normalize_login(&login) {
+            Some((PHONE_CREDENTIAL_KIND, login)) => login,
+            _ => return Err(AppError::InvalidToken),
+        };
+        let credential = match self.repo.find_user_credential_by_login(login).await {
+            Ok(Some(credential)) => credential,
+            Ok(None) => return Err(AppError::InvalidToken),
+            Err(_) => return Err(AppError::UnknownDatabaseError),
+        };
+
+        match self.repo.confirm_credential_with_code(credential.id, code.trim().to_string(), PHONE_CONFIRMATION_MAX_ATTEMPTS).await? {
+            true => Ok(()),
+            false => Err(AppError::InvalidToken),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        di::testing::{TestApp, TestSettings},
+        app::commands::{
+            SessionClient,
+        },
+    };
+
+    #[tokio::test]
+    async fn confirm_phone_command() {
+        // Given
+        let outbox_path = std::env::temp_dir().join(format!("auth-confirm-phone-{}.txt", std::process::id()));
+        let _ = std::fs::remove_file(&outbox_path);
+
+        let TestApp { container, db_pool: _, postgres: _postgres } = TestApp::start_with(TestSettings {
+            sms_outbox_path: outbox_path.clone(),
+            ..TestSettings::default()
+        }).await;
+        container.register_user_command.call("+1 (555) 010-9999".to_string(), "Qwerty123!".to_string()).await.unwrap();
+        let outbox = std::fs::read_to_string(&outbox_path).unwrap();
+        let code = outbox.lines().find_map(|line| line.strip_prefix("Your confirmation code: ")).unwrap().to_string();
+        let wrong_code = format!("{:06}", (code.parse::<u32>().unwrap() + 1) % 1_000_000);
+        let unconfirmed_res = container.authenticate_user_command.call("+15550109999".to_string(), "Qwerty123!".to_string(), SessionClient::default()).await;
+
+        // When
+        let wrong_code_res = container.confirm_phone_command.call("+15550109999".to_string(), wrong_code).await;
+        let code_as_token_res = container.confirm_credential_command.call(code.clone()).await;
+        let right_code_res = container.confirm_phone_command.call("+1 555 010 9999".to_string(), code).await;
+        let confirmed_res = container.authenticate_user_command.call("+15550109999".to_string(), "Qwerty123!".to_string(), SessionClient::default()).await;
+
+        // Then
+        assert!(matches!(unconfirmed_res, Err(AppError::UnconfirmedCredential)));
+        assert!(matches!(wrong_code_res, Err(AppError::InvalidToken)));
+        assert!(matches!(code_as_token_res, Err(AppError::InvalidToken)));
+        assert!(right_code_res.is_ok());
+        assert!(confirmed_res.is_ok());
+    }
+
+    #[tokio::test]
+    async fn confirm_phone_command_after_too_many_attempts() {
+        // Given
+        let outbox_path = std::env::temp_dir().join(format!("auth-confirm-phone-attempts-{}.txt", std::process::id()));
+        let _ = std::fs::remove_file(&outbox_path);
+
+        let TestApp { container, db_pool, postgres: _postgres } = TestApp::start_with(TestSettings {
+            sms_outbox_path: outbox_path.clone(),
+            ..TestSettings::default()
+        }).await;
+        container.register_user_command.call("+1 (555) 010-9999".to_string(), "Qwerty123!".to_string()).await.unwrap();
+        let outbox = std::fs::read_to_string(&outbox_path).unwrap();
+        let code = outbox.lines().find_map(|line| line.strip_prefix("Your confirmation code: ")).unwrap().to_string();
+        let wrong_code = format!("{:06}", (code.parse::<u32>().unwrap() + 1) % 1_000_000);
+        for _ in 0..PHONE_CONFIRMATION_MAX_ATTEMPTS {
+            container.confirm_phone_command.call("+15550109999".to_string(), wrong_code.clone()).await.unwrap_err();
+        }
+        // минута ожидания перед повторной отправкой прошла
+        sqlx::query("UPDATE user_credential_confirmations SET created_at = created_at - INTERVAL '2 minutes'").execute(&db_pool).await.unwrap();
+        container.request_credential_confirmation_command.call("+15550109999".to_string()).await.unwrap();
+        let outbox = std::fs::read_to_string(&outbox_path).unwrap();
+        let resent_code = outbox.lines().filter_map(|line| line.strip_prefix("Your confirmation code: ")).next_back().unwrap().to_string();
+
+        // When
+        let res = container.confirm_phone_command.call("+15550109999".to_string(), code).await;
+        let resent_res = container.confirm_phone_command.call("+15550109999".to_string(), resent_code).await;
+
+        // Then
+        assert_eq!(outbox.lines().filter(|line| line.starts_with("Your confirmation code: ")).count(), 2);
+        assert!(matches!(res, Err(AppError::InvalidToken)));
+        assert!(matches!(resent_res, Err(AppError::InvalidToken)));
+    }
+}
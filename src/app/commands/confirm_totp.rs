@@ -0,0 +1,57 @@
+use crate::{
+    errors::AppError,
+    providers::{
+        HashFuncProvider,
+        IdProvider,
+        TotpProvider,
+    },
+    app::commands::{
+        RecoveryCodes,
+        TotpDao,
+        RecoveryCodeDao,
+        regenerate_recovery_codes::generate_missing_recovery_codes,
+    },
+};
+
+pub struct ConfirmTotpCommand<F, E, H, R>
+where
+    F: TotpProvider,
+    E: IdProvider,
+    H: HashFuncProvider,
+    R: TotpDao + RecoveryCodeDao,
+{
+    totp_provider: F,
+    recovery_code_generator: E,
+    hash_func_provider: H,
+    repo: R,
+}
+
+impl<F, E, H, R> ConfirmTotpCommand<F, E, H, R>
+where
+    F: TotpProvider,
+    E: IdProvider,
+    H: HashFuncProvider,
+    R: TotpDao + RecoveryCodeDao,
+{
+    pub fn new(totp_provider: F, recovery_code_generator: E, hash_func_provider: H, repo: R) -> Self {
+        Self { totp_provider, recovery_code_generator, hash_func_provider, repo }
+    }
+
+    pub async fn call(&self, user_id: uuid::Uuid, code: String) -> Result<RecoveryCodes, AppError> {
+        let factor = match self.repo.find_totp_factor(user_id).await? {
+            Some(factor) => factor,
+            None => return Err(AppError::NotFound),
+        };
+        if factor.confirmed_at.is_some() { return Err(AppError::MfaAlreadyEnabled) };
+
+        let unix_time = chrono::Utc::now().timestamp().unsigned_abs();
+        let step = match self.totp_provider.verify(factor.secret, code, unix_time) {
+            Some(step) => step,
+            None => return Err(AppError::InvalidToken),
+        };
+
+        if !self.repo.confirm_totp_factor(factor.id, step).await? { return Err(AppError::MfaAlreadyEnabled) };
+
+        generate_missing_recovery_codes(&self.recovery_code_generator, &self.hash_func_provider, &self.repo, user_id).await
+    }
+}
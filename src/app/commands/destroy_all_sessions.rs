@@ -0,0 +1,24 @@
+use crate::{
+    errors::AppError,
+    app::commands::DestroySessionDao,
+};
+
+pub struct DestroyAllSessionsCommand<S>
+where
+    S: DestroySessionDao,
+{
+    repo: S,
+}
+
+impl<S> DestroyAllSessionsCommand<S>
+where
+    S: DestroySessionDao,
+{
+    pub fn new(repo: S) -> Self {
+        Self { repo }
+    }
+
+    pub async fn call(&self, user_id: uuid::Uuid) -> Result<(), AppError> {
+        self.repo.destroy_all_sessions(user_id).await
+    }
+}
@@ -0,0 +1,55 @@
+use crate::{
+    errors::AppError,
+    app::commands::DestroySessionDao,
+};
+
+pub struct DestroySessionCommand<S>
+where
+    S: DestroySessionDao,
+{
+    repo: S,
+}
+
+impl<S> DestroySessionCommand<S>
+where
+    S: DestroySessionDao,
+{
+    pub fn new(repo: S) -> Self {
+        Self { repo }
+    }
+
+    pub async fn call(&self, refresh_token: String) -> Result<(), AppError> {
+        // повторный выход по тому же токену не считается ошибкой
+        self.repo.destroy_session(refresh_token).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        di::testing::TestApp,
+        app::commands::{
+            Authentication,
+            SessionClient,
+        },
+    };
+
+    #[tokio::test]
+    async fn destroy_session_command() {
+        // Given
+        let TestApp { container, db_pool: _, postgres: _postgres } = TestApp::start().await;
+        container.register_user_command.call("username0".to_string(), "Qwerty123!".to_string()).await.unwrap();
+        let Authentication::Authenticated(session) = container.authenticate_user_command.call("username0".to_string(), "Qwerty123!".to_string(), SessionClient::default()).await.unwrap() else { panic!("MFA is not enabled") };
+
+        // When
+        let first_res = container.destroy_session_command.call(session.refresh_token.clone()).await;
+        let second_res = container.destroy_session_command.call(session.refresh_token.clone()).await;
+        let refresh_res = container.refresh_session_command.call(session.refresh_token, SessionClient::default()).await;
+
+        // Then
+        assert!(first_res.is_ok());
+        assert!(second_res.is_ok());
+        assert!(matches!(refresh_res, Err(AppError::LoginRequired)));
+    }
+}
@@ -0,0 +1,43 @@
+use crate::{
+    errors::AppError,
+    providers::TotpProvider,
+    app::commands::{
+        TotpEnrollment,
+        TotpDao,
+    },
+};
+
+pub struct EnrollTotpCommand<F, R>
+where
+    F: TotpProvider,
+    R: TotpDao,
+{
+    totp_provider: F,
+    repo: R,
+}
+
+impl<F, R> EnrollTotpCommand<F, R>
+where
+    F: TotpProvider,
+    R: TotpDao,
+{
+    pub fn new(totp_provider: F, repo: R) -> Self {
+        Self { totp_provider, repo }
+    }
+
+    // неподтверждённый секрет заменяется новым, подтверждённый остаётся нетронутым
+    pub async fn call(&self, user_id: uuid::Uuid) -> Result<TotpEnrollment, AppError> {
+        let account_name = match self.repo.find_user_login(user_id).await? {
+            Some(login) => login,
+            None => return Err(AppError::NotFound),
+        };
+        let secret = match self.totp_provider.generate_secret() {
+            Some(secret) => secret,
+            None => return Err(AppError::UnknownError),
+        };
+        self.repo.create_totp_factor(user_id, secret.clone()).await?;
+
+        let uri = self.totp_provider.provisioning_uri(secret.clone(), account_name);
+        Ok(TotpEnrollment { secret, uri })
+    }
+}
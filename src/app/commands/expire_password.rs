@@ -0,0 +1,97 @@
+use crate::{
+    errors::AppError,
+    app::commands::ChangePasswordDao,
+};
+
+// администратор может досрочно истечь пароль: открытые сессии закрываются, при следующем входе пользователь обязан его сменить
+pub struct ExpirePasswordCommand<R>
+where
+    R: ChangePasswordDao,
+{
+    repo: R,
+}
+
+impl<R> ExpirePasswordCommand<R>
+where
+    R: ChangePasswordDao,
+{
+    pub fn new(repo: R) -> Self {
+        Self { repo }
+    }
+
+    pub async fn call(&self, user_id: uuid::Uuid) -> Result<(), AppError> {
+        if self.repo.expire_password(user_id).await? {
+            Ok(())
+        } else {
+            Err(AppError::NotFound)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        di::testing::{TestApp, TestSettings},
+        providers,
+        providers::TotpProvider,
+        app::commands::{
+            Authentication,
+            password_policy::PasswordPolicy,
+            SessionClient,
+        },
+    };
+
+    #[tokio::test]
+    async fn expire_password_command() {
+        // Given
+        let TestApp { container, db_pool: _, postgres: _postgres } = TestApp::start_with(TestSettings {
+            password_policy: PasswordPolicy { max_age_in_days: 90, ..PasswordPolicy::default() },
+            ..TestSettings::default()
+        }).await;
+        container.register_user_command.call("username0".to_string(), "Qwerty123!".to_string()).await.unwrap();
+        let Authentication::Authenticated(session) = container.authenticate_user_command.call("username0".to_string(), "Qwerty123!".to_string(), SessionClient::default()).await.unwrap() else { panic!("MFA is not enabled") };
+
+        // When
+        let unknown_user_res = container.expire_password_command.call(uuid::Uuid::nil()).await;
+        let expire_res = container.expire_password_command.call(session.user_id).await;
+        let refresh_res = container.refresh_session_command.call(session.refresh_token, SessionClient::default()).await;
+        let login_res = container.authenticate_user_command.call("username0".to_string(), "Qwerty123!".to_string(), SessionClient::default()).await.unwrap();
+        let Authentication::PasswordExpired(password_change) = login_res else { panic!("password is expired") };
+        let reset_res = container.reset_password_command.call(password_change.password_change_token, "Asdfgh456?".to_string()).await;
+        let new_password_res = container.authenticate_user_command.call("username0".to_string(), "Asdfgh456?".to_string(), SessionClient::default()).await;
+
+        // Then
+        assert!(matches!(unknown_user_res, Err(AppError::NotFound)));
+        assert!(expire_res.is_ok());
+        assert!(refresh_res.is_err());
+        assert!(reset_res.is_ok());
+        assert!(matches!(new_password_res, Ok(Authentication::Authenticated(_))));
+    }
+
+    #[tokio::test]
+    async fn expired_password_with_totp_enrolled() {
+        // Given
+        let TestApp { container, db_pool: _, postgres: _postgres } = TestApp::start().await;
+        container.register_user_command.call("username0".to_string(), "Qwerty123!".to_string()).await.unwrap();
+        let Authentication::Authenticated(session) = container.authenticate_user_command.call("username0".to_string(), "Qwerty123!".to_string(), SessionClient::default()).await.unwrap() else { panic!("MFA is not enabled") };
+        let enrollment = container.enroll_totp_command.call(session.user_id).await.unwrap();
+        let totp = providers::hmac_totp::HmacTotpProvider::new("auth".to_string());
+        let unix_time = chrono::Utc::now().timestamp().unsigned_abs();
+        container.confirm_totp_command.call(session.user_id, totp.generate(enrollment.secret.clone(), unix_time).unwrap()).await.unwrap();
+        let next_code = totp.generate(enrollment.secret, unix_time + providers::hmac_totp::TOTP_PERIOD_IN_SECONDS).unwrap();
+        container.expire_password_command.call(session.user_id).await.unwrap();
+
+        // When
+        let password_res = container.authenticate_user_command.call("username0".to_string(), "Qwerty123!".to_string(), SessionClient::default()).await.unwrap();
+        let Authentication::MfaRequired(challenge) = password_res else { panic!("TOTP is enabled") };
+        let totp_res = container.verify_totp_challenge_command.call(challenge.mfa_token, next_code, SessionClient::default()).await.unwrap();
+        let Authentication::PasswordExpired(password_change) = totp_res else { panic!("password is expired") };
+        let reset_res = container.reset_password_command.call(password_change.password_change_token, "Asdfgh456?".to_string()).await;
+        let new_password_res = container.authenticate_user_command.call("username0".to_string(), "Asdfgh456?".to_string(), SessionClient::default()).await;
+
+        // Then
+        assert!(reset_res.is_ok());
+        assert!(matches!(new_password_res, Ok(Authentication::MfaRequired(_))));
+    }
+}
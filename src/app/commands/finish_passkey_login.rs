@@ -0,0 +1,195 @@
+use crate::{
+    errors::AppError,
+    providers::{
+        IdProvider,
+        TokenEncoderProvider,
+        WebauthnProvider,
+    },
+    app::{
+        UserMfaChallenge,
+        commands::{
+            MFA_CHALLENGE_MAX_ATTEMPTS,
+            PASSKEY_LOGIN_CHALLENGE_KIND,
+            Authentication,
+            SessionPolicy,
+            SessionClient,
+            AuthenticateUserDao,
+            PasswordResetDao,
+            MfaChallengeDao,
+            PasskeyDao,
+            authenticate_user::{issue_session, complete_mfa_challenge},
+        },
+    },
+};
+
+pub struct PasskeyAssertionRequest {
+    pub credential_id: String,
+    pub client_data_json: String,
+    pub authenticator_data: String,
+    pub signature: String,
+}
+
+pub struct FinishPasskeyLoginCommand<I, T, W, R>
+where
+    I: IdProvider,
+    T: TokenEncoderProvider,
+    W: WebauthnProvider,
+    R: AuthenticateUserDao + PasswordResetDao + MfaChallengeDao + PasskeyDao,
+{
+    refresh_token_generator: I,
+    access_token_provider: T,
+    webauthn_provider: W,
+    repo: R,
+    session_policy: SessionPolicy,
+}
+
+impl<I, T, W, R> FinishPasskeyLoginCommand<I, T, W, R>
+where
+    I: IdProvider,
+    T: TokenEncoderProvider,
+    W: WebauthnProvider,
+    R: AuthenticateUserDao + PasswordResetDao + MfaChallengeDao + PasskeyDao,
+{
+    pub fn new(refresh_token_generator: I, access_token_provider: T, webauthn_provider: W, repo: R, session_policy: SessionPolicy) -> Self {
+        Self {
+            refresh_token_generator,
+            access_token_provider,
+            webauthn_provider,
+            repo,
+            session_policy,
+        }
+    }
+
+    async fn reject(&self, mfa_challenge: Option<&UserMfaChallenge>) -> Result<Authentication, AppError> {
+        if let Some(mfa_challenge) = mfa_challenge {
+            self.repo.fail_mfa_challenge(mfa_challenge.id).await?;
+        }
+
+        Err(AppError::LoginError)
+    }
+
+    pub async fn call(&self, assertion: PasskeyAssertionRequest, mfa_token: Option<String>, client: SessionClient) -> Result<Authentication, AppError> {
+        let mfa_challenge = match mfa_token {
+            Some(mfa_token) => match self.repo.find_mfa_challenge(mfa_token, MFA_CHALLENGE_MAX_ATTEMPTS).await? {
+                Some(mfa_challenge) => Some(mfa_challenge),
+                None => return Err(AppError::InvalidToken),
+            },
+            None => None,
+        };
+        let passkey = match self.repo.find_passkey(assertion.credential_id).await? {
+            Some(passkey) => passkey,
+            None => return self.reject(mfa_challenge.as_ref()).await,
+        };
+        let verified = match self.webauthn_provider.verify_assertion(
+            passkey.public_key,
+            assertion.client_data_json,
+            assertion.authenticator_data,
+            assertion.signature,
+        ) {
+            Ok(verified) => verified,
+            Err(_) => return self.reject(mfa_challenge.as_ref()).await,
+        };
+
+        let challenge = match self.repo.use_webauthn_challenge(PASSKEY_LOGIN_CHALLENGE_KIND.to_string(), verified.challenge).await? {
+            Some(challenge) => challenge,
+            None => return Err(AppError::InvalidToken),
+        };
+        let is_owner = match &mfa_challenge {
+            Some(mfa_challenge) => mfa_challenge.user_id == passkey.user_id && challenge.user_id == Some(passkey.user_id),
+            // ключ заменяет и логин, и пароль, одного присутствия пользователя мало
+            None => challenge.user_id.is_none() && verified.user_verified,
+        };
+        if !is_owner { return self.reject(mfa_challenge.as_ref()).await };
+
+        // счётчик, который не растёт, выдаёт клон аутентификатора; нулевой счётчик разрешён спецификацией
+        let is_counter_valid = (passkey.sign_count == 0 && verified.sign_count == 0) || i64::from(verified.sign_count) > passkey.sign_count;
+        if !is_counter_valid || !self.repo.update_passkey_sign_count(passkey.id, passkey.sign_count, verified.sign_count).await? {
+            return self.reject(mfa_challenge.as_ref()).await;
+        }
+
+        if let Some(mfa_challenge) = mfa_challenge {
+            if !self.repo.use_mfa_challenge(mfa_challenge.id).await? { return Err(AppError::InvalidToken) };
+
+            return complete_mfa_challenge(
+                &self.refresh_token_generator,
+                &self.access_token_provider,
+                &self.repo,
+                &mfa_challenge,
+                self.session_policy,
+                client,
+            ).await;
+        }
+        let session = issue_session(
+            &self.refresh_token_generator,
+            &self.access_token_provider,
+            &self.repo,
+            passkey.user_credential_id,
+            passkey.user_id,
+            self.session_policy,
+            client,
+        ).await?;
+
+        Ok(Authentication::Authenticated(session))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        di::testing::TestApp,
+        providers::webauthn_verifier::tests::SoftwareAuthenticator,
+        app::commands::{
+            Authentication,
+            SessionClient,
+        },
+    };
+
+    #[tokio::test]
+    async fn finish_passkey_login_command() {
+        // Given
+        let TestApp { container, db_pool: _, postgres: _postgres } = TestApp::start().await;
+        container.register_user_command.call("username0".to_string(), "Qwerty123!".to_string()).await.unwrap();
+        let Authentication::Authenticated(session) = container.authenticate_user_command.call("username0".to_string(), "Qwerty123!".to_string(), SessionClient::default()).await.unwrap() else { panic!("MFA is not enabled") };
+        let mut authenticator = SoftwareAuthenticator::new("localhost", "http://localhost:5000");
+        let creation_options = container.start_passkey_registration_command.call(session.user_id).await.unwrap();
+        let (client_data_json, attestation_object) = authenticator.register(&creation_options.challenge);
+        container.finish_passkey_registration_command.call(session.user_id, client_data_json, attestation_object, Some("Laptop".to_string())).await.unwrap();
+        let credential_id = authenticator.credential_id();
+        let assertion = |(client_data_json, authenticator_data, signature): (String, String, String)| PasskeyAssertionRequest {
+            credential_id: credential_id.clone(),
+            client_data_json,
+            authenticator_data,
+            signature,
+        };
+
+        // When
+        let request_options = container.start_passkey_login_command.call(None).await.unwrap();
+        let signed = authenticator.assert(&request_options.challenge, true);
+        let passwordless_res = container.finish_passkey_login_command.call(assertion(signed.clone()), None, SessionClient::default()).await;
+        let replayed_res = container.finish_passkey_login_command.call(assertion(signed), None, SessionClient::default()).await;
+
+        let request_options = container.start_passkey_login_command.call(None).await.unwrap();
+        let unverified_res = container.finish_passkey_login_command.call(assertion(authenticator.assert(&request_options.challenge, false)), None, SessionClient::default()).await;
+
+        let Authentication::MfaRequired(challenge) = container.authenticate_user_command.call("username0".to_string(), "Qwerty123!".to_string(), SessionClient::default()).await.unwrap() else { panic!("MFA is enabled") };
+        let mfa_request_options = container.start_passkey_login_command.call(Some(challenge.mfa_token.clone())).await.unwrap();
+        let second_factor_res = container.finish_passkey_login_command.call(assertion(authenticator.assert(&mfa_request_options.challenge, false)), Some(challenge.mfa_token), SessionClient::default()).await;
+
+        authenticator.sign_count = 0;
+        let request_options = container.start_passkey_login_command.call(None).await.unwrap();
+        let cloned_res = container.finish_passkey_login_command.call(assertion(authenticator.assert(&request_options.challenge, true)), None, SessionClient::default()).await;
+
+        // Then
+        assert_eq!(creation_options.attestation, "none");
+        assert!(matches!(passwordless_res, Ok(Authentication::Authenticated(authenticated)) if authenticated.user_id == session.user_id));
+        assert!(matches!(replayed_res, Err(AppError::InvalidToken)));
+        assert!(matches!(unverified_res, Err(AppError::LoginError)));
+        assert_eq!(challenge.methods, ["webauthn", "recovery_code"]);
+        assert_eq!(mfa_request_options.allow_credentials.len(), 1);
+        assert_eq!(mfa_request_options.allow_credentials[0].id, credential_id);
+        assert!(matches!(second_factor_res, Ok(Authentication::Authenticated(authenticated)) if authenticated.user_id == session.user_id));
+        assert!(matches!(cloned_res, Err(AppError::LoginError)));
+    }
+}
@@ -0,0 +1,57 @@
+use crate::{
+    errors::AppError,
+    providers::{
+        HashFuncProvider,
+        IdProvider,
+        WebauthnProvider,
+    },
+    app::commands::{
+        PASSKEY_REGISTRATION_CHALLENGE_KIND,
+        RecoveryCodes,
+        PasskeyDao,
+        RecoveryCodeDao,
+        regenerate_recovery_codes::generate_missing_recovery_codes,
+    },
+};
+
+pub struct FinishPasskeyRegistrationCommand<W, E, H, R>
+where
+    W: WebauthnProvider,
+    E: IdProvider,
+    H: HashFuncProvider,
+    R: PasskeyDao + RecoveryCodeDao,
+{
+    webauthn_provider: W,
+    recovery_code_generator: E,
+    hash_func_provider: H,
+    repo: R,
+}
+
+impl<W, E, H, R> FinishPasskeyRegistrationCommand<W, E, H, R>
+where
+    W: WebauthnProvider,
+    E: IdProvider,
+    H: HashFuncProvider,
+    R: PasskeyDao + RecoveryCodeDao,
+{
+    pub fn new(webauthn_provider: W, recovery_code_generator: E, hash_func_provider: H, repo: R) -> Self {
+        Self { webauthn_provider, recovery_code_generator, hash_func_provider, repo }
+    }
+
+    pub async fn call(&self, user_id: uuid::Uuid, client_data_json: String, attestation_object: String, name: Option<String>) -> Result<RecoveryCodes, AppError> {
+        let registration = self.webauthn_provider.verify_registration(client_data_json, attestation_object)?;
+
+        let challenge = self.repo.use_webauthn_challenge(PASSKEY_REGISTRATION_CHALLENGE_KIND.to_string(), registration.challenge).await?;
+        if challenge.is_none_or(|challenge| challenge.user_id != Some(user_id)) {
+            return Err(AppError::InvalidToken);
+        }
+
+        let name = name.map(|name| name.trim().chars().take(255).collect()).filter(|name: &String| !name.is_empty());
+        if !self.repo.create_passkey(user_id, registration.credential_id, registration.public_key, registration.sign_count, name).await? {
+            return Err(AppError::InvalidPasskey);
+        }
+
+        // ключ включает второй фактор при входе по паролю, поэтому нужны и коды восстановления
+        generate_missing_recovery_codes(&self.recovery_code_generator, &self.hash_func_provider, &self.repo, user_id).await
+    }
+}
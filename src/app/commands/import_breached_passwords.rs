@@ -0,0 +1,142 @@
+use crate::{
+    errors::AppError,
+    app::{
+        BreachedPasswordSuffix,
+        commands::{
+            ImportBreachedPasswordsDao,
+            password_policy::BREACH_RANGE_PREFIX_LENGTH,
+        },
+    },
+};
+
+pub struct ImportBreachedPasswordsCommand<R>
+where
+    R: ImportBreachedPasswordsDao,
+{
+    repo: R,
+}
+
+impl<R> ImportBreachedPasswordsCommand<R>
+where
+    R: ImportBreachedPasswordsDao,
+{
+    pub fn new(repo: R) -> Self {
+        Self { repo }
+    }
+
+    // каталог в формате выгрузки Have I Been Pwned: файл {PREFIX}.txt на каждый диапазон, возвращает число загруженных диапазонов
+    pub async fn call(&self, range_dir: std::path::PathBuf) -> Result<usize, AppError> {
+        let mut entries = match tokio::fs::read_dir(&range_dir).await {
+            Ok(entries) => entries,
+            Err(_) => return Err(AppError::NotFound),
+        };
+
+        let mut imported = 0;
+        while let Some(entry) = entries.next_entry().await.map_err(|_| AppError::UnknownError)? {
+            let path = entry.path();
+            let prefix = match range_prefix(&path) {
+                Some(prefix) => prefix,
+                None => continue,
+            };
+            let contents = match tokio::fs::read_to_string(&path).await {
+                Ok(contents) => contents,
+                Err(_) => return Err(AppError::UnknownError),
+            };
+            // диапазон заменяется целиком, повторный импорт новой выгрузки безопасен
+            self.repo.replace_breached_password_range(prefix, parse_breached_password_range(&contents)).await?;
+            imported += 1;
+        }
+
+        Ok(imported)
+    }
+}
+
+fn range_prefix(path: &std::path::Path) -> Option<String> {
+    if path.extension().is_none_or(|extension| extension != "txt") {
+        return None;
+    }
+    let prefix = path.file_stem()?.to_str()?;
+    if prefix.len() != BREACH_RANGE_PREFIX_LENGTH || !prefix.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    Some(prefix.to_uppercase())
+}
+
+// строки вида SUFFIX:COUNT, хвосты с нулевым счётчиком - это набивка против анализа размера ответа
+pub fn parse_breached_password_range(contents: &str) -> Vec<BreachedPasswordSuffix> {
+    contents.lines()
+        .filter_map(|line| {
+            let (suffix, count) = line.trim().split_once(':')?;
+            let count: i64 = count.trim().parse().ok()?;
+            (count > 0).then(|| BreachedPasswordSuffix { suffix: suffix.trim().to_uppercase(), count })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use sha1::Digest;
+    use crate::{
+        errors::AppError,
+        adapters,
+        di::testing::{self, TestApp, TestSettings},
+        app::commands::{
+            Authentication,
+            SessionClient,
+            password_policy::{PasswordPolicy, PasswordWeakness, PasswordWarning, BreachAction},
+        },
+    };
+
+    #[tokio::test]
+    async fn import_breached_passwords_command() {
+        // Given
+        let TestApp { container: _, db_pool, postgres: _postgres } = TestApp::start().await;
+
+        let range_dir = std::env::temp_dir().join(format!("auth-breached-passwords-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&range_dir);
+        std::fs::create_dir_all(&range_dir).unwrap();
+        let digest = format!("{:X}", sha1::Sha1::digest(b"Breached-Password-42"));
+        let (prefix, suffix) = digest.split_at(5);
+        std::fs::write(range_dir.join(format!("{prefix}.txt")), format!("{suffix}:3\r\n{}:0\r\n", "0".repeat(35))).unwrap();
+        std::fs::write(range_dir.join("README.md"), "not a range").unwrap();
+
+        let container_for = |breach_corpus, password_policy| testing::container(&db_pool, TestSettings { password_policy, breach_corpus, ..TestSettings::default() });
+        let postgres_container = container_for(
+            adapters::breach_corpus::BreachCorpus::Postgres(adapters::postgres::UserRepository::new(db_pool.clone())),
+            PasswordPolicy::default(),
+        );
+        let files_container = container_for(adapters::breach_corpus::BreachCorpus::Files(range_dir.clone()), PasswordPolicy::default());
+        let lenient_container = container_for(
+            adapters::breach_corpus::BreachCorpus::Files(range_dir.clone()),
+            PasswordPolicy { breach_threshold: 5, ..PasswordPolicy::default() },
+        );
+        let warning_container = container_for(
+            adapters::breach_corpus::BreachCorpus::Files(range_dir.clone()),
+            PasswordPolicy { breach_action: BreachAction::Warn, ..PasswordPolicy::default() },
+        );
+
+        // When
+        let not_imported_res = postgres_container.register_user_command.call("user0".to_string(), "Breached-Password-42".to_string()).await;
+        let imported = postgres_container.import_breached_passwords_command.call(range_dir.clone()).await.unwrap();
+        let postgres_res = postgres_container.register_user_command.call("user1".to_string(), "Breached-Password-42".to_string()).await;
+        let files_res = files_container.register_user_command.call("user2".to_string(), "Breached-Password-42".to_string()).await;
+        let lenient_res = lenient_container.register_user_command.call("user3".to_string(), "Breached-Password-42".to_string()).await;
+        let warning_res = warning_container.register_user_command.call("user4".to_string(), "Breached-Password-42".to_string()).await;
+        warning_container.register_user_command.call("user5".to_string(), "Qwerty123!".to_string()).await.unwrap();
+        let Authentication::Authenticated(session) = warning_container.authenticate_user_command.call("user5".to_string(), "Qwerty123!".to_string(), SessionClient::default()).await.unwrap() else { panic!("MFA is not enabled") };
+        let change_warning_res = warning_container.change_password_command.call(session.user_id, "Qwerty123!".to_string(), "Breached-Password-42".to_string()).await;
+        let stored_count: i64 = sqlx::query_scalar("SELECT COUNT(1) FROM breached_passwords").fetch_one(&db_pool).await.unwrap();
+
+        // Then
+        assert!(not_imported_res.is_ok_and(|warnings| warnings.is_empty()));
+        assert_eq!(imported, 1);
+        assert_eq!(stored_count, 1);
+        assert!(matches!(postgres_res, Err(AppError::WeakPassword(reasons)) if reasons == vec![PasswordWeakness::Breached]));
+        assert!(matches!(files_res, Err(AppError::WeakPassword(reasons)) if reasons == vec![PasswordWeakness::Breached]));
+        assert!(lenient_res.is_ok());
+        assert!(matches!(warning_res, Ok(warnings) if warnings == vec![PasswordWarning::Breached]));
+        assert!(matches!(change_warning_res, Ok(warnings) if warnings == vec![PasswordWarning::Breached]));
+        let _ = std::fs::remove_dir_all(&range_dir);
+    }
+}
@@ -0,0 +1,252 @@
+use std::io::BufRead;
+use crate::{
+    errors::AppError,
+    providers::HashVerifierProvider,
+    app::commands::{
+        USERNAME_CREDENTIAL_KIND,
+        ImportedUser,
+        ImportUsersDao,
+        normalize_login,
+    },
+};
+
+pub const IMPORT_USERS_BATCH_SIZE: usize = 1000;
+// пачка пишется одним запросом, поэтому значение длиннее VARCHAR(255) отклоняется до базы, чтобы не сорвать всю пачку
+const IMPORTED_FIELD_MAX_LENGTH: usize = 255;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImportFormat {
+    Csv,
+    JsonLines,
+}
+
+impl ImportFormat {
+    pub fn from_path(path: &std::path::Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_lowercase().as_str() {
+            "csv" => Some(Self::Csv),
+            "jsonl" | "ndjson" => Some(Self::JsonLines),
+            _ => None,
+        }
+    }
+}
+
+// row - номер строки в файле, login - как в файле, если строку удалось разобрать
+#[derive(Debug)]
+pub struct ImportFailure {
+    pub row: usize,
+    pub login: Option<String>,
+    pub error: AppError,
+}
+
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    pub imported: usize,
+    pub failures: Vec<ImportFailure>,
+}
+
+type ImportRecords = Box<dyn Iterator<Item = (usize, Result<ImportedUser, AppError>)> + Send>;
+
+pub struct ImportUsersCommand<V, R>
+where
+    V: HashVerifierProvider,
+    R: ImportUsersDao,
+{
+    hash_verifier_provider: V,
+    repo: R,
+}
+
+impl<V, R> ImportUsersCommand<V, R>
+where
+    V: HashVerifierProvider,
+    R: ImportUsersDao,
+{
+    pub fn new(hash_verifier_provider: V, repo: R) -> Self {
+        Self { hash_verifier_provider, repo }
+    }
+
+    // ошибки строк собираются в отчёт, ошибка базы прерывает импорт, уже записанные пачки остаются
+    pub async fn call<S: std::io::Read + Send + 'static>(&self, source: S, format: ImportFormat, dry_run: bool) -> Result<ImportReport, AppError> {
+        let mut report = ImportReport::default();
+        // в пробном режиме пачки откатываются, поэтому повторы внутри файла ловим сами
+        let mut seen_logins = std::collections::HashSet::new();
+        let mut batch = Vec::with_capacity(IMPORT_USERS_BATCH_SIZE);
+
+        for (row, record) in read_records(source, format)? {
+            let user = match record {
+                Ok(user) => user,
+                Err(error) => {
+                    report.failures.push(ImportFailure { row, login: None, error });
+                    continue;
+                },
+            };
+            let login = user.login.clone();
+            let user = match self.validate(user) {
+                Ok(user) => user,
+                Err(error) => {
+                    report.failures.push(ImportFailure { row, login: Some(login), error });
+                    continue;
+                },
+            };
+            if !seen_logins.insert(user.login.clone()) {
+                report.failures.push(ImportFailure { row, login: Some(login), error: AppError::UsernameIsTaken });
+                continue;
+            }
+
+            batch.push((row, login, user));
+            if batch.len() == IMPORT_USERS_BATCH_SIZE {
+                self.import_batch(std::mem::take(&mut batch), dry_run, &mut report).await?;
+            }
+        }
+        if !batch.is_empty() {
+            self.import_batch(batch, dry_run, &mut report).await?;
+        }
+        // отказы базы приходят после отказов разбора той же пачки
+        report.failures.sort_by_key(|failure| failure.row);
+
+        Ok(report)
+    }
+
+    fn validate(&self, mut user: ImportedUser) -> Result<ImportedUser, AppError> {
+        let (kind, login) = match normalize_login(&user.login) {
+            Some(normalized) => normalized,
+            None => return Err(AppError::InvalidLogin),
+        };
+        if user.kind.as_deref().is_some_and(|provided| provided != kind) {
+            return Err(AppError::InvalidLogin);
+        }
+        if user.gender.as_deref().is_some_and(|gender| !matches!(gender, "female" | "male")) {
+            return Err(AppError::MalformedRecord);
+        }
+        let is_too_long = [Some(&login), user.first_name.as_ref(), user.middle_name.as_ref(), user.last_name.as_ref(), Some(&user.password_digest)]
+            .into_iter()
+            .flatten()
+            .any(|value| value.chars().count() > IMPORTED_FIELD_MAX_LENGTH);
+        if is_too_long {
+            return Err(AppError::MalformedRecord);
+        }
+        if !self.hash_verifier_provider.supports(&user.password_digest) {
+            return Err(AppError::UnsupportedPasswordDigest);
+        }
+
+        // имя пользователя подтверждать нечем, как и при регистрации
+        user.confirmed = Some(user.confirmed.unwrap_or(false) || kind == USERNAME_CREDENTIAL_KIND);
+        user.kind = Some(kind.to_string());
+        user.login = login;
+        Ok(user)
+    }
+
+    async fn import_batch(&self, batch: Vec<(usize, String, ImportedUser)>, dry_run: bool, report: &mut ImportReport) -> Result<(), AppError> {
+        let (rows, users): (Vec<_>, Vec<_>) = batch.into_iter().map(|(row, login, user)| ((row, login), user)).unzip();
+        let results = self.repo.import_users(users, dry_run).await?;
+
+        for ((row, login), result) in rows.into_iter().zip(results) {
+            match result {
+                Ok(()) => report.imported += 1,
+                Err(error) => report.failures.push(ImportFailure { row, login: Some(login), error }),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn read_records<S: std::io::Read + Send + 'static>(source: S, format: ImportFormat) -> Result<ImportRecords, AppError> {
+    match format {
+        ImportFormat::Csv => {
+            let mut reader = csv::ReaderBuilder::new().trim(csv::Trim::All).from_reader(source);
+            let headers = match reader.headers() {
+                Ok(headers) => headers.clone(),
+                Err(_) => return Err(AppError::MalformedRecord),
+            };
+            // первая строка - заголовок
+            Ok(Box::new(reader.into_records().enumerate().map(move |(index, record)| {
+                let row = record.as_ref().ok().and_then(csv::StringRecord::position).map_or(index + 2, |position| usize::try_from(position.line()).unwrap_or(index + 2));
+                let user = record.and_then(|record| record.deserialize(Some(&headers))).map_err(|_| AppError::MalformedRecord);
+                (row, user)
+            })))
+        },
+        ImportFormat::JsonLines => Ok(Box::new(std::io::BufReader::new(source).lines().enumerate()
+            .filter(|(_, line)| line.as_ref().is_err() || line.as_ref().is_ok_and(|line| !line.trim().is_empty()))
+            .map(|(index, line)| {
+                let user = line.ok().and_then(|line| serde_json::from_str(&line).ok()).ok_or(AppError::MalformedRecord);
+                (index + 1, user)
+            }))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        errors::AppError,
+        di::testing::TestApp,
+        app::commands::{
+            Authentication,
+            SessionClient,
+            import_users::ImportFormat,
+        },
+    };
+
+    #[tokio::test]
+    async fn import_users_command() {
+        // Given
+        let TestApp { container, db_pool, postgres: _postgres } = TestApp::start().await;
+        container.register_user_command.call("taken0".to_string(), "Qwerty123!".to_string()).await.unwrap();
+        let bcrypt_digest = bcrypt::hash("Imported-Password-1", 4).unwrap();
+        let csv = format!(
+            "login,kind,confirmed,first_name,middle_name,last_name,birthdate,gender,password_digest\n\
+             User1@Example.com,email,true,Ada,,Lovelace,1815-12-10,female,{bcrypt_digest}\n\
+             taken0,,false,,,,,,{bcrypt_digest}\n\
+             user2,,false,,,,,,md5$salt$digest\n\
+             user1@example.com,,true,,,,,,{bcrypt_digest}\n\
+             user3,,,,,,not-a-date,,{bcrypt_digest}\n\
+             Grace@Example.com,,,Grace,,Hopper,1906-12-09,female,{bcrypt_digest}\n\
+             user6,,,{long_name},,,,,{bcrypt_digest}\n",
+            long_name = "a".repeat(256),
+        );
+        let json_lines = format!(
+            "{{\"login\":\"user4\",\"password_digest\":\"{bcrypt_digest}\"}}\n\n{{\"login\":\"user5\"}}\n"
+        );
+        let users_count = || async { sqlx::query_scalar::<_, i64>("SELECT COUNT(1) FROM users").fetch_one(&db_pool).await.unwrap() };
+
+        // When
+        let dry_run_report = container.import_users_command.call(std::io::Cursor::new(csv.clone()), ImportFormat::Csv, true).await.unwrap();
+        let users_after_dry_run = users_count().await;
+        let csv_report = container.import_users_command.call(std::io::Cursor::new(csv), ImportFormat::Csv, false).await.unwrap();
+        let json_lines_report = container.import_users_command.call(std::io::Cursor::new(json_lines), ImportFormat::JsonLines, false).await.unwrap();
+        let users_after_import = users_count().await;
+        let login_res = container.authenticate_user_command.call("user1@example.com".to_string(), "Imported-Password-1".to_string(), SessionClient::default()).await;
+        let upgraded_digest: String = sqlx::query_scalar("SELECT password_digest FROM user_passwords up JOIN user_credentials uc ON uc.user_id = up.user_id WHERE uc.login = 'user1@example.com'")
+            .fetch_one(&db_pool)
+            .await
+            .unwrap();
+        let last_name: Option<String> = sqlx::query_scalar("SELECT u.last_name FROM users u JOIN user_credentials uc ON uc.user_id = u.id WHERE uc.login = 'user1@example.com'")
+            .fetch_one(&db_pool)
+            .await
+            .unwrap();
+        let (is_unconfirmed, birthdate): (bool, Option<chrono::NaiveDate>) = sqlx::query_as("SELECT uc.confirmed_at IS NULL, u.birthdate FROM users u JOIN user_credentials uc ON uc.user_id = u.id WHERE uc.login = 'grace@example.com'")
+            .fetch_one(&db_pool)
+            .await
+            .unwrap();
+
+        // Then
+        assert_eq!(dry_run_report.imported, 2);
+        assert_eq!(users_after_dry_run, 1);
+        assert_eq!(csv_report.imported, 2);
+        let failures: Vec<_> = csv_report.failures.iter().map(|failure| (failure.row, failure.login.as_deref())).collect();
+        assert_eq!(failures, vec![(3, Some("taken0")), (4, Some("user2")), (5, Some("user1@example.com")), (6, None), (8, Some("user6"))]);
+        assert!(matches!(csv_report.failures[0].error, AppError::UsernameIsTaken));
+        assert!(matches!(csv_report.failures[1].error, AppError::UnsupportedPasswordDigest));
+        assert!(matches!(csv_report.failures[2].error, AppError::UsernameIsTaken));
+        assert!(matches!(csv_report.failures[3].error, AppError::MalformedRecord));
+        assert!(matches!(csv_report.failures[4].error, AppError::MalformedRecord));
+        assert_eq!(json_lines_report.imported, 1);
+        assert_eq!(json_lines_report.failures.len(), 1);
+        assert_eq!(json_lines_report.failures[0].row, 3);
+        assert_eq!(users_after_import, 4);
+        assert!(matches!(login_res, Ok(Authentication::Authenticated(_))));
+        assert!(upgraded_digest.starts_with("$argon2id$"));
+        assert_eq!(last_name.as_deref(), Some("Lovelace"));
+        assert!(is_unconfirmed);
+        assert_eq!(birthdate, chrono::NaiveDate::from_ymd_opt(1906, 12, 9));
+    }
+}
@@ -0,0 +1,259 @@
+use sha1::Digest;
+use crate::{
+    errors::AppError,
+    providers::HashVerifierProvider,
+    app::commands::{
+        BreachedPasswordDao,
+        ChangePasswordDao,
+    },
+};
+
+// самые частые пароли из утечек, энтропия по алфавиту их не ловит
+const COMMON_PASSWORDS: [&str; 16] = [
+    "password", "qwerty", "letmein", "welcome", "admin", "iloveyou", "monkey", "dragon",
+    "football", "baseball", "abc123", "111111", "123456", "sunshine", "princess", "trustno1",
+];
+
+// логин короче этого не ищем в пароле, иначе ложные срабатывания
+const MIN_LOGIN_LENGTH_TO_MATCH: usize = 3;
+
+// длина префикса SHA-1, по которому запрашивается диапазон, как в Have I Been Pwned
+pub const BREACH_RANGE_PREFIX_LENGTH: usize = 5;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PasswordWeakness {
+    TooShort,
+    TooLong,
+    TooGuessable,
+    ContainsLogin,
+    Breached,
+    RecentlyUsed,
+}
+
+// пароль принят, но пользователю стоит предложить сменить его
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PasswordWarning {
+    Breached,
+}
+
+#[derive(Clone, Copy, Debug, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BreachAction {
+    Reject,
+    Warn,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    // ограничивает стоимость Argon2 на длинных строках
+    pub max_length: usize,
+    // 0..=4, как у zxcvbn
+    pub min_score: u8,
+    // 0 - корпус утечек не проверяется
+    pub breach_threshold: i64,
+    pub breach_action: BreachAction,
+    // сколько последних паролей, включая текущий, нельзя выбрать снова; 0 - история не ведётся
+    pub history_size: usize,
+    // 0 - пароль не истекает
+    pub max_age_in_days: u32,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self {
+            min_length: 8,
+            max_length: 128,
+            min_score: 2,
+            breach_threshold: 1,
+            breach_action: BreachAction::Reject,
+            history_size: 5,
+            max_age_in_days: 0,
+        }
+    }
+}
+
+impl PasswordPolicy {
+    // срок считается при входе, поэтому новая политика касается и паролей, заданных до неё
+    pub fn is_password_expired(&self, changed_at: chrono::NaiveDateTime) -> bool {
+        if self.max_age_in_days == 0 {
+            return false;
+        }
+
+        changed_at
+            .checked_add_signed(chrono::Duration::days(i64::from(self.max_age_in_days)))
+            .is_none_or(|expires_at| expires_at <= chrono::Utc::now().naive_local())
+    }
+
+    pub fn weaknesses(&self, password: &str, logins: &[String]) -> Vec<PasswordWeakness> {
+        let mut reasons = Vec::new();
+        let length = password.chars().count();
+        if length < self.min_length {
+            reasons.push(PasswordWeakness::TooShort);
+        }
+        if length > self.max_length {
+            reasons.push(PasswordWeakness::TooLong);
+        }
+        if password_score(password) < self.min_score {
+            reasons.push(PasswordWeakness::TooGuessable);
+        }
+        let lowered = password.to_lowercase();
+        let contains_login = logins.iter()
+            .flat_map(|login| {
+                let login = login.to_lowercase();
+                // у почты пароль чаще всего повторяет имя ящика
+                let local_part = login.split_once('@').map(|(local_part, _)| local_part.to_string());
+                std::iter::once(login).chain(local_part)
+            })
+            .any(|login| login.chars().count() >= MIN_LOGIN_LENGTH_TO_MATCH && lowered.contains(&login));
+        if contains_login {
+            reasons.push(PasswordWeakness::ContainsLogin);
+        }
+
+        reasons
+    }
+}
+
+// новый пароль при регистрации, смене и сбросе: правила политики плюс корпус утечек.
+// Предупреждения уходят клиенту в ответе на принятый пароль
+pub async fn validate_new_password<B: BreachedPasswordDao>(
+    password_policy: &PasswordPolicy,
+    breach_corpus: &B,
+    password: &str,
+    logins: &[String],
+) -> Result<Vec<PasswordWarning>, AppError> {
+    let mut reasons = password_policy.weaknesses(password, logins);
+    let mut warnings = Vec::new();
+    if password_policy.breach_threshold > 0 {
+        let breach_count = find_breach_count(breach_corpus, password).await?;
+        if breach_count >= password_policy.breach_threshold {
+            match password_policy.breach_action {
+                BreachAction::Reject => reasons.push(PasswordWeakness::Breached),
+                BreachAction::Warn => warnings.push(PasswordWarning::Breached),
+            }
+        }
+    }
+
+    if reasons.is_empty() { Ok(warnings) } else { Err(AppError::WeakPassword(reasons)) }
+}
+
+// сверяем с текущим и предыдущими дайджестами, сами пароли нигде не хранятся
+pub async fn ensure_not_recently_used<V: HashVerifierProvider, R: ChangePasswordDao>(
+    password_policy: &PasswordPolicy,
+    hash_verifier_provider: &V,
+    repo: &R,
+    user_id: uuid::Uuid,
+    current_password_digest: String,
+    password: &str,
+) -> Result<(), AppError> {
+    if password_policy.history_size == 0 {
+        return Ok(());
+    }
+    let mut digests = vec![current_password_digest];
+    digests.extend(repo.list_password_history(user_id, password_policy.history_size - 1).await?);
+
+    let is_recently_used = digests.into_iter()
+        .any(|digest| hash_verifier_provider.provide(password.to_string(), digest).is_confirmed);
+    if is_recently_used {
+        return Err(AppError::WeakPassword(vec![PasswordWeakness::RecentlyUsed]));
+    }
+
+    Ok(())
+}
+
+// наружу уходит только префикс хеша, совпадение ищем по хвосту локально
+pub async fn find_breach_count<B: BreachedPasswordDao>(breach_corpus: &B, password: &str) -> Result<i64, AppError> {
+    let digest = format!("{:X}", sha1::Sha1::digest(password.as_bytes()));
+    let (prefix, suffix) = digest.split_at(BREACH_RANGE_PREFIX_LENGTH);
+    let range = breach_corpus.find_breached_password_range(prefix.to_string()).await?;
+
+    Ok(range.iter().find(|entry| entry.suffix.eq_ignore_ascii_case(suffix)).map_or(0, |entry| entry.count))
+}
+
+// грубая оценка энтропии: мощность алфавита на длину, где повторы и последовательности почти ничего не добавляют
+pub fn password_score(password: &str) -> u8 {
+    let lowered = password.to_lowercase();
+    if COMMON_PASSWORDS.contains(&lowered.as_str()) {
+        return 0;
+    }
+
+    let mut pool = 0;
+    if password.chars().any(|c| c.is_ascii_lowercase()) {
+        pool += 26;
+    }
+    if password.chars().any(|c| c.is_ascii_uppercase()) {
+        pool += 26;
+    }
+    if password.chars().any(|c| c.is_ascii_digit()) {
+        pool += 10;
+    }
+    if password.chars().any(|c| c.is_ascii() && !c.is_ascii_alphanumeric()) {
+        pool += 33;
+    }
+    if !password.is_ascii() {
+        pool += 100;
+    }
+    if pool == 0 {
+        return 0;
+    }
+
+    let mut effective_length = 0.0;
+    let mut previous: Option<char> = None;
+    for c in lowered.chars() {
+        let continues_run = previous.is_some_and(|previous| (i64::from(u32::from(c)) - i64::from(u32::from(previous))).abs() <= 1);
+        effective_length += if continues_run { 0.25 } else { 1.0 };
+        previous = Some(c);
+    }
+
+    let bits = effective_length * f64::from(pool).log2();
+    match bits {
+        bits if bits < 28.0 => 0,
+        bits if bits < 36.0 => 1,
+        bits if bits < 60.0 => 2,
+        bits if bits < 80.0 => 3,
+        _ => 4,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn password_score_penalizes_common_and_repetitive_passwords() {
+        // Given
+        let common = "password";
+        let sequence = "123123";
+        let repeated = "aaaaaaaaaaaa";
+        let mixed = "Qwerty123!";
+        let passphrase = "correct horse battery staple";
+
+        // When
+        let scores = [common, sequence, repeated, mixed, passphrase].map(password_score);
+
+        // Then
+        assert_eq!(scores[0], 0);
+        assert_eq!(scores[1], 0);
+        assert_eq!(scores[2], 0);
+        assert!(scores[3] >= 2);
+        assert_eq!(scores[4], 4);
+    }
+
+    #[test]
+    fn password_policy_reports_every_reason() {
+        // Given
+        let policy = PasswordPolicy::default();
+
+        // When
+        let weak_reasons = policy.weaknesses("user0", &["user0@example.com".to_string()]);
+        let long_reasons = PasswordPolicy { max_length: 16, ..PasswordPolicy::default() }.weaknesses("correct horse battery staple", &[]);
+        let strong_reasons = policy.weaknesses("Qwerty123!", &["user0@example.com".to_string()]);
+
+        // Then
+        assert_eq!(weak_reasons, vec![PasswordWeakness::TooShort, PasswordWeakness::TooGuessable, PasswordWeakness::ContainsLogin]);
+        assert_eq!(long_reasons, vec![PasswordWeakness::TooLong]);
+        assert!(strong_reasons.is_empty());
+    }
+}
@@ -0,0 +1,24 @@
+use crate::{
+    errors::AppError,
+    providers::KeyRingProvider,
+};
+
+pub struct PromoteSigningKeyCommand<G>
+where
+    G: KeyRingProvider,
+{
+    key_ring_provider: G,
+}
+
+impl<G> PromoteSigningKeyCommand<G>
+where
+    G: KeyRingProvider,
+{
+    pub fn new(key_ring_provider: G) -> Self {
+        Self { key_ring_provider }
+    }
+
+    pub fn call(&self, kid: String) -> Result<(), AppError> {
+        self.key_ring_provider.promote(kid.trim().to_string())
+    }
+}
@@ -0,0 +1,106 @@
+use crate::{
+    errors::AppError,
+    providers::{
+        IdProvider,
+        TokenEncoderProvider,
+    },
+    app::commands::{
+        Authentication,
+        SessionPolicy,
+        SessionClient,
+        AuthenticateUserDao,
+        PasswordResetDao,
+        TotpDao,
+        MfaChallengeDao,
+        PasskeyDao,
+        RecoveryCodeDao,
+        MagicLinkDao,
+        authenticate_user::complete_authentication,
+    },
+};
+
+pub struct RedeemMagicLinkCommand<I, T, R>
+where
+    I: IdProvider,
+    T: TokenEncoderProvider,
+    R: AuthenticateUserDao + PasswordResetDao + TotpDao + MfaChallengeDao + PasskeyDao + RecoveryCodeDao + MagicLinkDao,
+{
+    refresh_token_generator: I,
+    access_token_provider: T,
+    repo: R,
+    session_policy: SessionPolicy,
+}
+
+impl<I, T, R> RedeemMagicLinkCommand<I, T, R>
+where
+    I: IdProvider,
+    T: TokenEncoderProvider,
+    R: AuthenticateUserDao + PasswordResetDao + TotpDao + MfaChallengeDao + PasskeyDao + RecoveryCodeDao + MagicLinkDao,
+{
+    pub fn new(refresh_token_generator: I, access_token_provider: T, repo: R, session_policy: SessionPolicy) -> Self {
+        Self {
+            refresh_token_generator,
+            access_token_provider,
+            repo,
+            session_policy,
+        }
+    }
+
+    // ссылка заменяет только пароль, подключённый второй фактор всё равно потребуется
+    pub async fn call(&self, token: String, client: SessionClient) -> Result<Authentication, AppError> {
+        let magic_link = match self.repo.use_magic_link(token.trim().to_string()).await? {
+            Some(magic_link) => magic_link,
+            None => return Err(AppError::InvalidToken),
+        };
+
+        complete_authentication(
+            &self.refresh_token_generator,
+            &self.access_token_provider,
+            &self.repo,
+            magic_link.user_credential_id,
+            magic_link.user_id,
+            // пароль ссылкой не предъявлялся
+            false,
+            self.session_policy,
+            client,
+        ).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::di::testing::{TestApp, TestSettings};
+
+    #[tokio::test]
+    async fn redeem_magic_link_command() {
+        // Given
+        let outbox_path = std::env::temp_dir().join(format!("auth-magic-link-{}.txt", std::process::id()));
+        let _ = std::fs::remove_file(&outbox_path);
+
+        let TestApp { container, db_pool: _, postgres: _postgres } = TestApp::start_with(TestSettings {
+            mailer_outbox_path: outbox_path.clone(),
+            ..TestSettings::default()
+        }).await;
+        container.register_user_command.call("User0@Example.com".to_string(), "Qwerty123!".to_string()).await.unwrap();
+        let outbox = std::fs::read_to_string(&outbox_path).unwrap();
+        let confirmation_token = outbox.lines().find_map(|line| line.strip_prefix("Your confirmation code: ")).unwrap().to_string();
+        container.confirm_credential_command.call(confirmation_token).await.unwrap();
+        let unknown_res = container.request_magic_link_command.call("nobody@example.com".to_string()).await;
+        container.request_magic_link_command.call("User0@Example.com".to_string()).await.unwrap();
+        let outbox = std::fs::read_to_string(&outbox_path).unwrap();
+        let link = outbox.lines().find_map(|line| line.strip_prefix("Your sign-in link: ")).unwrap().to_string();
+        let token = link.strip_prefix("http://localhost:5000/magic-link?token=").unwrap().to_string();
+
+        // When
+        let first_res = container.redeem_magic_link_command.call(token.clone(), SessionClient::default()).await;
+        let second_res = container.redeem_magic_link_command.call(token, SessionClient::default()).await;
+
+        // Then
+        assert!(unknown_res.is_ok());
+        assert_eq!(outbox.matches("Your sign-in link: ").count(), 1);
+        let Authentication::Authenticated(session) = first_res.unwrap() else { panic!("MFA is not enabled") };
+        assert!(container.refresh_session_command.call(session.refresh_token, SessionClient::default()).await.is_ok());
+        assert!(matches!(second_res, Err(AppError::InvalidToken)));
+    }
+}
@@ -6,8 +6,10 @@ use crate::{
     },
     app::commands::{
         Session,
-        UserCredential,
+        SessionClient,
+        SessionPolicy,
         RefreshSessionDao,
+        DestroySessionDao,
     },
 };
 
@@ -15,37 +17,52 @@ pub struct RefreshSessionCommand<I, T, R>
 where
     I: IdProvider,
     T: TokenEncoderProvider,
-    R: RefreshSessionDao,
+    R: RefreshSessionDao + DestroySessionDao,
 {
     id_provider: I,
     token_provider: T,
     repo: R,
+    session_policy: SessionPolicy,
 }
 
 #[derive(sqlx::FromRow)]
 pub struct UserSession {
     pub user_credential_id: uuid::Uuid,
+    pub family_id: uuid::Uuid,
+    pub authenticated_at: chrono::NaiveDateTime,
+    // время последнего обмена токена
+    pub created_at: chrono::NaiveDateTime,
 }
 
 impl<I, T, R> RefreshSessionCommand<I, T, R> 
 where
     I: IdProvider,
     T: TokenEncoderProvider,
-    R: RefreshSessionDao
+    R: RefreshSessionDao + DestroySessionDao,
 {
-    pub fn new(id_provider: I, token_provider: T, repo: R) -> Self {
-        Self { id_provider, token_provider, repo }
+    pub fn new(id_provider: I, token_provider: T, repo: R, session_policy: SessionPolicy) -> Self {
+        Self { id_provider, token_provider, repo, session_policy }
     }
 
-    pub async fn call(&self, old_refresh_token: String) -> Result<Session, AppError> {
+    pub async fn call(&self, old_refresh_token: String, client: SessionClient) -> Result<Session, AppError> {
+        let some_session_or_none = match self.repo.find_active_session(old_refresh_token.clone()).await {
+            Ok(some_session_or_none) => some_session_or_none,
+            Err(_) => return Err(AppError::UnknownDatabaseError),
+        };
+        if let Some(session) = some_session_or_none && self.is_expired(&session) {
+            self.repo.destroy_session(old_refresh_token).await?;
+            return Err(AppError::SessionExpired);
+        }
+
         let new_refresh_token = match self.id_provider.provide() {
             Some(token) => token,
             None => return Err(AppError::LoginRequired),
         };
 
-        let result_some_credential_or_none = self.repo.refresh_session(old_refresh_token, new_refresh_token.clone()).await;
+        let result_some_credential_or_none = self.repo.refresh_session(old_refresh_token, new_refresh_token.clone(), client).await;
         let some_credential_or_none = match result_some_credential_or_none {
             Ok(some_credential_or_none) => some_credential_or_none,
+            Err(AppError::RefreshTokenReused) => return Err(AppError::RefreshTokenReused),
             Err(_) => return Err(AppError::UnknownDatabaseError),
         };
         let credential = match some_credential_or_none {
@@ -62,4 +79,84 @@ where
         let user_id = credential.user_id;
         Ok(Session { user_id, refresh_token, access_token })
     }
+
+    fn is_expired(&self, session: &UserSession) -> bool {
+        let now = chrono::Utc::now().naive_local();
+        let is_idle = self.session_policy.idle_timeout_in_days > 0 &&
+            session.created_at + chrono::Duration::days(self.session_policy.idle_timeout_in_days.into()) < now;
+        let is_outlived = self.session_policy.absolute_lifetime_in_days > 0 &&
+            session.authenticated_at + chrono::Duration::days(self.session_policy.absolute_lifetime_in_days.into()) < now;
+
+        is_idle || is_outlived
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        di::testing::{TestApp, TestSettings},
+        app::commands::{
+            Authentication,
+            SessionPolicy,
+            SessionClient,
+        },
+    };
+
+    #[tokio::test]
+    async fn refresh_session_command_with_reused_token() {
+        // Given
+        let TestApp { container, db_pool: _, postgres: _postgres } = TestApp::start().await;
+        container.register_user_command.call("username0".to_string(), "Qwerty123!".to_string()).await.unwrap();
+        let Authentication::Authenticated(session) = container.authenticate_user_command.call("username0".to_string(), "Qwerty123!".to_string(), SessionClient::default()).await.unwrap() else { panic!("MFA is not enabled") };
+
+        // When
+        let rotated_session = container.refresh_session_command.call(session.refresh_token.clone(), SessionClient::default()).await.unwrap();
+        let reuse_res = container.refresh_session_command.call(session.refresh_token, SessionClient::default()).await;
+        let refresh_res = container.refresh_session_command.call(rotated_session.refresh_token, SessionClient::default()).await;
+
+        // Then
+        assert!(matches!(reuse_res, Err(AppError::RefreshTokenReused)));
+        assert!(matches!(refresh_res, Err(AppError::LoginRequired)));
+    }
+
+    #[tokio::test]
+    async fn refresh_session_command_after_idle_timeout() {
+        // Given
+        let TestApp { container, db_pool, postgres: _postgres } = TestApp::start_with(TestSettings {
+            session_policy: SessionPolicy { idle_timeout_in_days: 14, absolute_lifetime_in_days: 30, ..SessionPolicy::default() },
+            ..TestSettings::default()
+        }).await;
+        container.register_user_command.call("username0".to_string(), "Qwerty123!".to_string()).await.unwrap();
+        let Authentication::Authenticated(session) = container.authenticate_user_command.call("username0".to_string(), "Qwerty123!".to_string(), SessionClient::default()).await.unwrap() else { panic!("MFA is not enabled") };
+        sqlx::query("UPDATE user_sessions SET created_at = created_at - INTERVAL '15 days'").execute(&db_pool).await.unwrap();
+
+        // When
+        let expired_res = container.refresh_session_command.call(session.refresh_token.clone(), SessionClient::default()).await;
+        let repeated_res = container.refresh_session_command.call(session.refresh_token, SessionClient::default()).await;
+
+        // Then
+        assert!(matches!(expired_res, Err(AppError::SessionExpired)));
+        assert!(matches!(repeated_res, Err(AppError::LoginRequired)));
+    }
+
+    #[tokio::test]
+    async fn refresh_session_command_after_absolute_lifetime() {
+        // Given
+        let TestApp { container, db_pool, postgres: _postgres } = TestApp::start_with(TestSettings {
+            session_policy: SessionPolicy { idle_timeout_in_days: 14, absolute_lifetime_in_days: 30, ..SessionPolicy::default() },
+            ..TestSettings::default()
+        }).await;
+        container.register_user_command.call("username0".to_string(), "Qwerty123!".to_string()).await.unwrap();
+        let Authentication::Authenticated(session) = container.authenticate_user_command.call("username0".to_string(), "Qwerty123!".to_string(), SessionClient::default()).await.unwrap() else { panic!("MFA is not enabled") };
+        sqlx::query("UPDATE user_sessions SET authenticated_at = authenticated_at - INTERVAL '31 days'").execute(&db_pool).await.unwrap();
+
+        // When
+        let expired_res = container.refresh_session_command.call(session.refresh_token.clone(), SessionClient::default()).await;
+        let repeated_res = container.refresh_session_command.call(session.refresh_token, SessionClient::default()).await;
+
+        // Then
+        assert!(matches!(expired_res, Err(AppError::SessionExpired)));
+        assert!(matches!(repeated_res, Err(AppError::LoginRequired)));
+    }
 }
@@ -0,0 +1,168 @@
+use crate::{
+    errors::AppError,
+    providers::{
+        HashFuncProvider,
+        HashVerifierProvider,
+        IdProvider,
+        TotpProvider,
+        recovery_code_generator::RecoveryCodeGeneratorProvider,
+    },
+    app::{
+        queries::FindUserSecretDao,
+        commands::{
+            RECOVERY_CODES_COUNT,
+            RecoveryCodes,
+            Reauthentication,
+            RecoveryCodeDao,
+            TotpDao,
+            PasskeyDao,
+        },
+    },
+};
+
+pub struct RegenerateRecoveryCodesCommand<E, H, V, F, R>
+where
+    E: IdProvider,
+    H: HashFuncProvider,
+    V: HashVerifierProvider,
+    F: TotpProvider,
+    R: FindUserSecretDao + RecoveryCodeDao + TotpDao + PasskeyDao,
+{
+    recovery_code_generator: E,
+    hash_func_provider: H,
+    hash_verifier_provider: V,
+    totp_provider: F,
+    repo: R,
+}
+
+impl<E, H, V, F, R> RegenerateRecoveryCodesCommand<E, H, V, F, R>
+where
+    E: IdProvider,
+    H: HashFuncProvider,
+    V: HashVerifierProvider,
+    F: TotpProvider,
+    R: FindUserSecretDao + RecoveryCodeDao + TotpDao + PasskeyDao,
+{
+    pub fn new(recovery_code_generator: E, hash_func_provider: H, hash_verifier_provider: V, totp_provider: F, repo: R) -> Self {
+        Self { recovery_code_generator, hash_func_provider, hash_verifier_provider, totp_provider, repo }
+    }
+
+    // украденного токена доступа мало: старые коды сгорают, поэтому нужен пароль или код из приложения
+    pub async fn call(&self, user_id: uuid::Uuid, reauthentication: Reauthentication) -> Result<RecoveryCodes, AppError> {
+        // без второго фактора коды восстановления ничего не заменяют
+        let totp_factor = self.repo.find_totp_factor(user_id).await?.filter(|factor| factor.confirmed_at.is_some());
+        if totp_factor.is_none() && self.repo.list_passkey_credential_ids(user_id).await?.is_empty() {
+            return Err(AppError::MfaNotEnabled);
+        }
+
+        let is_reauthenticated = match reauthentication {
+            Reauthentication::Password(password) => match self.repo.find_user_secret_by_user_id(user_id).await {
+                Ok(Some(secret)) => self.hash_verifier_provider.provide(password, secret.password_digest).is_confirmed,
+                Ok(None) => false,
+                Err(_) => return Err(AppError::UnknownDatabaseError),
+            },
+            Reauthentication::TotpCode(code) => match totp_factor {
+                Some(factor) => {
+                    let unix_time = chrono::Utc::now().timestamp().unsigned_abs();
+                    match self.totp_provider.verify(factor.secret, code, unix_time) {
+                        Some(step) => self.repo.use_totp_step(factor.id, step).await?,
+                        None => false,
+                    }
+                },
+                None => false,
+            },
+        };
+        if !is_reauthenticated {
+            return Err(AppError::LoginError);
+        }
+
+        let recovery_codes = generate_recovery_codes(&self.recovery_code_generator, &self.hash_func_provider, &self.repo, user_id).await?;
+
+        Ok(RecoveryCodes { recovery_codes })
+    }
+}
+
+// новый набор целиком заменяет старый, включая неиспользованные коды
+pub async fn generate_recovery_codes<E, H, R>(recovery_code_generator: &E, hash_func_provider: &H, repo: &R, user_id: uuid::Uuid) -> Result<Vec<String>, AppError>
+where
+    E: IdProvider,
+    H: HashFuncProvider,
+    R: RecoveryCodeDao,
+{
+    let mut recovery_codes = Vec::with_capacity(RECOVERY_CODES_COUNT);
+    let mut code_digests = Vec::with_capacity(RECOVERY_CODES_COUNT);
+    for _ in 0..RECOVERY_CODES_COUNT {
+        let recovery_code = match recovery_code_generator.provide() {
+            Some(recovery_code) => recovery_code,
+            None => return Err(AppError::UnknownError),
+        };
+        let code_digest = match hash_func_provider.provide(RecoveryCodeGeneratorProvider::normalize(&recovery_code)) {
+            Some(code_digest) => code_digest,
+            None => return Err(AppError::UnknownError),
+        };
+        recovery_codes.push(recovery_code);
+        code_digests.push(code_digest);
+    }
+    repo.replace_recovery_codes(user_id, code_digests).await?;
+
+    Ok(recovery_codes)
+}
+
+// при подключении первого второго фактора; у кого коды уже есть, получают пустой список
+pub async fn generate_missing_recovery_codes<E, H, R>(recovery_code_generator: &E, hash_func_provider: &H, repo: &R, user_id: uuid::Uuid) -> Result<RecoveryCodes, AppError>
+where
+    E: IdProvider,
+    H: HashFuncProvider,
+    R: RecoveryCodeDao,
+{
+    if !repo.list_recovery_codes(user_id).await?.is_empty() {
+        return Ok(RecoveryCodes { recovery_codes: Vec::new() });
+    }
+    let recovery_codes = generate_recovery_codes(recovery_code_generator, hash_func_provider, repo, user_id).await?;
+
+    Ok(RecoveryCodes { recovery_codes })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        errors::AppError,
+        di::testing::TestApp,
+        providers,
+        providers::TotpProvider,
+        app::commands::{
+            RECOVERY_CODES_COUNT,
+            Authentication,
+            Reauthentication,
+            SessionClient,
+        },
+    };
+
+    #[tokio::test]
+    async fn regenerate_recovery_codes_command() {
+        // Given
+        let TestApp { container, db_pool: _, postgres: _postgres } = TestApp::start().await;
+        container.register_user_command.call("username0".to_string(), "Qwerty123!".to_string()).await.unwrap();
+        let Authentication::Authenticated(session) = container.authenticate_user_command.call("username0".to_string(), "Qwerty123!".to_string(), SessionClient::default()).await.unwrap() else { panic!("MFA is not enabled") };
+        let without_mfa_res = container.regenerate_recovery_codes_command.call(session.user_id, Reauthentication::Password("Qwerty123!".to_string())).await;
+        let enrollment = container.enroll_totp_command.call(session.user_id).await.unwrap();
+        let totp = providers::hmac_totp::HmacTotpProvider::new("auth".to_string());
+        let unix_time = chrono::Utc::now().timestamp().unsigned_abs();
+        let confirmation_code = totp.generate(enrollment.secret.clone(), unix_time).unwrap();
+        container.confirm_totp_command.call(session.user_id, confirmation_code.clone()).await.unwrap();
+        let next_code = totp.generate(enrollment.secret, unix_time + providers::hmac_totp::TOTP_PERIOD_IN_SECONDS).unwrap();
+
+        // When
+        let wrong_password_res = container.regenerate_recovery_codes_command.call(session.user_id, Reauthentication::Password("Asdfgh456?".to_string())).await;
+        let reused_code_res = container.regenerate_recovery_codes_command.call(session.user_id, Reauthentication::TotpCode(confirmation_code)).await;
+        let password_res = container.regenerate_recovery_codes_command.call(session.user_id, Reauthentication::Password("Qwerty123!".to_string())).await;
+        let totp_res = container.regenerate_recovery_codes_command.call(session.user_id, Reauthentication::TotpCode(next_code)).await;
+
+        // Then
+        assert!(matches!(without_mfa_res, Err(AppError::MfaNotEnabled)));
+        assert!(matches!(wrong_password_res, Err(AppError::LoginError)));
+        assert!(matches!(reused_code_res, Err(AppError::LoginError)));
+        assert_eq!(password_res.unwrap().recovery_codes.len(), RECOVERY_CODES_COUNT);
+        assert_eq!(totp_res.unwrap().recovery_codes.len(), RECOVERY_CODES_COUNT);
+    }
+}
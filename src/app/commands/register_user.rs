@@ -1,91 +1,99 @@
 use crate::{
     errors::AppError,
-    providers::HashFuncProvider,
-    app::commands::RegisterUserDao,
+    providers::{
+        HashFuncProvider,
+        IdProvider,
+        MailerProvider,
+        SmsProvider,
+    },
+    app::commands::{
+        USERNAME_CREDENTIAL_KIND,
+        RegisterUserDao,
+        ConfirmCredentialDao,
+        BreachedPasswordDao,
+        normalize_login,
+        password_policy::{PasswordPolicy, PasswordWarning, validate_new_password},
+        request_credential_confirmation::send_credential_confirmation,
+    },
 };
 
-pub struct RegisterUserCommand<H, R>
+pub struct RegisterUserCommand<H, I, O, M, P, R, B>
 where
     H: HashFuncProvider,
-    R: RegisterUserDao,
+    I: IdProvider,
+    O: IdProvider,
+    M: MailerProvider,
+    P: SmsProvider,
+    R: RegisterUserDao + ConfirmCredentialDao,
+    B: BreachedPasswordDao,
 {
     hash_func_provider: H,
+    id_provider: I,
+    otp_provider: O,
+    mailer_provider: M,
+    sms_provider: P,
     repo: R,
+    breach_corpus: B,
+    password_policy: PasswordPolicy,
 }
 
-impl<H, R> RegisterUserCommand<H, R> 
+impl<H, I, O, M, P, R, B> RegisterUserCommand<H, I, O, M, P, R, B> 
 where
     H: HashFuncProvider,
-    R: RegisterUserDao,
+    I: IdProvider,
+    O: IdProvider,
+    M: MailerProvider,
+    P: SmsProvider,
+    R: RegisterUserDao + ConfirmCredentialDao,
+    B: BreachedPasswordDao,
 {
-    pub fn new(hash_func_provider: H, repo: R) -> Self {
-        Self { hash_func_provider, repo }
+    pub fn new(hash_func_provider: H, id_provider: I, otp_provider: O, mailer_provider: M, sms_provider: P, repo: R, breach_corpus: B, password_policy: PasswordPolicy) -> Self {
+        Self { hash_func_provider, id_provider, otp_provider, mailer_provider, sms_provider, repo, breach_corpus, password_policy }
     }
 
-    pub async fn call(&self, username: String, password: String) -> Result<(), AppError> {
+    pub async fn call(&self, login: String, password: String) -> Result<Vec<PasswordWarning>, AppError> {
+        let (login_type, login) = match normalize_login(&login) {
+            Some(normalized) => normalized,
+            None => return Err(AppError::InvalidLogin),
+        };
+        let warnings = validate_new_password(&self.password_policy, &self.breach_corpus, &password, std::slice::from_ref(&login)).await?;
+
         let password_digest = match self.hash_func_provider.provide(password) {
             Some(hash) => hash,
             None => {
                 return Err(AppError::UnknownError);
             }, 
         };
-        let login_type = "username".to_string();
-        self.repo.register_user(login_type, username.trim().to_lowercase(), password_digest).await?;
-
-        Ok(())
+        // имя пользователя подтверждать нечем, а владение почтой и телефоном нужно доказать
+        let is_confirmed = login_type == USERNAME_CREDENTIAL_KIND;
+        let user_credential_id = self.repo.register_user(login_type.to_string(), login.clone(), password_digest, is_confirmed).await?;
+
+        if !is_confirmed {
+            send_credential_confirmation(
+                &self.id_provider,
+                &self.otp_provider,
+                &self.mailer_provider,
+                &self.sms_provider,
+                &self.repo,
+                user_credential_id,
+                login_type,
+                login,
+            ).await?;
+        }
+
+        Ok(warnings)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use testcontainers_modules::{
-        postgres,
-        testcontainers::{
-            ImageExt,
-            runners::AsyncRunner,
-        },
-    };
-    use crate::{
-        di,
-        providers,
-        adapters,
-    };
+    use crate::di::testing::TestApp;
 
     #[tokio::test]
     async fn first_call_register_user_command() {
         // Given
-        let postgres_container = postgres::Postgres::default()
-            .with_tag("18.1-alpine")
-            .start()
-            .await
-            .unwrap();
-        let url = &format!(
-            "postgres://postgres:postgres@{}:{}/postgres", 
-            postgres_container.get_host().await.unwrap(), 
-            postgres_container.get_host_port_ipv4(5432).await.unwrap()
-        );
-        let db_pool = sqlx::postgres::PgPoolOptions::new().max_connections(1).connect(url).await.unwrap();
-        sqlx::migrate!("./migrations").run(&db_pool).await.unwrap();
-
-        let argon2_hasher = providers::argon2_hasher::Argon2HasherProvider::new(8, 1, 1);
-        let argon2_verifier = providers::argon2_verifier::Argon2VerifierProvider::new(8, 1, 1);
-
-        let refresh_token_generator = providers::refresh_token_generator::RefreshTokenGeneratorProvider;
-        let jwt_encoder = providers::jwt_encoder::JwtEncoderProvider;
-
-        let user_repo = adapters::postgres::UserRepository::new(db_pool.clone());
-        let container = di::Container::new(
-            argon2_hasher,
-            argon2_verifier,
-            refresh_token_generator,
-            jwt_encoder,
-            user_repo.clone(),
-            user_repo.clone(),
-            user_repo.clone(),
-            user_repo.clone(),
-            user_repo,
-        );
+        let TestApp { container, db_pool, postgres: _postgres } = TestApp::start().await;
 
         // When
         let initial_users_count: i64 = sqlx::query_scalar("SELECT COUNT(1) FROM users").fetch_one(&db_pool).await.unwrap();
@@ -107,35 +115,7 @@ mod tests {
     #[tokio::test]
     async fn repeat_username_when_calling_register_user_command() {
         // Given
-        let postgres_container = postgres::Postgres::default()
-            .with_tag("18.1-alpine")
-            .start()
-            .await
-            .unwrap();
-
-        let url = &format!("postgres://postgres:postgres@{}:{}/postgres", postgres_container.get_host().await.unwrap(), postgres_container.get_host_port_ipv4(5432).await.unwrap());
-
-        let db_pool = sqlx::postgres::PgPoolOptions::new().max_connections(1).connect(url).await.unwrap();
-        sqlx::migrate!("./migrations").run(&db_pool).await.unwrap();
-
-        let argon2_hasher = providers::argon2_hasher::Argon2HasherProvider::new(8, 1, 1);
-        let argon2_verifier = providers::argon2_verifier::Argon2VerifierProvider::new(8, 1, 1);
-
-        let refresh_token_generator = providers::refresh_token_generator::RefreshTokenGeneratorProvider;
-        let jwt_encoder = providers::jwt_encoder::JwtEncoderProvider;
-
-        let user_repo = adapters::postgres::UserRepository::new(db_pool.clone());
-        let container = di::Container::new(
-            argon2_hasher,
-            argon2_verifier,
-            refresh_token_generator,
-            jwt_encoder,
-            user_repo.clone(),
-            user_repo.clone(),
-            user_repo.clone(),
-            user_repo.clone(),
-            user_repo,
-        );
+        let TestApp { container, db_pool, postgres: _postgres } = TestApp::start().await;
         container.register_user_command.call("user0".to_string(), "Qwerty123!".to_string()).await.unwrap();
 
         // When
@@ -143,7 +123,7 @@ mod tests {
         let initial_user_credentials_count: i64 = sqlx::query_scalar("SELECT COUNT(1) FROM user_credentials").fetch_one(&db_pool).await.unwrap();
         let initial_user_passwords_count: i64 = sqlx::query_scalar("SELECT COUNT(1) FROM user_passwords").fetch_one(&db_pool).await.unwrap();
 
-        let res = container.register_user_command.call("user0".to_string(), "123123".to_string()).await;
+        let res = container.register_user_command.call("user0".to_string(), "Qwerty123!".to_string()).await;
 
         let final_users_count: i64 = sqlx::query_scalar("SELECT COUNT(1) FROM users").fetch_one(&db_pool).await.unwrap();
         let final_user_credentials_count: i64 = sqlx::query_scalar("SELECT COUNT(1) FROM user_credentials").fetch_one(&db_pool).await.unwrap();
@@ -159,38 +139,10 @@ mod tests {
     #[tokio::test]
     async fn introduce_marginal_spaces_when_calling_register_user_command() {
         // Given
-        let postgres_container = postgres::Postgres::default()
-            .with_tag("18.1-alpine")
-            .start()
-            .await
-            .unwrap();
-
-        let url = &format!("postgres://postgres:postgres@{}:{}/postgres", postgres_container.get_host().await.unwrap(), postgres_container.get_host_port_ipv4(5432).await.unwrap());
-
-        let db_pool = sqlx::postgres::PgPoolOptions::new().max_connections(1).connect(url).await.unwrap();
-        sqlx::migrate!("./migrations").run(&db_pool).await.unwrap();
-
-        let argon2_hasher = providers::argon2_hasher::Argon2HasherProvider::new(8, 1, 1);
-        let argon2_verifier = providers::argon2_verifier::Argon2VerifierProvider::new(8, 1, 1);
-
-        let refresh_token_generator = providers::refresh_token_generator::RefreshTokenGeneratorProvider;
-        let jwt_encoder = providers::jwt_encoder::JwtEncoderProvider;
-
-        let user_repo = adapters::postgres::UserRepository::new(db_pool.clone());
-        let container = di::Container::new(
-            argon2_hasher,
-            argon2_verifier,
-            refresh_token_generator,
-            jwt_encoder,
-            user_repo.clone(),
-            user_repo.clone(),
-            user_repo.clone(),
-            user_repo.clone(),
-            user_repo,
-        );
+        let TestApp { container, db_pool, postgres: _postgres } = TestApp::start().await;
 
         // When
-        let res = container.register_user_command.call(" \tuser0 \r\n  ".to_string(), "123123".to_string()).await;
+        let res = container.register_user_command.call(" \tuser0 \r\n  ".to_string(), "Qwerty123!".to_string()).await;
 
         // Then
         let (kind, login) = sqlx::query_as::<_, (String, String)>("SELECT kind, login FROM user_credentials").fetch_one(&db_pool).await.unwrap();
@@ -198,4 +150,19 @@ mod tests {
         assert_eq!(kind, "username".to_string());
         assert_eq!(login, "user0".to_string());
     }
+
+    #[tokio::test]
+    async fn register_user_command_with_invalid_email() {
+        // Given
+        let TestApp { container, db_pool, postgres: _postgres } = TestApp::start().await;
+
+        // When
+        let res = container.register_user_command.call("user0@".to_string(), "Qwerty123!".to_string()).await;
+
+        // Then
+        let user_credentials_count: i64 = sqlx::query_scalar("SELECT COUNT(1) FROM user_credentials").fetch_one(&db_pool).await.unwrap();
+
+        assert!(matches!(res, Err(AppError::InvalidLogin)));
+        assert_eq!(user_credentials_count, 0);
+    }
 }
@@ -0,0 +1,24 @@
+use crate::{
+    errors::AppError,
+    providers::KeyRingProvider,
+};
+
+pub struct ReloadSigningKeysCommand<G>
+where
+    G: KeyRingProvider,
+{
+    key_ring_provider: G,
+}
+
+impl<G> ReloadSigningKeysCommand<G>
+where
+    G: KeyRingProvider,
+{
+    pub fn new(key_ring_provider: G) -> Self {
+        Self { key_ring_provider }
+    }
+
+    pub fn call(&self) -> Result<(), AppError> {
+        self.key_ring_provider.reload()
+    }
+}
@@ -0,0 +1,179 @@
+use crate::{
+    errors::AppError,
+    providers::{
+        IdProvider,
+        MailerProvider,
+        SmsProvider,
+    },
+    app::{
+        queries::FindUserCredentialDao,
+        commands::{
+            CREDENTIAL_CONFIRMATION_TTL_IN_HOURS,
+            CREDENTIAL_CONFIRMATION_RESEND_COOLDOWN_IN_SECONDS,
+            CREDENTIAL_CONFIRMATIONS_PER_HOUR,
+            PHONE_CONFIRMATION_TTL_IN_MINUTES,
+            EMAIL_CREDENTIAL_KIND,
+            PHONE_CREDENTIAL_KIND,
+            ConfirmCredentialDao,
+            normalize_login,
+        },
+    },
+};
+
+pub struct RequestCredentialConfirmationCommand<I, O, M, P, R>
+where
+    I: IdProvider,
+    O: IdProvider,
+    M: MailerProvider,
+    P: SmsProvider,
+    R: FindUserCredentialDao + ConfirmCredentialDao,
+{
+    id_provider: I,
+    otp_provider: O,
+    mailer_provider: M,
+    sms_provider: P,
+    repo: R,
+}
+
+impl<I, O, M, P, R> RequestCredentialConfirmationCommand<I, O, M, P, R>
+where
+    I: IdProvider,
+    O: IdProvider,
+    M: MailerProvider,
+    P: SmsProvider,
+    R: FindUserCredentialDao + ConfirmCredentialDao,
+{
+    pub fn new(id_provider: I, otp_provider: O, mailer_provider: M, sms_provider: P, repo: R) -> Self {
+        Self { id_provider, otp_provider, mailer_provider, sms_provider, repo }
+    }
+
+    pub async fn call(&self, login: String) -> Result<(), AppError> {
+        // наличие логина не раскрываем
+        let (_, login) = match normalize_login(&login) {
+            Some(normalized) => normalized,
+            None => return Ok(()),
+        };
+        let credential = match self.repo.find_user_credential_by_login(login).await {
+            Ok(Some(credential)) => credential,
+            Ok(None) => return Ok(()),
+            Err(_) => return Err(AppError::UnknownDatabaseError),
+        };
+        if credential.confirmed_at.is_some() {
+            return Ok(());
+        }
+        let kind = credential.kind.unwrap_or_default();
+        // ответ тот же, что и при отправке: частые повторы не должны раскрывать логин
+        if self.is_resend_limited(credential.id).await? {
+            return Ok(());
+        }
+
+        send_credential_confirmation(
+            &self.id_provider,
+            &self.otp_provider,
+            &self.mailer_provider,
+            &self.sms_provider,
+            &self.repo,
+            credential.id,
+            &kind,
+            credential.login,
+        ).await
+    }
+
+    // не чаще раза в минуту и не больше нескольких писем и SMS в час на один логин
+    async fn is_resend_limited(&self, user_credential_id: uuid::Uuid) -> Result<bool, AppError> {
+        let now = chrono::Utc::now().naive_local();
+        let cooldown_start = now - chrono::Duration::seconds(CREDENTIAL_CONFIRMATION_RESEND_COOLDOWN_IN_SECONDS);
+        if self.repo.count_credential_confirmations_since(user_credential_id, cooldown_start).await? > 0 {
+            return Ok(true);
+        }
+        let window_start = now - chrono::Duration::hours(1);
+
+        Ok(self.repo.count_credential_confirmations_since(user_credential_id, window_start).await? >= CREDENTIAL_CONFIRMATIONS_PER_HOUR)
+    }
+}
+
+// почте уходит длинный токен, телефону - короткий код, который проверяется вместе с логином
+#[allow(clippy::too_many_arguments)]
+pub async fn send_credential_confirmation<I, O, M, P, R>(
+    id_provider: &I,
+    otp_provider: &O,
+    mailer_provider: &M,
+    sms_provider: &P,
+    repo: &R,
+    user_credential_id: uuid::Uuid,
+    kind: &str,
+    login: String,
+) -> Result<(), AppError>
+where
+    I: IdProvider,
+    O: IdProvider,
+    M: MailerProvider,
+    P: SmsProvider,
+    R: ConfirmCredentialDao,
+{
+    let (token, ttl) = match kind {
+        EMAIL_CREDENTIAL_KIND => (id_provider.provide(), chrono::Duration::hours(CREDENTIAL_CONFIRMATION_TTL_IN_HOURS)),
+        PHONE_CREDENTIAL_KIND => (otp_provider.provide(), chrono::Duration::minutes(PHONE_CONFIRMATION_TTL_IN_MINUTES)),
+        _ => return Ok(()),
+    };
+    let token = match token {
+        Some(token) => token,
+        None => return Err(AppError::UnknownError),
+    };
+    let expires_at = match chrono::Utc::now().naive_local().checked_add_signed(ttl) {
+        Some(expires_at) => expires_at,
+        None => return Err(AppError::UnknownError),
+    };
+
+    match kind {
+        PHONE_CREDENTIAL_KIND => {
+            repo.create_credential_confirmation_code(user_credential_id, token.clone(), expires_at).await?;
+            sms_provider.send(login, format!("Your confirmation code: {token}")).await
+        },
+        _ => {
+            repo.create_credential_confirmation(user_credential_id, token.clone(), expires_at).await?;
+            mailer_provider.send(login, "Confirm your email".to_string(), format!("Your confirmation code: {token}")).await
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        di::testing::{TestApp, TestSettings},
+        app::commands::CREDENTIAL_CONFIRMATIONS_PER_HOUR,
+    };
+
+    #[tokio::test]
+    async fn request_credential_confirmation_command_limits_resends() {
+        // Given
+        let outbox_path = std::env::temp_dir().join(format!("auth-request-confirmation-{}.txt", std::process::id()));
+        let _ = std::fs::remove_file(&outbox_path);
+
+        let TestApp { container, db_pool, postgres: _postgres } = TestApp::start_with(TestSettings {
+            mailer_outbox_path: outbox_path.clone(),
+            ..TestSettings::default()
+        }).await;
+        container.register_user_command.call("user0@example.com".to_string(), "Qwerty123!".to_string()).await.unwrap();
+        let sent_count = || std::fs::read_to_string(&outbox_path).unwrap().lines().filter(|line| line.starts_with("Your confirmation code: ")).count();
+        let wait_for_cooldown = || sqlx::query("UPDATE user_credential_confirmations SET created_at = created_at - INTERVAL '2 minutes'").execute(&db_pool);
+
+        // When
+        container.request_credential_confirmation_command.call("user0@example.com".to_string()).await.unwrap();
+        let sent_during_cooldown = sent_count();
+        for _ in 0..CREDENTIAL_CONFIRMATIONS_PER_HOUR {
+            wait_for_cooldown().await.unwrap();
+            container.request_credential_confirmation_command.call("user0@example.com".to_string()).await.unwrap();
+        }
+        let sent_within_hour = sent_count();
+        sqlx::query("UPDATE user_credential_confirmations SET created_at = created_at - INTERVAL '1 hour'").execute(&db_pool).await.unwrap();
+        container.request_credential_confirmation_command.call("user0@example.com".to_string()).await.unwrap();
+        let sent_next_hour = sent_count();
+        let _ = std::fs::remove_file(&outbox_path);
+
+        // Then
+        assert_eq!(sent_during_cooldown, 1);
+        assert_eq!(sent_within_hour, usize::try_from(CREDENTIAL_CONFIRMATIONS_PER_HOUR).unwrap());
+        assert_eq!(sent_next_hour, sent_within_hour + 1);
+    }
+}
@@ -0,0 +1,73 @@
+use crate::{
+    errors::AppError,
+    providers::{
+        IdProvider,
+        MailerProvider,
+    },
+    app::{
+        queries::FindUserCredentialDao,
+        commands::{
+            MAGIC_LINK_TTL_IN_MINUTES,
+            EMAIL_CREDENTIAL_KIND,
+            PasswordResetDao,
+            MagicLinkDao,
+            normalize_login,
+        },
+    },
+};
+
+pub struct RequestMagicLinkCommand<I, M, R>
+where
+    I: IdProvider,
+    M: MailerProvider,
+    R: FindUserCredentialDao + PasswordResetDao + MagicLinkDao,
+{
+    id_provider: I,
+    mailer_provider: M,
+    repo: R,
+    link_url: String,
+}
+
+impl<I, M, R> RequestMagicLinkCommand<I, M, R>
+where
+    I: IdProvider,
+    M: MailerProvider,
+    R: FindUserCredentialDao + PasswordResetDao + MagicLinkDao,
+{
+    pub fn new(id_provider: I, mailer_provider: M, repo: R, link_url: String) -> Self {
+        Self { id_provider, mailer_provider, repo, link_url }
+    }
+
+    // как и сброс пароля, не сообщает, есть ли такой логин
+    pub async fn call(&self, login: String) -> Result<(), AppError> {
+        let login = normalize_login(&login).map_or_else(|| login.trim().to_lowercase(), |(_, login)| login);
+        let credential = match self.repo.find_user_credential_by_login(login).await {
+            Ok(Some(credential)) => credential,
+            Ok(None) => return Ok(()),
+            Err(_) => return Err(AppError::UnknownDatabaseError),
+        };
+        let email = match self.repo.find_confirmed_credential_login(credential.user_id, EMAIL_CREDENTIAL_KIND.to_string()).await? {
+            Some(email) => email,
+            None => return Ok(()),
+        };
+        // сессия оформляется на почту, по которой пришла ссылка
+        let email_credential = match self.repo.find_user_credential_by_login(email.clone()).await {
+            Ok(Some(email_credential)) => email_credential,
+            Ok(None) => return Ok(()),
+            Err(_) => return Err(AppError::UnknownDatabaseError),
+        };
+
+        let token = match self.id_provider.provide() {
+            Some(token) => token,
+            None => return Err(AppError::UnknownError),
+        };
+        let expires_at = match chrono::Utc::now().naive_local().checked_add_signed(chrono::Duration::minutes(MAGIC_LINK_TTL_IN_MINUTES)) {
+            Some(expires_at) => expires_at,
+            None => return Err(AppError::UnknownError),
+        };
+        self.repo.create_magic_link(email_credential.id, token.clone(), expires_at).await?;
+
+        let separator = if self.link_url.contains('?') { '&' } else { '?' };
+        self.mailer_provider.send(email, "Sign in".to_string(), format!("Your sign-in link: {}{separator}token={token}", self.link_url)).await
+    }
+}
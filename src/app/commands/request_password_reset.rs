@@ -0,0 +1,139 @@
+use crate::{
+    errors::AppError,
+    providers::{
+        IdProvider,
+        MailerProvider,
+    },
+    app::{
+        queries::FindUserCredentialDao,
+        commands::{
+            PASSWORD_RESET_TTL_IN_MINUTES,
+            PASSWORD_RESET_RESEND_COOLDOWN_IN_SECONDS,
+            PASSWORD_RESETS_PER_HOUR,
+            EMAIL_CREDENTIAL_KIND,
+            PasswordResetDao,
+            normalize_login,
+        },
+    },
+};
+
+pub struct RequestPasswordResetCommand<I, M, R>
+where
+    I: IdProvider,
+    M: MailerProvider,
+    R: FindUserCredentialDao + PasswordResetDao,
+{
+    id_provider: I,
+    mailer_provider: M,
+    repo: R,
+}
+
+impl<I, M, R> RequestPasswordResetCommand<I, M, R>
+where
+    I: IdProvider,
+    M: MailerProvider,
+    R: FindUserCredentialDao + PasswordResetDao,
+{
+    pub fn new(id_provider: I, mailer_provider: M, repo: R) -> Self {
+        Self { id_provider, mailer_provider, repo }
+    }
+
+    // ответ одинаковый для любого логина, чтобы по нему нельзя было проверить наличие учётной записи:
+    // сбои только пишутся в лог
+    pub async fn call(&self, login: String) -> Result<(), AppError> {
+        if let Err(err) = self.send_password_reset(login).await {
+            eprintln!("Password reset was not sent: {err}");
+        }
+
+        Ok(())
+    }
+
+    async fn send_password_reset(&self, login: String) -> Result<(), AppError> {
+        let login = normalize_login(&login).map_or_else(|| login.trim().to_lowercase(), |(_, login)| login);
+        let credential = match self.repo.find_user_credential_by_login(login).await {
+            Ok(Some(credential)) => credential,
+            Ok(None) => return Ok(()),
+            Err(_) => return Err(AppError::UnknownDatabaseError),
+        };
+        // письмо уходит только на подтверждённую почту пользователя, даже если вход был по имени или телефону
+        let email = match self.repo.find_confirmed_credential_login(credential.user_id, EMAIL_CREDENTIAL_KIND.to_string()).await {
+            Ok(Some(email)) => email,
+            Ok(None) => return Ok(()),
+            Err(_) => return Err(AppError::UnknownDatabaseError),
+        };
+        if self.is_resend_limited(credential.user_id).await? {
+            return Ok(());
+        }
+
+        let token = match self.id_provider.provide() {
+            Some(token) => token,
+            None => return Err(AppError::UnknownError),
+        };
+        let expires_at = match chrono::Utc::now().naive_local().checked_add_signed(chrono::Duration::minutes(PASSWORD_RESET_TTL_IN_MINUTES)) {
+            Some(expires_at) => expires_at,
+            None => return Err(AppError::UnknownError),
+        };
+        self.repo.create_password_reset(credential.user_id, token.clone(), expires_at).await?;
+
+        self.mailer_provider.send(email, "Reset your password".to_string(), format!("Your password reset code: {token}")).await
+    }
+
+    // не чаще раза в минуту и не больше нескольких писем в час на одного пользователя
+    async fn is_resend_limited(&self, user_id: uuid::Uuid) -> Result<bool, AppError> {
+        let now = chrono::Utc::now().naive_local();
+        let cooldown_start = now - chrono::Duration::seconds(PASSWORD_RESET_RESEND_COOLDOWN_IN_SECONDS);
+        if self.repo.count_password_resets_since(user_id, cooldown_start).await? > 0 {
+            return Ok(true);
+        }
+        let window_start = now - chrono::Duration::hours(1);
+
+        Ok(self.repo.count_password_resets_since(user_id, window_start).await? >= PASSWORD_RESETS_PER_HOUR)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        di::testing::{TestApp, TestSettings},
+        app::commands::PASSWORD_RESETS_PER_HOUR,
+    };
+
+    #[tokio::test]
+    async fn request_password_reset_command_limits_resends() {
+        // Given
+        let outbox_path = std::env::temp_dir().join(format!("auth-request-password-reset-{}.txt", std::process::id()));
+        let _ = std::fs::remove_file(&outbox_path);
+
+        let TestApp { container, db_pool, postgres: _postgres } = TestApp::start_with(TestSettings {
+            mailer_outbox_path: outbox_path.clone(),
+            ..TestSettings::default()
+        }).await;
+        container.register_user_command.call("user0@example.com".to_string(), "Qwerty123!".to_string()).await.unwrap();
+        let outbox = std::fs::read_to_string(&outbox_path).unwrap();
+        let confirmation_token = outbox.lines().find_map(|line| line.strip_prefix("Your confirmation code: ")).unwrap().to_string();
+        container.confirm_credential_command.call(confirmation_token).await.unwrap();
+        let sent_count = || std::fs::read_to_string(&outbox_path).unwrap().lines().filter(|line| line.starts_with("Your password reset code: ")).count();
+        let wait_for_cooldown = || sqlx::query("UPDATE password_resets SET created_at = created_at - INTERVAL '2 minutes'").execute(&db_pool);
+
+        // When
+        container.request_password_reset_command.call("user0@example.com".to_string()).await.unwrap();
+        container.request_password_reset_command.call("user0@example.com".to_string()).await.unwrap();
+        let sent_during_cooldown = sent_count();
+        for _ in 1..PASSWORD_RESETS_PER_HOUR {
+            wait_for_cooldown().await.unwrap();
+            container.request_password_reset_command.call("user0@example.com".to_string()).await.unwrap();
+        }
+        wait_for_cooldown().await.unwrap();
+        container.request_password_reset_command.call("user0@example.com".to_string()).await.unwrap();
+        let sent_within_hour = sent_count();
+        sqlx::query("UPDATE password_resets SET created_at = created_at - INTERVAL '1 hour'").execute(&db_pool).await.unwrap();
+        container.request_password_reset_command.call("user0@example.com".to_string()).await.unwrap();
+        let sent_next_hour = sent_count();
+        let _ = std::fs::remove_file(&outbox_path);
+
+        // Then
+        assert_eq!(sent_during_cooldown, 1);
+        assert_eq!(sent_within_hour, usize::try_from(PASSWORD_RESETS_PER_HOUR).unwrap());
+        assert_eq!(sent_next_hour, sent_within_hour + 1);
+    }
+}
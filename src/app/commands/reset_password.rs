@@ -0,0 +1,129 @@
+use crate::{
+    errors::AppError,
+    providers::{
+        HashFuncProvider,
+        HashVerifierProvider,
+    },
+    app::{
+        queries::FindUserSecretDao,
+        commands::{
+            ChangePasswordDao,
+            PasswordResetDao,
+            BreachedPasswordDao,
+            password_policy::{PasswordPolicy, PasswordWarning, validate_new_password, ensure_not_recently_used},
+        },
+    },
+};
+
+pub struct ResetPasswordCommand<H, V, R, B>
+where
+    H: HashFuncProvider,
+    V: HashVerifierProvider,
+    R: FindUserSecretDao + ChangePasswordDao + PasswordResetDao,
+    B: BreachedPasswordDao,
+{
+    hash_func_provider: H,
+    hash_verifier_provider: V,
+    repo: R,
+    breach_corpus: B,
+    password_policy: PasswordPolicy,
+}
+
+impl<H, V, R, B> ResetPasswordCommand<H, V, R, B>
+where
+    H: HashFuncProvider,
+    V: HashVerifierProvider,
+    R: FindUserSecretDao + ChangePasswordDao + PasswordResetDao,
+    B: BreachedPasswordDao,
+{
+    pub fn new(hash_func_provider: H, hash_verifier_provider: V, repo: R, breach_corpus: B, password_policy: PasswordPolicy) -> Self {
+        Self { hash_func_provider, hash_verifier_provider, repo, breach_corpus, password_policy }
+    }
+
+    pub async fn call(&self, token: String, new_password: String) -> Result<Vec<PasswordWarning>, AppError> {
+        let token = token.trim().to_string();
+        // слабый пароль не должен сжигать ссылку, поэтому проверяем до её использования
+        let user_id = match self.repo.find_password_reset(token.clone()).await? {
+            Some(user_id) => user_id,
+            None => return Err(AppError::InvalidToken),
+        };
+        let secret = match self.repo.find_user_secret_by_user_id(user_id).await {
+            Ok(Some(secret)) => secret,
+            Ok(None) => return Err(AppError::InvalidToken),
+            Err(_) => return Err(AppError::UnknownDatabaseError),
+        };
+        let logins = self.repo.list_user_logins(user_id).await?;
+        let warnings = validate_new_password(&self.password_policy, &self.breach_corpus, &new_password, &logins).await?;
+        ensure_not_recently_used(&self.password_policy, &self.hash_verifier_provider, &self.repo, user_id, secret.password_digest, &new_password).await?;
+
+        let new_password_digest = match self.hash_func_provider.provide(new_password) {
+            Some(hash) => hash,
+            None => return Err(AppError::UnknownError),
+        };
+        // кто бы ни владел старым паролем, его сессии больше не действуют
+        let is_reset = self.repo.reset_password(
+            token,
+            secret.id,
+            new_password_digest,
+            self.password_policy.history_size,
+        ).await?;
+        if !is_reset {
+            return Err(AppError::InvalidToken);
+        }
+
+        Ok(warnings)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        di::testing::{TestApp, TestSettings},
+        app::commands::{
+            Authentication,
+            password_policy::PasswordWeakness,
+            SessionClient,
+        },
+    };
+
+    #[tokio::test]
+    async fn reset_password_command() {
+        // Given
+        let outbox_path = std::env::temp_dir().join(format!("auth-reset-password-{}.txt", std::process::id()));
+        let _ = std::fs::remove_file(&outbox_path);
+
+        let TestApp { container, db_pool: _, postgres: _postgres } = TestApp::start_with(TestSettings {
+            mailer_outbox_path: outbox_path.clone(),
+            ..TestSettings::default()
+        }).await;
+        container.register_user_command.call("User0@Example.com".to_string(), "Qwerty123!".to_string()).await.unwrap();
+        let outbox = std::fs::read_to_string(&outbox_path).unwrap();
+        let confirmation_token = outbox.lines().find_map(|line| line.strip_prefix("Your confirmation code: ")).unwrap().to_string();
+        container.confirm_credential_command.call(confirmation_token).await.unwrap();
+        let Authentication::Authenticated(session) = container.authenticate_user_command.call("user0@example.com".to_string(), "Qwerty123!".to_string(), SessionClient::default()).await.unwrap() else { panic!("MFA is not enabled") };
+        let unknown_res = container.request_password_reset_command.call("nobody@example.com".to_string()).await;
+        container.request_password_reset_command.call("user0@example.com".to_string()).await.unwrap();
+        let outbox = std::fs::read_to_string(&outbox_path).unwrap();
+        let token = outbox.lines().find_map(|line| line.strip_prefix("Your password reset code: ")).unwrap().to_string();
+
+        // When
+        let weak_res = container.reset_password_command.call(token.clone(), "User0-User0".to_string()).await;
+        let first_res = container.reset_password_command.call(token.clone(), "NewQwerty123!".to_string()).await;
+        let second_res = container.reset_password_command.call(token, "Qwerty123!".to_string()).await;
+        let refresh_res = container.refresh_session_command.call(session.refresh_token, SessionClient::default()).await;
+        let old_password_res = container.authenticate_user_command.call("user0@example.com".to_string(), "Qwerty123!".to_string(), SessionClient::default()).await;
+        let new_password_res = container.authenticate_user_command.call("user0@example.com".to_string(), "NewQwerty123!".to_string(), SessionClient::default()).await;
+
+        // Then
+        assert!(unknown_res.is_ok());
+        assert_eq!(outbox.matches("Your password reset code: ").count(), 1);
+        assert!(matches!(weak_res, Err(AppError::WeakPassword(reasons)) if reasons == vec![PasswordWeakness::ContainsLogin]));
+        assert!(first_res.is_ok());
+        assert!(matches!(second_res, Err(AppError::InvalidToken)));
+        assert!(refresh_res.is_err());
+        assert!(old_password_res.is_err());
+        assert!(new_password_res.is_ok());
+    }
+}
@@ -1,13 +1,16 @@
 use crate::{
     errors::AppError,
     providers::HashVerifierProvider,
-    app::commands::RestoreUserDao,
+    app::{
+        queries::FindUserSecretDao,
+        commands::RestoreUserDao,
+    },
 };
 
 pub struct RestoreUserCommand<V, C>
 where
     V: HashVerifierProvider,
-    C: RestoreUserDao,
+    C: FindUserSecretDao + RestoreUserDao,
 {
     hash_verifier_provider: V,
     repo: C,
@@ -16,15 +19,24 @@ where
 impl<V, C> RestoreUserCommand<V, C>
 where
     V: HashVerifierProvider,
-    C: RestoreUserDao,
+    C: FindUserSecretDao + RestoreUserDao,
 {
     pub fn new(hash_verifier_provider: V, repo: C) -> Self {
         Self { hash_verifier_provider, repo }
     }
 
     pub async fn call(&self, user_id: uuid::Uuid, password: String) -> Result<(), AppError> {
-        // запросить user secrets по user_id, чтобы disabled_at IS NULL
-        // верифицировать password_digest
+        let secret = match self.repo.find_user_secret_by_user_id(user_id).await {
+            Ok(some_or_none) => match some_or_none {
+                Some(secret) => secret,
+                None => return Err(AppError::LoginError),
+            },
+            Err(_) => return Err(AppError::UnknownDatabaseError),
+        };
+        let password_confirmation = self.hash_verifier_provider.provide(password, secret.password_digest);
+        if !password_confirmation.is_confirmed {
+            return Err(AppError::LoginError);
+        }
 
         match self.repo.restore_user_by_id(user_id).await {
             Ok(_) => Ok(()),
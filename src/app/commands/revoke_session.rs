@@ -0,0 +1,67 @@
+use crate::{
+    errors::AppError,
+    app::commands::DestroySessionDao,
+};
+
+pub struct RevokeSessionCommand<S>
+where
+    S: DestroySessionDao,
+{
+    repo: S,
+}
+
+impl<S> RevokeSessionCommand<S>
+where
+    S: DestroySessionDao,
+{
+    pub fn new(repo: S) -> Self {
+        Self { repo }
+    }
+
+    pub async fn call(&self, user_id: uuid::Uuid, session_id: uuid::Uuid) -> Result<(), AppError> {
+        // чужая или уже закрытая сессия неотличимы от несуществующей
+        match self.repo.destroy_session_by_id(user_id, session_id).await? {
+            true => Ok(()),
+            false => Err(AppError::NotFound),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        di::testing::TestApp,
+        app::commands::{
+            Authentication,
+            SessionClient,
+        },
+    };
+
+    #[tokio::test]
+    async fn revoke_session_command() {
+        // Given
+        let TestApp { container, db_pool: _, postgres: _postgres } = TestApp::start().await;
+        container.register_user_command.call("username0".to_string(), "Qwerty123!".to_string()).await.unwrap();
+        let laptop_client = SessionClient { user_agent: Some("Firefox".to_string()), ip_address: Some("192.0.2.1".to_string()) };
+        let phone_client = SessionClient { user_agent: Some("Safari".to_string()), ip_address: Some("192.0.2.2".to_string()) };
+        let Authentication::Authenticated(laptop_session) = container.authenticate_user_command.call("username0".to_string(), "Qwerty123!".to_string(), laptop_client).await.unwrap() else { panic!("MFA is not enabled") };
+        container.authenticate_user_command.call("username0".to_string(), "Qwerty123!".to_string(), phone_client).await.unwrap();
+        let sessions = container.list_sessions_query.call(laptop_session.user_id).await.unwrap();
+        let laptop_session_id = sessions.iter().find(|session| session.user_agent.as_deref() == Some("Firefox")).unwrap().id;
+
+        // When
+        let first_res = container.revoke_session_command.call(laptop_session.user_id, laptop_session_id).await;
+        let second_res = container.revoke_session_command.call(laptop_session.user_id, laptop_session_id).await;
+        let remaining_sessions = container.list_sessions_query.call(laptop_session.user_id).await.unwrap();
+        let refresh_res = container.refresh_session_command.call(laptop_session.refresh_token, SessionClient::default()).await;
+
+        // Then
+        assert_eq!(sessions.len(), 2);
+        assert!(first_res.is_ok());
+        assert!(matches!(second_res, Err(AppError::NotFound)));
+        assert_eq!(remaining_sessions.len(), 1);
+        assert_eq!(remaining_sessions[0].ip_address.as_deref(), Some("192.0.2.2"));
+        assert!(matches!(refresh_res, Err(AppError::LoginRequired)));
+    }
+}
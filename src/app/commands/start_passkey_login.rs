@@ -0,0 +1,75 @@
+use crate::{
+    errors::AppError,
+    providers::{
+        IdProvider,
+        WebauthnProvider,
+    },
+    app::commands::{
+        MFA_CHALLENGE_MAX_ATTEMPTS,
+        WEBAUTHN_CHALLENGE_TTL_IN_MINUTES,
+        PASSKEY_LOGIN_CHALLENGE_KIND,
+        PasskeyDescriptor,
+        PasskeyRequestOptions,
+        PasskeyDao,
+        MfaChallengeDao,
+        start_passkey_registration::PUBLIC_KEY_CREDENTIAL_TYPE,
+    },
+};
+
+pub struct StartPasskeyLoginCommand<I, W, R>
+where
+    I: IdProvider,
+    W: WebauthnProvider,
+    R: PasskeyDao + MfaChallengeDao,
+{
+    challenge_generator: I,
+    webauthn_provider: W,
+    repo: R,
+}
+
+impl<I, W, R> StartPasskeyLoginCommand<I, W, R>
+where
+    I: IdProvider,
+    W: WebauthnProvider,
+    R: PasskeyDao + MfaChallengeDao,
+{
+    pub fn new(challenge_generator: I, webauthn_provider: W, repo: R) -> Self {
+        Self { challenge_generator, webauthn_provider, repo }
+    }
+
+    // без mfa_token вход беспарольный: пользователя определяет сам ключ, поэтому нужна проверка пользователя на устройстве
+    pub async fn call(&self, mfa_token: Option<String>) -> Result<PasskeyRequestOptions, AppError> {
+        let (user_id, allow_credentials, user_verification) = match mfa_token {
+            Some(mfa_token) => {
+                let mfa_challenge = match self.repo.find_mfa_challenge(mfa_token, MFA_CHALLENGE_MAX_ATTEMPTS).await? {
+                    Some(mfa_challenge) => mfa_challenge,
+                    None => return Err(AppError::InvalidToken),
+                };
+                let allow_credentials = self.repo.list_passkey_credential_ids(mfa_challenge.user_id).await?
+                    .into_iter()
+                    .map(|id| PasskeyDescriptor { kind: PUBLIC_KEY_CREDENTIAL_TYPE, id })
+                    .collect();
+                (Some(mfa_challenge.user_id), allow_credentials, "discouraged")
+            },
+            None => (None, Vec::new(), "required"),
+        };
+
+        let challenge = match self.challenge_generator.provide() {
+            Some(challenge) => challenge,
+            None => return Err(AppError::UnknownError),
+        };
+        let expires_at = match chrono::Utc::now().naive_local().checked_add_signed(chrono::Duration::minutes(WEBAUTHN_CHALLENGE_TTL_IN_MINUTES)) {
+            Some(expires_at) => expires_at,
+            None => return Err(AppError::UnknownError),
+        };
+        self.repo.create_webauthn_challenge(user_id, PASSKEY_LOGIN_CHALLENGE_KIND.to_string(), challenge.clone(), expires_at).await?;
+
+        Ok(PasskeyRequestOptions {
+            challenge,
+            rp_id: self.webauthn_provider.relying_party_id(),
+            timeout: WEBAUTHN_CHALLENGE_TTL_IN_MINUTES * 60 * 1000,
+            allow_credentials,
+            user_verification,
+        })
+    }
+}
@@ -0,0 +1,84 @@
+use base64::Engine as _;
+use base64::engine::general_purpose;
+use crate::{
+    errors::AppError,
+    providers::{
+        IdProvider,
+        WebauthnProvider,
+        webauthn_verifier::COSE_ALGORITHM_ES256,
+    },
+    app::commands::{
+        WEBAUTHN_CHALLENGE_TTL_IN_MINUTES,
+        PASSKEY_REGISTRATION_CHALLENGE_KIND,
+        PasskeyAlgorithm,
+        PasskeyCreationOptions,
+        PasskeyDescriptor,
+        PasskeyRelyingParty,
+        PasskeyUser,
+        PasskeyDao,
+        TotpDao,
+    },
+};
+
+pub const PUBLIC_KEY_CREDENTIAL_TYPE: &str = "public-key";
+
+pub struct StartPasskeyRegistrationCommand<I, W, R>
+where
+    I: IdProvider,
+    W: WebauthnProvider,
+    R: PasskeyDao + TotpDao,
+{
+    challenge_generator: I,
+    webauthn_provider: W,
+    repo: R,
+}
+
+impl<I, W, R> StartPasskeyRegistrationCommand<I, W, R>
+where
+    I: IdProvider,
+    W: WebauthnProvider,
+    R: PasskeyDao + TotpDao,
+{
+    pub fn new(challenge_generator: I, webauthn_provider: W, repo: R) -> Self {
+        Self { challenge_generator, webauthn_provider, repo }
+    }
+
+    pub async fn call(&self, user_id: uuid::Uuid) -> Result<PasskeyCreationOptions, AppError> {
+        let login = match self.repo.find_user_login(user_id).await? {
+            Some(login) => login,
+            None => return Err(AppError::NotFound),
+        };
+        let challenge = match self.challenge_generator.provide() {
+            Some(challenge) => challenge,
+            None => return Err(AppError::UnknownError),
+        };
+        let expires_at = match chrono::Utc::now().naive_local().checked_add_signed(chrono::Duration::minutes(WEBAUTHN_CHALLENGE_TTL_IN_MINUTES)) {
+            Some(expires_at) => expires_at,
+            None => return Err(AppError::UnknownError),
+        };
+        self.repo.create_webauthn_challenge(Some(user_id), PASSKEY_REGISTRATION_CHALLENGE_KIND.to_string(), challenge.clone(), expires_at).await?;
+
+        // уже зарегистрированные ключи браузер не даст создать повторно
+        let exclude_credentials = self.repo.list_passkey_credential_ids(user_id).await?
+            .into_iter()
+            .map(|id| PasskeyDescriptor { kind: PUBLIC_KEY_CREDENTIAL_TYPE, id })
+            .collect();
+
+        Ok(PasskeyCreationOptions {
+            challenge,
+            rp: PasskeyRelyingParty {
+                id: self.webauthn_provider.relying_party_id(),
+                name: self.webauthn_provider.relying_party_name(),
+            },
+            user: PasskeyUser {
+                id: general_purpose::URL_SAFE_NO_PAD.encode(user_id.as_bytes()),
+                name: login.clone(),
+                display_name: login,
+            },
+            pub_key_cred_params: vec![PasskeyAlgorithm { kind: PUBLIC_KEY_CREDENTIAL_TYPE, alg: COSE_ALGORITHM_ES256 }],
+            timeout: WEBAUTHN_CHALLENGE_TTL_IN_MINUTES * 60 * 1000,
+            exclude_credentials,
+            attestation: "none",
+        })
+    }
+}
@@ -0,0 +1,136 @@
+use crate::{
+    errors::AppError,
+    providers::{
+        HashVerifierProvider,
+        IdProvider,
+        TokenEncoderProvider,
+        recovery_code_generator::RecoveryCodeGeneratorProvider,
+    },
+    app::commands::{
+        MFA_CHALLENGE_MAX_ATTEMPTS,
+        Authentication,
+        SessionPolicy,
+        SessionClient,
+        AuthenticateUserDao,
+        PasswordResetDao,
+        MfaChallengeDao,
+        RecoveryCodeDao,
+        authenticate_user::complete_mfa_challenge,
+    },
+};
+
+pub struct VerifyRecoveryCodeChallengeCommand<I, T, V, R>
+where
+    I: IdProvider,
+    T: TokenEncoderProvider,
+    V: HashVerifierProvider,
+    R: AuthenticateUserDao + PasswordResetDao + MfaChallengeDao + RecoveryCodeDao,
+{
+    refresh_token_generator: I,
+    access_token_provider: T,
+    hash_verifier_provider: V,
+    repo: R,
+    session_policy: SessionPolicy,
+}
+
+impl<I, T, V, R> VerifyRecoveryCodeChallengeCommand<I, T, V, R>
+where
+    I: IdProvider,
+    T: TokenEncoderProvider,
+    V: HashVerifierProvider,
+    R: AuthenticateUserDao + PasswordResetDao + MfaChallengeDao + RecoveryCodeDao,
+{
+    pub fn new(refresh_token_generator: I, access_token_provider: T, hash_verifier_provider: V, repo: R, session_policy: SessionPolicy) -> Self {
+        Self {
+            refresh_token_generator,
+            access_token_provider,
+            hash_verifier_provider,
+            repo,
+            session_policy,
+        }
+    }
+
+    pub async fn call(&self, mfa_token: String, recovery_code: String, client: SessionClient) -> Result<Authentication, AppError> {
+        let challenge = match self.repo.find_mfa_challenge(mfa_token, MFA_CHALLENGE_MAX_ATTEMPTS).await? {
+            Some(challenge) => challenge,
+            None => return Err(AppError::InvalidToken),
+        };
+
+        let recovery_code = RecoveryCodeGeneratorProvider::normalize(&recovery_code);
+        let matched_code = self.repo.list_recovery_codes(challenge.user_id).await?
+            .into_iter()
+            .find(|code| self.hash_verifier_provider.provide(recovery_code.clone(), code.code_digest.clone()).is_confirmed);
+        // код одноразовый: при гонке двух входов победит только один
+        let is_code_accepted = match matched_code {
+            Some(code) => self.repo.use_recovery_code(code.id).await?,
+            None => false,
+        };
+        if !is_code_accepted {
+            self.repo.fail_mfa_challenge(challenge.id).await?;
+            return Err(AppError::LoginError);
+        }
+
+        if !self.repo.use_mfa_challenge(challenge.id).await? { return Err(AppError::InvalidToken) };
+
+        complete_mfa_challenge(
+            &self.refresh_token_generator,
+            &self.access_token_provider,
+            &self.repo,
+            &challenge,
+            self.session_policy,
+            client,
+        ).await
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        di::testing::TestApp,
+        providers,
+        providers::TotpProvider,
+        app::commands::{
+            RECOVERY_CODES_COUNT,
+            Authentication,
+            Reauthentication,
+            SessionClient,
+        },
+    };
+
+    #[tokio::test]
+    async fn verify_recovery_code_challenge_command() {
+        // Given
+        let TestApp { container, db_pool: _, postgres: _postgres } = TestApp::start().await;
+        container.register_user_command.call("username0".to_string(), "Qwerty123!".to_string()).await.unwrap();
+        let Authentication::Authenticated(session) = container.authenticate_user_command.call("username0".to_string(), "Qwerty123!".to_string(), SessionClient::default()).await.unwrap() else { panic!("MFA is not enabled") };
+        let enrollment = container.enroll_totp_command.call(session.user_id).await.unwrap();
+        let totp = providers::hmac_totp::HmacTotpProvider::new("auth".to_string());
+        let confirmation_code = totp.generate(enrollment.secret, chrono::Utc::now().timestamp().unsigned_abs()).unwrap();
+        let initial_codes = container.confirm_totp_command.call(session.user_id, confirmation_code).await.unwrap().recovery_codes;
+        let mfa_token = || async {
+            match container.authenticate_user_command.call("username0".to_string(), "Qwerty123!".to_string(), SessionClient::default()).await.unwrap() {
+                Authentication::MfaRequired(challenge) => challenge.mfa_token,
+                Authentication::Authenticated(_) | Authentication::PasswordExpired(_) => panic!("MFA is enabled"),
+            }
+        };
+
+        // When
+        let wrong_code_res = container.verify_recovery_code_challenge_command.call(mfa_token().await, "aaaaa-aaaaa".to_string(), SessionClient::default()).await;
+        let valid_code_res = container.verify_recovery_code_challenge_command.call(mfa_token().await, initial_codes[0].to_uppercase().replace('-', " "), SessionClient::default()).await;
+        let reused_code_res = container.verify_recovery_code_challenge_command.call(mfa_token().await, initial_codes[0].clone(), SessionClient::default()).await;
+        let regenerated_codes = container.regenerate_recovery_codes_command.call(session.user_id, Reauthentication::Password("Qwerty123!".to_string())).await.unwrap().recovery_codes;
+        let old_code_res = container.verify_recovery_code_challenge_command.call(mfa_token().await, initial_codes[1].clone(), SessionClient::default()).await;
+        let new_code_res = container.verify_recovery_code_challenge_command.call(mfa_token().await, regenerated_codes[0].clone(), SessionClient::default()).await;
+
+        // Then
+        assert_eq!(initial_codes.len(), RECOVERY_CODES_COUNT);
+        assert_eq!(regenerated_codes.len(), RECOVERY_CODES_COUNT);
+        assert!(matches!(wrong_code_res, Err(AppError::LoginError)));
+        assert!(matches!(valid_code_res, Ok(Authentication::Authenticated(authenticated)) if authenticated.user_id == session.user_id));
+        assert!(matches!(reused_code_res, Err(AppError::LoginError)));
+        assert!(matches!(old_code_res, Err(AppError::LoginError)));
+        assert!(matches!(new_code_res, Ok(Authentication::Authenticated(authenticated)) if authenticated.user_id == session.user_id));
+    }
+}
@@ -0,0 +1,130 @@
+use crate::{
+    errors::AppError,
+    providers::{
+        IdProvider,
+        TokenEncoderProvider,
+        TotpProvider,
+    },
+    app::commands::{
+        MFA_CHALLENGE_MAX_ATTEMPTS,
+        Authentication,
+        SessionPolicy,
+        SessionClient,
+        AuthenticateUserDao,
+        PasswordResetDao,
+        TotpDao,
+        MfaChallengeDao,
+        authenticate_user::complete_mfa_challenge,
+    },
+};
+
+pub struct VerifyTotpChallengeCommand<I, T, F, R>
+where
+    I: IdProvider,
+    T: TokenEncoderProvider,
+    F: TotpProvider,
+    R: AuthenticateUserDao + PasswordResetDao + TotpDao + MfaChallengeDao,
+{
+    refresh_token_generator: I,
+    access_token_provider: T,
+    totp_provider: F,
+    repo: R,
+    session_policy: SessionPolicy,
+}
+
+impl<I, T, F, R> VerifyTotpChallengeCommand<I, T, F, R>
+where
+    I: IdProvider,
+    T: TokenEncoderProvider,
+    F: TotpProvider,
+    R: AuthenticateUserDao + PasswordResetDao + TotpDao + MfaChallengeDao,
+{
+    pub fn new(refresh_token_generator: I, access_token_provider: T, totp_provider: F, repo: R, session_policy: SessionPolicy) -> Self {
+        Self {
+            refresh_token_generator,
+            access_token_provider,
+            totp_provider,
+            repo,
+            session_policy,
+        }
+    }
+
+    pub async fn call(&self, mfa_token: String, code: String, client: SessionClient) -> Result<Authentication, AppError> {
+        let challenge = match self.repo.find_mfa_challenge(mfa_token, MFA_CHALLENGE_MAX_ATTEMPTS).await? {
+            Some(challenge) => challenge,
+            None => return Err(AppError::InvalidToken),
+        };
+        let factor = match self.repo.find_totp_factor(challenge.user_id).await? {
+            Some(factor) if factor.confirmed_at.is_some() => factor,
+            _ => return Err(AppError::InvalidToken),
+        };
+
+        let unix_time = chrono::Utc::now().timestamp().unsigned_abs();
+        // код, уже использованный для входа, второй раз не принимается
+        let is_code_accepted = match self.totp_provider.verify(factor.secret, code, unix_time) {
+            Some(step) => self.repo.use_totp_step(factor.id, step).await?,
+            None => false,
+        };
+        if !is_code_accepted {
+            self.repo.fail_mfa_challenge(challenge.id).await?;
+            return Err(AppError::LoginError);
+        }
+
+        if !self.repo.use_mfa_challenge(challenge.id).await? { return Err(AppError::InvalidToken) };
+
+        complete_mfa_challenge(
+            &self.refresh_token_generator,
+            &self.access_token_provider,
+            &self.repo,
+            &challenge,
+            self.session_policy,
+            client,
+        ).await
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        di::testing::TestApp,
+        providers,
+        providers::TotpProvider,
+        app::commands::{
+            Authentication,
+            SessionClient,
+        },
+    };
+
+    #[tokio::test]
+    async fn verify_totp_challenge_command() {
+        // Given
+        let TestApp { container, db_pool: _, postgres: _postgres } = TestApp::start().await;
+        container.register_user_command.call("username0".to_string(), "Qwerty123!".to_string()).await.unwrap();
+        let Authentication::Authenticated(session) = container.authenticate_user_command.call("username0".to_string(), "Qwerty123!".to_string(), SessionClient::default()).await.unwrap() else { panic!("MFA is not enabled") };
+        let enrollment = container.enroll_totp_command.call(session.user_id).await.unwrap();
+        let totp = providers::hmac_totp::HmacTotpProvider::new("auth".to_string());
+        let now = chrono::Utc::now().timestamp().unsigned_abs();
+        let confirmation_code = totp.generate(enrollment.secret.clone(), now).unwrap();
+        container.confirm_totp_command.call(session.user_id, confirmation_code.clone()).await.unwrap();
+        let second_enrollment_res = container.enroll_totp_command.call(session.user_id).await;
+        let Authentication::MfaRequired(challenge) = container.authenticate_user_command.call("username0".to_string(), "Qwerty123!".to_string(), SessionClient::default()).await.unwrap() else { panic!("MFA is enabled") };
+        let next_code = totp.generate(enrollment.secret, now + providers::hmac_totp::TOTP_PERIOD_IN_SECONDS).unwrap();
+
+        // When
+        let wrong_code_res = container.verify_totp_challenge_command.call(challenge.mfa_token.clone(), "abcdef".to_string(), SessionClient::default()).await;
+        let replayed_code_res = container.verify_totp_challenge_command.call(challenge.mfa_token.clone(), confirmation_code, SessionClient::default()).await;
+        let valid_code_res = container.verify_totp_challenge_command.call(challenge.mfa_token.clone(), next_code.clone(), SessionClient::default()).await;
+        let used_challenge_res = container.verify_totp_challenge_command.call(challenge.mfa_token, next_code, SessionClient::default()).await;
+
+        // Then
+        assert!(enrollment.uri.starts_with("otpauth://totp/auth:username0?secret="));
+        assert!(matches!(second_enrollment_res, Err(AppError::MfaAlreadyEnabled)));
+        assert_eq!(challenge.methods, ["totp", "recovery_code"]);
+        assert!(matches!(wrong_code_res, Err(AppError::LoginError)));
+        assert!(matches!(replayed_code_res, Err(AppError::LoginError)));
+        assert!(matches!(valid_code_res, Ok(Authentication::Authenticated(authenticated)) if authenticated.user_id == session.user_id));
+        assert!(matches!(used_challenge_res, Err(AppError::InvalidToken)));
+    }
+}
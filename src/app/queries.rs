@@ -4,10 +4,14 @@ use crate::{
         UserCredential,
         UserSecret,
         User,
+        ActiveSession,
     },
 };
 
 pub mod find_user;
+pub mod verify_access_token;
+pub mod list_public_keys;
+pub mod list_sessions;
 
 pub trait FindUserCredentialDao {
     fn find_user_credential_by_login(&self, login: String) -> impl std::future::Future<Output = Result<Option<UserCredential>, AppError>> + Send;
@@ -21,3 +25,7 @@ pub trait FindUserSecretDao {
 pub trait FindUserDao {
     fn find_user_by_id(&self, user_id: uuid::Uuid) -> impl std::future::Future<Output = Result<Option<User>, AppError>> + Send;
 }
+
+pub trait ListSessionsDao {
+    fn list_active_sessions(&self, user_id: uuid::Uuid) -> impl std::future::Future<Output = Result<Vec<ActiveSession>, AppError>> + Send;
+}
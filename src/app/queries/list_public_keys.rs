@@ -0,0 +1,22 @@
+use jsonwebtoken::jwk::JwkSet;
+use crate::providers::KeyRingProvider;
+
+pub struct ListPublicKeysQuery<G>
+where
+    G: KeyRingProvider,
+{
+    key_ring_provider: G,
+}
+
+impl<G> ListPublicKeysQuery<G>
+where
+    G: KeyRingProvider,
+{
+    pub fn new(key_ring_provider: G) -> Self {
+        Self { key_ring_provider }
+    }
+
+    pub fn call(&self) -> JwkSet {
+        JwkSet { keys: self.key_ring_provider.public_keys() }
+    }
+}
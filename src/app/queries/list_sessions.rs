@@ -0,0 +1,27 @@
+use crate::{
+    errors::AppError,
+    app::{
+        ActiveSession,
+        queries::ListSessionsDao,
+    },
+};
+
+pub struct ListSessionsQuery<S>
+where
+    S: ListSessionsDao,
+{
+    repo: S,
+}
+
+impl<S> ListSessionsQuery<S>
+where
+    S: ListSessionsDao,
+{
+    pub fn new(repo: S) -> Self {
+        Self { repo }
+    }
+
+    pub async fn call(&self, user_id: uuid::Uuid) -> Result<Vec<ActiveSession>, AppError> {
+        self.repo.list_active_sessions(user_id).await
+    }
+}
@@ -0,0 +1,27 @@
+use crate::{
+    errors::AppError,
+    providers::{
+        Claims,
+        TokenDecoderProvider,
+    },
+};
+
+pub struct VerifyAccessTokenQuery<K>
+where
+    K: TokenDecoderProvider,
+{
+    token_decoder_provider: K,
+}
+
+impl<K> VerifyAccessTokenQuery<K>
+where
+    K: TokenDecoderProvider,
+{
+    pub fn new(token_decoder_provider: K) -> Self {
+        Self { token_decoder_provider }
+    }
+
+    pub fn call(&self, access_token: String) -> Result<Claims, AppError> {
+        self.token_decoder_provider.provide(access_token)
+    }
+}
@@ -2,6 +2,16 @@
 #[derive(Debug, validator::Validate, serde::Deserialize)]
 pub struct Config {
     pub server: ServerConfig,
+    pub jwt: JwtConfig,
+    pub admin: AdminConfig,
+    pub session: SessionConfig,
+    pub mailer: MailerConfig,
+    pub sms: SmsConfig,
+    pub totp: TotpConfig,
+    pub webauthn: WebauthnConfig,
+    pub magic_link: MagicLinkConfig,
+    pub password: PasswordConfig,
+    pub breached_passwords: BreachedPasswordsConfig,
     // #[validate(required)]
     pub database_url: String,
     #[validate(range(min = 1))]
@@ -19,6 +29,85 @@ pub struct ServerConfig {
     pub port: u16,
 }
 
+#[derive(Debug, serde::Deserialize)]
+pub struct JwtConfig {
+    pub issuer: String,
+    pub audience: String,
+    pub key_id: String,
+    pub algorithm: jsonwebtoken::Algorithm,
+    pub secret: String,
+    pub private_key_path: String,
+    pub key_dir: String,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct SessionConfig {
+    pub max_active: u32,
+    pub eviction_policy: crate::app::commands::SessionEvictionPolicy,
+    pub idle_timeout_in_days: u32,
+    pub absolute_lifetime_in_days: u32,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct PasswordConfig {
+    pub min_length: usize,
+    pub max_length: usize,
+    pub min_score: u8,
+    pub history_size: usize,
+    pub max_age_in_days: u32,
+    pub pepper: String,
+    pub pepper_path: String,
+    pub pepper_version: u32,
+}
+
+#[derive(Clone, Copy, Debug, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BreachCorpusSource {
+    None,
+    Files,
+    Postgres,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct BreachedPasswordsConfig {
+    pub source: BreachCorpusSource,
+    pub path: String,
+    pub threshold: i64,
+    pub action: crate::app::commands::password_policy::BreachAction,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct MailerConfig {
+    pub outbox_path: String,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct SmsConfig {
+    pub outbox_path: String,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct TotpConfig {
+    pub issuer: String,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct WebauthnConfig {
+    pub rp_id: String,
+    pub rp_name: String,
+    pub origin: String,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct MagicLinkConfig {
+    pub url: String,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct AdminConfig {
+    pub token: String,
+}
+
 impl Config {
     pub fn init() -> Self {
         dotenvy::dotenv().ok();
@@ -27,8 +116,39 @@ impl Config {
             .set_default("database_max_connections", 5).unwrap()
             .set_default("server.host", "0.0.0.0").unwrap()
             .set_default("server.port", 5000).unwrap()
+            .set_default("jwt.issuer", "auth").unwrap()
+            .set_default("jwt.audience", "auth").unwrap()
+            .set_default("jwt.key_id", "default").unwrap()
+            .set_default("jwt.algorithm", "HS256").unwrap()
+            .set_default("jwt.secret", "").unwrap()
+            .set_default("jwt.private_key_path", "").unwrap()
+            .set_default("jwt.key_dir", "").unwrap()
+            .set_default("admin.token", "").unwrap()
+            .set_default("mailer.outbox_path", "").unwrap()
+            .set_default("sms.outbox_path", "").unwrap()
+            .set_default("totp.issuer", "auth").unwrap()
+            .set_default("webauthn.rp_id", "localhost").unwrap()
+            .set_default("webauthn.rp_name", "auth").unwrap()
+            .set_default("webauthn.origin", "http://localhost:5000").unwrap()
+            .set_default("magic_link.url", "http://localhost:5000/magic-link").unwrap()
+            .set_default("session.max_active", 10).unwrap()
+            .set_default("session.eviction_policy", "oldest-first").unwrap()
+            .set_default("session.idle_timeout_in_days", 14).unwrap()
+            .set_default("session.absolute_lifetime_in_days", 30).unwrap()
+            .set_default("password.min_length", 8).unwrap()
+            .set_default("password.max_length", 128).unwrap()
+            .set_default("password.min_score", 2).unwrap()
+            .set_default("password.history_size", 5).unwrap()
+            .set_default("password.max_age_in_days", 0).unwrap()
+            .set_default("password.pepper", "").unwrap()
+            .set_default("password.pepper_path", "").unwrap()
+            .set_default("password.pepper_version", 1).unwrap()
+            .set_default("breached_passwords.source", "none").unwrap()
+            .set_default("breached_passwords.path", "").unwrap()
+            .set_default("breached_passwords.threshold", 1).unwrap()
+            .set_default("breached_passwords.action", "reject").unwrap()
             .add_source(
-                config::Environment::default()
+                config::Environment::default().separator("__")
             )
             .build()
             .unwrap();
@@ -3,88 +3,361 @@ use crate::{
         queries::{
             FindUserCredentialDao,
             FindUserSecretDao,
+            verify_access_token::VerifyAccessTokenQuery,
+            ListSessionsDao,
+            list_public_keys::ListPublicKeysQuery,
+            list_sessions::ListSessionsQuery,
         },
         commands::{
             RegisterUserDao,
+            ConfirmCredentialDao,
             AuthenticateUserDao,
             RefreshSessionDao,
+            DestroySessionDao,
             ChangePasswordDao,
+            PasswordResetDao,
+            TotpDao,
+            MfaChallengeDao,
+            PasskeyDao,
+            RecoveryCodeDao,
+            MagicLinkDao,
+            BreachedPasswordDao,
+            ImportBreachedPasswordsDao,
+            ImportUsersDao,
             DeleteUserDao,
             RestoreUserDao,
+            SessionPolicy,
+            password_policy::PasswordPolicy,
             register_user::RegisterUserCommand,
             authenticate_user::AuthenticateUserCommand,
             refresh_session::RefreshSessionCommand,
+            destroy_session::DestroySessionCommand,
+            destroy_all_sessions::DestroyAllSessionsCommand,
+            revoke_session::RevokeSessionCommand,
+            confirm_credential::ConfirmCredentialCommand,
+            request_credential_confirmation::RequestCredentialConfirmationCommand,
+            confirm_phone::ConfirmPhoneCommand,
+            request_password_reset::RequestPasswordResetCommand,
+            reset_password::ResetPasswordCommand,
+            enroll_totp::EnrollTotpCommand,
+            confirm_totp::ConfirmTotpCommand,
+            verify_totp_challenge::VerifyTotpChallengeCommand,
+            start_passkey_registration::StartPasskeyRegistrationCommand,
+            finish_passkey_registration::FinishPasskeyRegistrationCommand,
+            start_passkey_login::StartPasskeyLoginCommand,
+            finish_passkey_login::FinishPasskeyLoginCommand,
+            regenerate_recovery_codes::RegenerateRecoveryCodesCommand,
+            verify_recovery_code_challenge::VerifyRecoveryCodeChallengeCommand,
+            request_magic_link::RequestMagicLinkCommand,
+            redeem_magic_link::RedeemMagicLinkCommand,
+            import_breached_passwords::ImportBreachedPasswordsCommand,
+            import_users::ImportUsersCommand,
+            expire_password::ExpirePasswordCommand,
             change_password::ChangePasswordCommand,
             delete_user::SoftDeleteUserCommand,
             restore_user::RestoreUserCommand,
+            reload_signing_keys::ReloadSigningKeysCommand,
+            promote_signing_key::PromoteSigningKeyCommand,
         },
     },
-    providers::{HashFuncProvider, HashVerifierProvider, IdProvider, TokenEncoderProvider},
+    providers::{
+        HashFuncProvider,
+        HashVerifierProvider,
+        IdProvider,
+        TokenEncoderProvider,
+        TokenDecoderProvider,
+        KeyRingProvider,
+        MailerProvider,
+        SmsProvider,
+        TotpProvider,
+        WebauthnProvider,
+        argon2_hasher::Argon2HasherProvider,
+        multi_algorithm_verifier::MultiAlgorithmVerifierProvider,
+        refresh_token_generator::RefreshTokenGeneratorProvider,
+        otp_generator::OtpGeneratorProvider,
+        recovery_code_generator::RecoveryCodeGeneratorProvider,
+        jwt_encoder::JwtEncoderProvider,
+        jwt_decoder::JwtDecoderProvider,
+        jwt_key_ring::JwtKeyRing,
+        file_mailer::FileMailerProvider,
+        file_sms::FileSmsProvider,
+        hmac_totp::HmacTotpProvider,
+        webauthn_verifier::WebauthnVerifierProvider,
+    },
+    adapters::{
+        postgres::UserRepository,
+        breach_corpus::BreachCorpus,
+    },
 };
 
-pub struct Container<H, V, I, T, R, A, S, D, C>
+#[cfg(test)]
+pub mod testing;
+
+pub type AppContainer = Container<
+    Argon2HasherProvider,
+    MultiAlgorithmVerifierProvider,
+    RefreshTokenGeneratorProvider,
+    OtpGeneratorProvider,
+    RecoveryCodeGeneratorProvider,
+    JwtEncoderProvider,
+    JwtDecoderProvider,
+    JwtKeyRing,
+    FileMailerProvider,
+    FileSmsProvider,
+    HmacTotpProvider,
+    WebauthnVerifierProvider,
+    UserRepository,
+    UserRepository,
+    UserRepository,
+    UserRepository,
+    UserRepository,
+    BreachCorpus,
+>;
+
+// зависимости контейнера сгруппированы, чтобы не передавать два десятка аргументов подряд
+pub struct Providers<H, V, I, O, E, T, K, G, M, P, F, W> {
+    pub hash_func: H,
+    pub hash_verifier: V,
+    pub id: I,
+    pub otp: O,
+    pub recovery_code: E,
+    pub token_encoder: T,
+    pub token_decoder: K,
+    pub key_ring: G,
+    pub mailer: M,
+    pub sms: P,
+    pub totp: F,
+    pub webauthn: W,
+}
+
+pub struct Repositories<R, A, S, D, C, B> {
+    pub register_user: R,
+    pub authenticate_user: A,
+    pub refresh_session: S,
+    pub delete_user: D,
+    pub restore_user: C,
+    pub breach_corpus: B,
+}
+
+pub struct Settings {
+    pub session_policy: SessionPolicy,
+    pub password_policy: PasswordPolicy,
+    pub magic_link_url: String,
+}
+
+pub struct Container<H, V, I, O, E, T, K, G, M, P, F, W, R, A, S, D, C, B>
 where
     H: HashFuncProvider + Clone,
     V: HashVerifierProvider + Clone,
     I: IdProvider + Clone,
+    O: IdProvider + Clone,
+    E: IdProvider + Clone,
     T: TokenEncoderProvider + Clone,
-    R: RegisterUserDao,
-    A: FindUserCredentialDao + FindUserSecretDao+ AuthenticateUserDao + ChangePasswordDao + Clone,
-    S: RefreshSessionDao,
-    D: DeleteUserDao,
-    C: RestoreUserDao,
+    K: TokenDecoderProvider,
+    G: KeyRingProvider + Clone,
+    M: MailerProvider + Clone,
+    P: SmsProvider + Clone,
+    F: TotpProvider + Clone,
+    W: WebauthnProvider + Clone,
+    R: RegisterUserDao + FindUserCredentialDao + ConfirmCredentialDao + ImportBreachedPasswordsDao + ImportUsersDao + Clone,
+    A: FindUserCredentialDao + FindUserSecretDao + AuthenticateUserDao + ChangePasswordDao + PasswordResetDao + DestroySessionDao + TotpDao + MfaChallengeDao + PasskeyDao + RecoveryCodeDao + MagicLinkDao + Clone,
+    S: RefreshSessionDao + DestroySessionDao + ListSessionsDao + Clone,
+    D: FindUserSecretDao + DeleteUserDao,
+    C: FindUserSecretDao + RestoreUserDao,
+    B: BreachedPasswordDao + Clone,
 {
-    pub register_user_command: RegisterUserCommand<H, R>,
+    pub register_user_command: RegisterUserCommand<H, I, O, M, P, R, B>,
+    pub confirm_credential_command: ConfirmCredentialCommand<R>,
+    pub request_credential_confirmation_command: RequestCredentialConfirmationCommand<I, O, M, P, R>,
+    pub confirm_phone_command: ConfirmPhoneCommand<R>,
     pub authenticate_user_command: AuthenticateUserCommand<H, V, I, T, A>,
     pub refresh_session_command: RefreshSessionCommand<I, T, S>,
-    pub change_password_command: ChangePasswordCommand<H, V, A>,
+    pub destroy_session_command: DestroySessionCommand<S>,
+    pub destroy_all_sessions_command: DestroyAllSessionsCommand<S>,
+    pub revoke_session_command: RevokeSessionCommand<S>,
+    pub list_sessions_query: ListSessionsQuery<S>,
+    pub change_password_command: ChangePasswordCommand<H, V, A, B>,
+    pub request_password_reset_command: RequestPasswordResetCommand<I, M, A>,
+    pub reset_password_command: ResetPasswordCommand<H, V, A, B>,
+    pub enroll_totp_command: EnrollTotpCommand<F, A>,
+    pub confirm_totp_command: ConfirmTotpCommand<F, E, H, A>,
+    pub verify_totp_challenge_command: VerifyTotpChallengeCommand<I, T, F, A>,
+    pub start_passkey_registration_command: StartPasskeyRegistrationCommand<I, W, A>,
+    pub finish_passkey_registration_command: FinishPasskeyRegistrationCommand<W, E, H, A>,
+    pub start_passkey_login_command: StartPasskeyLoginCommand<I, W, A>,
+    pub finish_passkey_login_command: FinishPasskeyLoginCommand<I, T, W, A>,
+    pub regenerate_recovery_codes_command: RegenerateRecoveryCodesCommand<E, H, V, F, A>,
+    pub verify_recovery_code_challenge_command: VerifyRecoveryCodeChallengeCommand<I, T, V, A>,
+    pub request_magic_link_command: RequestMagicLinkCommand<I, M, A>,
+    pub redeem_magic_link_command: RedeemMagicLinkCommand<I, T, A>,
+    pub import_breached_passwords_command: ImportBreachedPasswordsCommand<R>,
+    pub import_users_command: ImportUsersCommand<V, R>,
+    pub expire_password_command: ExpirePasswordCommand<A>,
     pub delete_user_command: SoftDeleteUserCommand<V, D>,
     pub restore_user_command: RestoreUserCommand<V, C>,
+    pub verify_access_token_query: VerifyAccessTokenQuery<K>,
+    pub list_public_keys_query: ListPublicKeysQuery<G>,
+    pub reload_signing_keys_command: ReloadSigningKeysCommand<G>,
+    pub promote_signing_key_command: PromoteSigningKeyCommand<G>,
 }
 
-impl<H, V, I, T, R, A, S, D, C> Container<H, V, I, T, R, A, S, D, C>
+impl<H, V, I, O, E, T, K, G, M, P, F, W, R, A, S, D, C, B> Container<H, V, I, O, E, T, K, G, M, P, F, W, R, A, S, D, C, B>
 where
     H: HashFuncProvider + Clone,
     V: HashVerifierProvider + Clone,
     I: IdProvider + Clone,
+    O: IdProvider + Clone,
+    E: IdProvider + Clone,
     T: TokenEncoderProvider + Clone,
-    R: RegisterUserDao,
-    A: FindUserCredentialDao + FindUserSecretDao + AuthenticateUserDao + ChangePasswordDao + Clone,
-    S: RefreshSessionDao,
-    D: DeleteUserDao,
-    C: RestoreUserDao,
+    K: TokenDecoderProvider,
+    G: KeyRingProvider + Clone,
+    M: MailerProvider + Clone,
+    P: SmsProvider + Clone,
+    F: TotpProvider + Clone,
+    W: WebauthnProvider + Clone,
+    R: RegisterUserDao + FindUserCredentialDao + ConfirmCredentialDao + ImportBreachedPasswordsDao + ImportUsersDao + Clone,
+    A: FindUserCredentialDao + FindUserSecretDao + AuthenticateUserDao + ChangePasswordDao + PasswordResetDao + DestroySessionDao + TotpDao + MfaChallengeDao + PasskeyDao + RecoveryCodeDao + MagicLinkDao + Clone,
+    S: RefreshSessionDao + DestroySessionDao + ListSessionsDao + Clone,
+    D: FindUserSecretDao + DeleteUserDao,
+    C: FindUserSecretDao + RestoreUserDao,
+    B: BreachedPasswordDao + Clone,
 {
-    pub fn new(
-        hash_func_provider: H,
-        hash_verifier_provider: V,
-        id_provider: I,
-        token_provider: T,
-        register_user_dao: R, 
-        authenticate_user_dao: A,
-        refresh_session_dao: S,
-        delete_user_dao: D,
-        restore_user_dao: C,
-    ) -> Self {
-        let register_user_command = RegisterUserCommand::new(hash_func_provider.clone(), register_user_dao);
+    pub fn new(providers: Providers<H, V, I, O, E, T, K, G, M, P, F, W>, repositories: Repositories<R, A, S, D, C, B>, settings: Settings) -> Self {
+        let Providers { hash_func: hash_func_provider, hash_verifier: hash_verifier_provider, id: id_provider, otp: otp_provider, recovery_code: recovery_code_provider, token_encoder: token_provider, token_decoder: token_decoder_provider, key_ring: key_ring_provider, mailer: mailer_provider, sms: sms_provider, totp: totp_provider, webauthn: webauthn_provider } = providers;
+        let Repositories { register_user: register_user_dao, authenticate_user: authenticate_user_dao, refresh_session: refresh_session_dao, delete_user: delete_user_dao, restore_user: restore_user_dao, breach_corpus } = repositories;
+        let Settings { session_policy, password_policy, magic_link_url } = settings;
+
+        let register_user_command = RegisterUserCommand::new(
+            hash_func_provider.clone(),
+            id_provider.clone(),
+            otp_provider.clone(),
+            mailer_provider.clone(),
+            sms_provider.clone(),
+            register_user_dao.clone(),
+            breach_corpus.clone(),
+            password_policy,
+        );
+        let confirm_credential_command = ConfirmCredentialCommand::new(register_user_dao.clone());
+        let import_breached_passwords_command = ImportBreachedPasswordsCommand::new(register_user_dao.clone());
+        let import_users_command = ImportUsersCommand::new(hash_verifier_provider.clone(), register_user_dao.clone());
+        let request_credential_confirmation_command = RequestCredentialConfirmationCommand::new(
+            id_provider.clone(),
+            otp_provider,
+            mailer_provider.clone(),
+            sms_provider,
+            register_user_dao.clone(),
+        );
+        let confirm_phone_command = ConfirmPhoneCommand::new(register_user_dao);
         let authenticate_user_command = AuthenticateUserCommand::new(
             hash_func_provider.clone(), 
             hash_verifier_provider.clone(), 
             id_provider.clone(), 
             token_provider.clone(), 
             authenticate_user_dao.clone(),
+            session_policy,
+            password_policy,
+        );
+        let refresh_session_command = RefreshSessionCommand::new(id_provider.clone(), token_provider.clone(), refresh_session_dao.clone(), session_policy);
+        let destroy_session_command = DestroySessionCommand::new(refresh_session_dao.clone());
+        let destroy_all_sessions_command = DestroyAllSessionsCommand::new(refresh_session_dao.clone());
+        let revoke_session_command = RevokeSessionCommand::new(refresh_session_dao.clone());
+        let list_sessions_query = ListSessionsQuery::new(refresh_session_dao);
+        let request_password_reset_command = RequestPasswordResetCommand::new(id_provider.clone(), mailer_provider.clone(), authenticate_user_dao.clone());
+        let request_magic_link_command = RequestMagicLinkCommand::new(id_provider.clone(), mailer_provider, authenticate_user_dao.clone(), magic_link_url);
+        let reset_password_command = ResetPasswordCommand::new(
+            hash_func_provider.clone(),
+            hash_verifier_provider.clone(),
+            authenticate_user_dao.clone(),
+            breach_corpus.clone(),
+            password_policy,
+        );
+        let enroll_totp_command = EnrollTotpCommand::new(totp_provider.clone(), authenticate_user_dao.clone());
+        let confirm_totp_command = ConfirmTotpCommand::new(
+            totp_provider.clone(),
+            recovery_code_provider.clone(),
+            hash_func_provider.clone(),
+            authenticate_user_dao.clone(),
+        );
+        let verify_totp_challenge_command = VerifyTotpChallengeCommand::new(
+            id_provider.clone(),
+            token_provider.clone(),
+            totp_provider.clone(),
+            authenticate_user_dao.clone(),
+            session_policy,
+        );
+        let start_passkey_registration_command = StartPasskeyRegistrationCommand::new(id_provider.clone(), webauthn_provider.clone(), authenticate_user_dao.clone());
+        let finish_passkey_registration_command = FinishPasskeyRegistrationCommand::new(
+            webauthn_provider.clone(),
+            recovery_code_provider.clone(),
+            hash_func_provider.clone(),
+            authenticate_user_dao.clone(),
+        );
+        let start_passkey_login_command = StartPasskeyLoginCommand::new(id_provider.clone(), webauthn_provider.clone(), authenticate_user_dao.clone());
+        let finish_passkey_login_command = FinishPasskeyLoginCommand::new(
+            id_provider.clone(),
+            token_provider.clone(),
+            webauthn_provider,
+            authenticate_user_dao.clone(),
+            session_policy,
+        );
+        let regenerate_recovery_codes_command = RegenerateRecoveryCodesCommand::new(
+            recovery_code_provider,
+            hash_func_provider.clone(),
+            hash_verifier_provider.clone(),
+            totp_provider,
+            authenticate_user_dao.clone(),
+        );
+        let redeem_magic_link_command = RedeemMagicLinkCommand::new(id_provider.clone(), token_provider.clone(), authenticate_user_dao.clone(), session_policy);
+        let verify_recovery_code_challenge_command = VerifyRecoveryCodeChallengeCommand::new(
+            id_provider,
+            token_provider,
+            hash_verifier_provider.clone(),
+            authenticate_user_dao.clone(),
+            session_policy,
         );
-        let refresh_session_command = RefreshSessionCommand::new(id_provider, token_provider, refresh_session_dao);
-        let change_password_command = ChangePasswordCommand::new(hash_func_provider, hash_verifier_provider.clone(), authenticate_user_dao);
+        let expire_password_command = ExpirePasswordCommand::new(authenticate_user_dao.clone());
+        let change_password_command = ChangePasswordCommand::new(hash_func_provider, hash_verifier_provider.clone(), authenticate_user_dao, breach_corpus, password_policy);
         let delete_user_command = SoftDeleteUserCommand::new(hash_verifier_provider.clone(), delete_user_dao);
         let restore_user_command = RestoreUserCommand::new(hash_verifier_provider, restore_user_dao);
+        let verify_access_token_query = VerifyAccessTokenQuery::new(token_decoder_provider);
+        let reload_signing_keys_command = ReloadSigningKeysCommand::new(key_ring_provider.clone());
+        let promote_signing_key_command = PromoteSigningKeyCommand::new(key_ring_provider.clone());
+        let list_public_keys_query = ListPublicKeysQuery::new(key_ring_provider);
 
         Self {
             register_user_command,
+            confirm_credential_command,
+            request_credential_confirmation_command,
+            confirm_phone_command,
             authenticate_user_command,
             refresh_session_command,
+            destroy_session_command,
+            destroy_all_sessions_command,
+            revoke_session_command,
+            list_sessions_query,
             change_password_command,
+            request_password_reset_command,
+            reset_password_command,
+            enroll_totp_command,
+            confirm_totp_command,
+            verify_totp_challenge_command,
+            start_passkey_registration_command,
+            finish_passkey_registration_command,
+            start_passkey_login_command,
+            finish_passkey_login_command,
+            regenerate_recovery_codes_command,
+            verify_recovery_code_challenge_command,
+            request_magic_link_command,
+            redeem_magic_link_command,
+            import_breached_passwords_command,
+            import_users_command,
+            expire_password_command,
             delete_user_command,
             restore_user_command,
+            verify_access_token_query,
+            list_public_keys_query,
+            reload_signing_keys_command,
+            promote_signing_key_command,
         }
     }
 }
@@ -0,0 +1,108 @@
+use testcontainers_modules::{
+    postgres,
+    testcontainers::{
+        ContainerAsync,
+        ImageExt,
+        runners::AsyncRunner,
+    },
+};
+use crate::{
+    di,
+    providers,
+    adapters,
+    app::commands::{
+        SessionPolicy,
+        password_policy::PasswordPolicy,
+    },
+};
+
+// то, чем тесты отличаются друг от друга; остальное собирается одинаково
+pub struct TestSettings {
+    pub session_policy: SessionPolicy,
+    pub password_policy: PasswordPolicy,
+    pub breach_corpus: adapters::breach_corpus::BreachCorpus,
+    pub mailer_outbox_path: std::path::PathBuf,
+    pub sms_outbox_path: std::path::PathBuf,
+}
+
+impl Default for TestSettings {
+    fn default() -> Self {
+        Self {
+            session_policy: SessionPolicy::default(),
+            password_policy: PasswordPolicy::default(),
+            breach_corpus: adapters::breach_corpus::BreachCorpus::Disabled,
+            mailer_outbox_path: std::env::temp_dir().join(format!("auth-test-mailer-{}.txt", std::process::id())),
+            sms_outbox_path: std::env::temp_dir().join(format!("auth-test-sms-{}.txt", std::process::id())),
+        }
+    }
+}
+
+// база живёт, пока жив postgres: при разборе структуры его нужно связать с именем, а не с _
+pub struct TestApp {
+    pub container: di::AppContainer,
+    pub db_pool: sqlx::PgPool,
+    pub postgres: ContainerAsync<postgres::Postgres>,
+}
+
+impl TestApp {
+    pub async fn start() -> Self {
+        Self::start_with(TestSettings::default()).await
+    }
+
+    pub async fn start_with(settings: TestSettings) -> Self {
+        let postgres_container = postgres::Postgres::default()
+            .with_tag("18.1-alpine")
+            .start()
+            .await
+            .unwrap();
+
+        let url = &format!(
+            "postgres://postgres:postgres@{}:{}/postgres",
+            postgres_container.get_host().await.unwrap(),
+            postgres_container.get_host_port_ipv4(5432).await.unwrap()
+        );
+
+        let db_pool = sqlx::postgres::PgPoolOptions::new().max_connections(1).connect(url).await.unwrap();
+        sqlx::migrate!("./migrations").run(&db_pool).await.unwrap();
+
+        Self { container: container(&db_pool, settings), db_pool, postgres: postgres_container }
+    }
+}
+
+// контейнер поверх уже поднятой базы, например ещё один с другой политикой
+pub fn container(db_pool: &sqlx::PgPool, settings: TestSettings) -> di::AppContainer {
+    let jwt_key = providers::jwt_key::JwtKey::from_secret("test".to_string(), b"my-super-secret-key").unwrap();
+    let jwt_key_ring = providers::jwt_key_ring::JwtKeyRing::new(jwt_key);
+    let argon2_verifier = providers::argon2_verifier::Argon2VerifierProvider::new(8, 1, 1);
+    let user_repo = adapters::postgres::UserRepository::new(db_pool.clone());
+
+    di::Container::new(
+        di::Providers {
+            hash_func: providers::argon2_hasher::Argon2HasherProvider::new(8, 1, 1),
+            hash_verifier: providers::multi_algorithm_verifier::MultiAlgorithmVerifierProvider::new(argon2_verifier),
+            id: providers::refresh_token_generator::RefreshTokenGeneratorProvider,
+            otp: providers::otp_generator::OtpGeneratorProvider,
+            recovery_code: providers::recovery_code_generator::RecoveryCodeGeneratorProvider,
+            token_encoder: providers::jwt_encoder::JwtEncoderProvider::new("auth".to_string(), "auth".to_string(), jwt_key_ring.clone()),
+            token_decoder: providers::jwt_decoder::JwtDecoderProvider::new("auth".to_string(), "auth".to_string(), jwt_key_ring.clone()),
+            key_ring: jwt_key_ring,
+            mailer: providers::file_mailer::FileMailerProvider::new(settings.mailer_outbox_path),
+            sms: providers::file_sms::FileSmsProvider::new(settings.sms_outbox_path),
+            totp: providers::hmac_totp::HmacTotpProvider::new("auth".to_string()),
+            webauthn: providers::webauthn_verifier::WebauthnVerifierProvider::new("localhost".to_string(), "auth".to_string(), "http://localhost:5000".to_string()),
+        },
+        di::Repositories {
+            register_user: user_repo.clone(),
+            authenticate_user: user_repo.clone(),
+            refresh_session: user_repo.clone(),
+            delete_user: user_repo.clone(),
+            restore_user: user_repo,
+            breach_corpus: settings.breach_corpus,
+        },
+        di::Settings {
+            session_policy: settings.session_policy,
+            password_policy: settings.password_policy,
+            magic_link_url: "http://localhost:5000/magic-link".to_string(),
+        },
+    )
+}
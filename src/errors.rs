@@ -1,15 +1,32 @@
 use std::fmt::{self, Display, Formatter};
+use crate::app::commands::password_policy::PasswordWeakness;
 
 #[derive(thiserror::Error, Debug)]
 pub enum AppError {
     UsernameIsTaken,
     UnknownDatabaseError,
-    WeakPassword,
+    WeakPassword(Vec<PasswordWeakness>),
     UnknownError,
     LoginError,
     TempLocked,
     LoginRequired,
     NotFound,
+    AccessDenied,
+    ExpiredToken,
+    MalformedToken,
+    InvalidTokenSignature,
+    InvalidToken,
+    InvalidKeyRing,
+    RefreshTokenReused,
+    TooManySessions,
+    SessionExpired,
+    InvalidLogin,
+    UnconfirmedCredential,
+    MfaAlreadyEnabled,
+    MfaNotEnabled,
+    InvalidPasskey,
+    MalformedRecord,
+    UnsupportedPasswordDigest,
 }
 
 impl Display for AppError {
@@ -18,11 +35,27 @@ impl Display for AppError {
             AppError::UsernameIsTaken => write!(f, "Username is taken"),
             AppError::UnknownDatabaseError => write!(f, "Unknown database error"),
             AppError::UnknownError => write!(f, "Unknown system error"),
-            AppError::WeakPassword => write!(f, "Weak password"),
+            AppError::WeakPassword(_) => write!(f, "Weak password"),
             AppError::LoginError => write!(f, "Incorrect login or password"),
             AppError::TempLocked => write!(f, "Temporarily locked"),
             AppError::LoginRequired => write!(f, "Login required"),
             AppError::NotFound => write!(f, "Not Found"),
+            AppError::AccessDenied => write!(f, "Access denied"),
+            AppError::ExpiredToken => write!(f, "Token has expired"),
+            AppError::MalformedToken => write!(f, "Malformed token"),
+            AppError::InvalidTokenSignature => write!(f, "Invalid token signature"),
+            AppError::InvalidToken => write!(f, "Invalid token"),
+            AppError::InvalidKeyRing => write!(f, "Invalid signing key ring"),
+            AppError::RefreshTokenReused => write!(f, "Refresh token reuse detected"),
+            AppError::TooManySessions => write!(f, "Too many active sessions"),
+            AppError::SessionExpired => write!(f, "Session has expired"),
+            AppError::InvalidLogin => write!(f, "Invalid login"),
+            AppError::UnconfirmedCredential => write!(f, "Credential is not confirmed"),
+            AppError::MfaAlreadyEnabled => write!(f, "Two-factor authentication is already enabled"),
+            AppError::MfaNotEnabled => write!(f, "Two-factor authentication is not enabled"),
+            AppError::InvalidPasskey => write!(f, "Invalid passkey"),
+            AppError::MalformedRecord => write!(f, "Malformed record"),
+            AppError::UnsupportedPasswordDigest => write!(f, "Unsupported password digest format"),
         }
     }
 }
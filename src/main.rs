@@ -19,38 +19,149 @@ async fn main() {
     println!("SERVER_URL={}:{}", conf.server.host, conf.server.port);
     let db_pool = sqlx::postgres::PgPoolOptions::new().max_connections(DATABASE_MAX_CONNECTIONS).connect(&conf.database_url).await.unwrap();
 
-    let argon2_hasher = providers::argon2_hasher::Argon2HasherProvider::new(ARGON2_MEMORY_COST, ARGON2_TIME_COST, ARGON2_PARALLELISM);
-    let argon2_verifier = providers::argon2_verifier::Argon2VerifierProvider::new(ARGON2_MEMORY_COST, ARGON2_TIME_COST, ARGON2_PARALLELISM);
+    let password_pepper = providers::password_pepper::PasswordPepper::new(conf.password.pepper_version, &conf.password.pepper, &conf.password.pepper_path)
+        .expect("Password pepper is misconfigured: PASSWORD__PEPPER_PATH must be readable and hold a key for PASSWORD__PEPPER_VERSION");
+    let argon2_hasher = providers::argon2_hasher::Argon2HasherProvider::new(ARGON2_MEMORY_COST, ARGON2_TIME_COST, ARGON2_PARALLELISM)
+        .with_pepper(password_pepper.clone());
+    let argon2_verifier = providers::argon2_verifier::Argon2VerifierProvider::new(ARGON2_MEMORY_COST, ARGON2_TIME_COST, ARGON2_PARALLELISM)
+        .with_pepper(password_pepper);
+    // bcrypt, scrypt, PBKDF2 и солёные SHA из импортированных баз проверяются и перехешируются при входе
+    let password_verifier = providers::multi_algorithm_verifier::MultiAlgorithmVerifierProvider::new(argon2_verifier);
 
     let refresh_token_generator = providers::refresh_token_generator::RefreshTokenGeneratorProvider;
-    let jwt_encoder = providers::jwt_encoder::JwtEncoderProvider;
+    let otp_generator = providers::otp_generator::OtpGeneratorProvider;
+    let recovery_code_generator = providers::recovery_code_generator::RecoveryCodeGeneratorProvider;
+    let jwt_key_ring = if conf.jwt.key_dir.is_empty() {
+        let jwt_key = providers::jwt_key::JwtKey::new(conf.jwt.key_id.clone(), conf.jwt.algorithm, &conf.jwt.secret, &conf.jwt.private_key_path)
+            .expect("JWT signing key is not configured: set JWT__SECRET, JWT__PRIVATE_KEY_PATH or JWT__KEY_DIR");
+        providers::jwt_key_ring::JwtKeyRing::new(jwt_key)
+    } else {
+        providers::jwt_key_ring::JwtKeyRing::load(std::path::PathBuf::from(&conf.jwt.key_dir))
+            .expect("JWT key directory must contain a keys.toml manifest with exactly one active key")
+    };
+    let jwt_encoder = providers::jwt_encoder::JwtEncoderProvider::new(conf.jwt.issuer.clone(), conf.jwt.audience.clone(), jwt_key_ring.clone());
+    let jwt_decoder = providers::jwt_decoder::JwtDecoderProvider::new(conf.jwt.issuer.clone(), conf.jwt.audience.clone(), jwt_key_ring.clone());
+
+    let mailer = providers::file_mailer::FileMailerProvider::new(required_path(&conf.mailer.outbox_path, "MAILER__OUTBOX_PATH"));
+    let sms = providers::file_sms::FileSmsProvider::new(required_path(&conf.sms.outbox_path, "SMS__OUTBOX_PATH"));
+    let totp = providers::hmac_totp::HmacTotpProvider::new(conf.totp.issuer.clone());
+    let webauthn = providers::webauthn_verifier::WebauthnVerifierProvider::new(
+        conf.webauthn.rp_id.clone(),
+        conf.webauthn.rp_name.clone(),
+        conf.webauthn.origin.clone(),
+    );
 
     let user_repo = adapters::postgres::UserRepository::new(db_pool.clone());
+    let breach_corpus = match conf.breached_passwords.source {
+        config::BreachCorpusSource::None => adapters::breach_corpus::BreachCorpus::Disabled,
+        config::BreachCorpusSource::Files => adapters::breach_corpus::BreachCorpus::Files(std::path::PathBuf::from(&conf.breached_passwords.path)),
+        config::BreachCorpusSource::Postgres => adapters::breach_corpus::BreachCorpus::Postgres(user_repo.clone()),
+    };
     let container = di::Container::new(
-        argon2_hasher,
-        argon2_verifier,
-        refresh_token_generator,
-        jwt_encoder,
-        user_repo.clone(),
-        user_repo.clone(),
-        user_repo.clone(),
-        user_repo.clone(),
-        user_repo,
+        di::Providers {
+            hash_func: argon2_hasher,
+            hash_verifier: password_verifier,
+            id: refresh_token_generator,
+            otp: otp_generator,
+            recovery_code: recovery_code_generator,
+            token_encoder: jwt_encoder,
+            token_decoder: jwt_decoder,
+            key_ring: jwt_key_ring,
+            mailer,
+            sms,
+            totp,
+            webauthn,
+        },
+        di::Repositories {
+            register_user: user_repo.clone(),
+            authenticate_user: user_repo.clone(),
+            refresh_session: user_repo.clone(),
+            delete_user: user_repo.clone(),
+            restore_user: user_repo,
+            breach_corpus,
+        },
+        di::Settings {
+            session_policy: app::commands::SessionPolicy {
+                max_active_sessions: conf.session.max_active,
+                eviction_policy: conf.session.eviction_policy,
+                idle_timeout_in_days: conf.session.idle_timeout_in_days,
+                absolute_lifetime_in_days: conf.session.absolute_lifetime_in_days,
+            },
+            password_policy: app::commands::password_policy::PasswordPolicy {
+                min_length: conf.password.min_length,
+                max_length: conf.password.max_length,
+                min_score: conf.password.min_score,
+                breach_threshold: conf.breached_passwords.threshold,
+                breach_action: conf.breached_passwords.action,
+                history_size: conf.password.history_size,
+                max_age_in_days: conf.password.max_age_in_days,
+            },
+            magic_link_url: conf.magic_link.url.clone(),
+        },
     );
-    // let res = container.register_user_command.call("qotofey".to_string(), "Qwerty123!".to_string()).await.unwrap();
-    let res = container.authenticate_user_command.call("qotofey  ".to_string(), "Qwerty123!".to_string()).await.unwrap();
-    let res = container.refresh_session_command.call(res.refresh_token).await.unwrap();
-    let res = container.refresh_session_command.call(res.refresh_token).await.unwrap();
-    let res = container.refresh_session_command.call(res.refresh_token).await.unwrap();
-    let _ = container.change_password_command.call(res.user_id, "Qwerty123!".to_string(), "123123".to_string()).await.unwrap();
-    let res = container.authenticate_user_command.call("qotofey".to_string(), "123123".to_string()).await.unwrap();
-    let res = container.refresh_session_command.call(res.refresh_token).await.unwrap();
-    let res = container.refresh_session_command.call(res.refresh_token).await.unwrap();
-    let _ = container.change_password_command.call(res.user_id, "123123".to_string(), "Qwerty123!".to_string()).await.unwrap();
-    let res = container.refresh_session_command.call(res.refresh_token).await.unwrap();
-
-    println!("Refresh Token = {} \nAccess Token = {}", res.refresh_token, res.access_token);
-    let _ = container.delete_user_command.call(res.user_id, "123123".to_string()).await.unwrap();
-    let _ = container.restore_user_command.call(res.user_id, "123123".to_string()).await.unwrap();
+
+    let mut args = std::env::args().skip(1);
+    if let Some(subcommand) = args.next() {
+        run_subcommand(&container, &subcommand, args).await;
+        return;
+    }
+
+    let container = std::sync::Arc::new(container);
+
+    let signal_container = container.clone();
+    tokio::spawn(async move {
+        let mut hangup = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup()).unwrap();
+        while hangup.recv().await.is_some() {
+            if let Err(err) = signal_container.reload_signing_keys_command.call() {
+                eprintln!("Signing keys were not reloaded: {err}");
+            }
+        }
+    });
+
+    adapters::http::serve(&conf.server.host, conf.server.port, container, conf.admin.token).await.unwrap();
+}
+
+// без пути сервис не стартует: запасного вывода в stdout нет, туда попали бы коды из сообщений
+fn required_path(path: &str, variable: &str) -> std::path::PathBuf {
+    assert!(!path.is_empty(), "{variable} is not configured");
+    std::path::PathBuf::from(path)
 }
 
+// разовые задачи обслуживания выполняются вместо запуска сервера
+async fn run_subcommand(container: &di::AppContainer, subcommand: &str, mut args: impl Iterator<Item = String>) {
+    match subcommand {
+        "import-breached-passwords" => {
+            let range_dir = args.next().expect("Usage: auth import-breached-passwords <range-dir>");
+            match container.import_breached_passwords_command.call(std::path::PathBuf::from(range_dir)).await {
+                Ok(imported) => println!("Imported {imported} breached password ranges"),
+                Err(err) => {
+                    eprintln!("Breached passwords were not imported: {err}");
+                    std::process::exit(1);
+                },
+            }
+        },
+        "import-users" => {
+            let path = args.next().map(std::path::PathBuf::from).expect("Usage: auth import-users <file.csv|file.jsonl> [--dry-run]");
+            let dry_run = args.any(|arg| arg == "--dry-run");
+            let format = app::commands::import_users::ImportFormat::from_path(&path).expect("Users file must have a .csv, .jsonl or .ndjson extension");
+            let source = std::fs::File::open(&path).expect("Users file is not readable");
+            match container.import_users_command.call(source, format, dry_run).await {
+                Ok(report) => {
+                    for failure in &report.failures {
+                        eprintln!("Row {} ({}): {}", failure.row, failure.login.as_deref().unwrap_or("-"), failure.error);
+                    }
+                    let verb = if dry_run { "Would import" } else { "Imported" };
+                    println!("{verb} {} users, {} rows failed", report.imported, report.failures.len());
+                },
+                Err(err) => {
+                    eprintln!("Users were not imported: {err}");
+                    std::process::exit(1);
+                },
+            }
+        },
+        _ => {
+            eprintln!("Unknown subcommand: {subcommand}");
+            std::process::exit(2);
+        },
+    }
+}
@@ -1,7 +1,20 @@
+use crate::errors::AppError;
+
 pub mod argon2_hasher;
 pub mod argon2_verifier;
+pub mod multi_algorithm_verifier;
+pub mod password_pepper;
 pub mod jwt_encoder;
+pub mod jwt_decoder;
+pub mod jwt_key;
+pub mod jwt_key_ring;
 pub mod refresh_token_generator;
+pub mod file_mailer;
+pub mod file_sms;
+pub mod otp_generator;
+pub mod recovery_code_generator;
+pub mod hmac_totp;
+pub mod webauthn_verifier;
 
 pub trait HashFuncProvider {
     fn provide(&self, password: String) -> Option<String>;
@@ -14,17 +27,70 @@ pub struct PasswordConfirmation {
 
 pub trait HashVerifierProvider {
     fn provide(&self, password: String, password_digest: String) -> PasswordConfirmation;
+    // распознаёт ли провайдер формат дайджеста, сам пароль не проверяется
+    fn supports(&self, password_digest: &str) -> bool;
 }
 
 pub trait TokenEncoderProvider {
     fn provide(&self, user_id: String) -> Option<String>;
 }
 
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Claims {
+    pub sub: String,
+    pub iss: String,
+    pub aud: String,
+    pub exp: u64,
+    pub iat: u64,
+}
+
 pub trait TokenDecoderProvider {
-    fn provider(&self, token: String) -> Option<String>;
+    fn provide(&self, token: String) -> Result<Claims, AppError>;
+}
+
+pub trait KeyRingProvider {
+    fn reload(&self) -> Result<(), AppError>;
+    fn promote(&self, kid: String) -> Result<(), AppError>;
+    fn public_keys(&self) -> Vec<jsonwebtoken::jwk::Jwk>;
 }
 
 pub trait IdProvider {
     fn provide(&self) -> Option<String>;
 }
 
+
+pub trait MailerProvider {
+    fn send(&self, to: String, subject: String, body: String) -> impl std::future::Future<Output = Result<(), AppError>> + Send;
+}
+
+pub trait SmsProvider {
+    fn send(&self, to: String, text: String) -> impl std::future::Future<Output = Result<(), AppError>> + Send;
+}
+
+pub trait TotpProvider {
+    fn generate_secret(&self) -> Option<String>;
+    fn provisioning_uri(&self, secret: String, account_name: String) -> String;
+    fn generate(&self, secret: String, unix_time: u64) -> Option<String>;
+    // шаг времени, на котором код совпал, нужен для защиты от повторного предъявления
+    fn verify(&self, secret: String, code: String, unix_time: u64) -> Option<u64>;
+}
+
+pub struct PasskeyRegistration {
+    pub challenge: String,
+    pub credential_id: String,
+    pub public_key: Vec<u8>,
+    pub sign_count: u32,
+}
+
+pub struct PasskeyAssertion {
+    pub challenge: String,
+    pub sign_count: u32,
+    pub user_verified: bool,
+}
+
+pub trait WebauthnProvider {
+    fn relying_party_id(&self) -> String;
+    fn relying_party_name(&self) -> String;
+    fn verify_registration(&self, client_data_json: String, attestation_object: String) -> Result<PasskeyRegistration, AppError>;
+    fn verify_assertion(&self, public_key: Vec<u8>, client_data_json: String, authenticator_data: String, signature: String) -> Result<PasskeyAssertion, AppError>;
+}
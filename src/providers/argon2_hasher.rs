@@ -2,25 +2,31 @@ use argon2::{
     Argon2,
     Algorithm,
     Version,
-    Params,
+    KeyId,
+    ParamsBuilder,
     password_hash::{
         rand_core::OsRng,  
         SaltString,
         PasswordHasher, 
     },
 };
-use crate::providers::HashFuncProvider;
+use crate::providers::{HashFuncProvider, password_pepper::PasswordPepper};
 
 #[derive(Clone)]
 pub struct Argon2HasherProvider {
     memory_cost: u32,
     time_cost: u32,
     parallelism: u32,
+    pepper: PasswordPepper,
 }
 
 impl Argon2HasherProvider {
     pub fn new(memory_cost: u32, time_cost: u32, parallelism: u32) -> Self {
-        Self { memory_cost, time_cost, parallelism }
+        Self { memory_cost, time_cost, parallelism, pepper: PasswordPepper::default() }
+    }
+
+    pub fn with_pepper(self, pepper: PasswordPepper) -> Self {
+        Self { pepper, ..self }
     }
 }
 
@@ -28,7 +34,15 @@ impl HashFuncProvider for Argon2HasherProvider {
     fn provide(&self, password: String) -> Option<String> {
         let salt = SaltString::generate(&mut OsRng);
 
-        let params = match Params::new(self.memory_cost, self.time_cost, self.parallelism, None) {
+        // версия перца уходит в keyid дайджеста, сам ключ в базу не попадает
+        let pepper_version = self.pepper.current_version();
+        let password = self.pepper.apply(pepper_version, &password)?;
+        let mut params_builder = ParamsBuilder::new();
+        params_builder.m_cost(self.memory_cost).t_cost(self.time_cost).p_cost(self.parallelism);
+        if let Some(pepper_version) = pepper_version {
+            params_builder.keyid(KeyId::new(&pepper_version.to_be_bytes()).ok()?);
+        }
+        let params = match params_builder.build() {
             Ok(params) => params,
             Err(_) => {
                 // TODO: add logger
@@ -40,7 +54,7 @@ impl HashFuncProvider for Argon2HasherProvider {
             Version::V0x13,
             params,
         );
-        let password_digest = match argon2.hash_password(password.as_bytes(), &salt) {
+        let password_digest = match argon2.hash_password(&password, &salt) {
             Ok(hash) => hash.to_string(),
             Err(_) => {
                 // TODO: add legger
@@ -96,4 +110,19 @@ mod tests {
         assert_eq!(parsed_params.t_cost(), 2);
         assert_eq!(parsed_params.p_cost(), 1);
     }
+
+    #[tokio::test]
+    async fn get_peppered_password_hash() {
+        // Given
+        let pepper = PasswordPepper::from_secret(7, b"pepper").unwrap();
+        let argon2_hasher = Argon2HasherProvider::new(8, 1, 1).with_pepper(pepper);
+
+        // When
+        let password_digest = argon2_hasher.provide("!Qwerty123".to_string()).unwrap();
+        let parsed_hash = argon2::PasswordHash::new(&password_digest).unwrap();
+        let parsed_params = argon2::Params::try_from(&parsed_hash).unwrap();
+
+        // Then
+        assert_eq!(parsed_params.keyid(), 7u32.to_be_bytes());
+    }
 }
@@ -1,29 +1,68 @@
 use argon2::{
     Argon2,
+    Algorithm,
+    Params,
     password_hash::{PasswordHash, PasswordVerifier},
 };
-use crate::providers::{HashVerifierProvider, PasswordConfirmation};
+use crate::providers::{HashVerifierProvider, PasswordConfirmation, password_pepper::PasswordPepper};
 
 #[derive(Clone)]
 pub struct Argon2VerifierProvider {
     memory_cost: u32,
     time_cost: u32,
     parallelism: u32,
+    pepper: PasswordPepper,
 }
 
 impl Argon2VerifierProvider {
     pub fn new(memory_cost: u32, time_cost: u32, parallelism: u32) -> Self {
-        Self { memory_cost, time_cost, parallelism }
+        Self { memory_cost, time_cost, parallelism, pepper: PasswordPepper::default() }
+    }
+
+    pub fn with_pepper(self, pepper: PasswordPepper) -> Self {
+        Self { pepper, ..self }
+    }
+}
+
+// какой перец записан в keyid дайджеста
+enum DigestPepper {
+    None,
+    Version(u32),
+    // keyid не наш или параметры не разбираются
+    Foreign,
+}
+
+fn digest_pepper(parsed_hash: &PasswordHash) -> DigestPepper {
+    let Ok(params) = Params::try_from(parsed_hash) else {
+        return DigestPepper::Foreign;
+    };
+    match params.keyid() {
+        [] => DigestPepper::None,
+        keyid => keyid.try_into().map_or(DigestPepper::Foreign, |version| DigestPepper::Version(u32::from_be_bytes(version))),
     }
 }
 
 impl HashVerifierProvider for Argon2VerifierProvider {
     fn provide(&self, password: String, password_digest: String) -> PasswordConfirmation {
-        // TODO: избавиться от unwrap
-        let parsed_hash = PasswordHash::new(&password_digest).unwrap();
+        // испорченный дайджест - просто неверный пароль, а не падение сервера
+        let parsed_hash = match PasswordHash::new(&password_digest) {
+            Ok(parsed_hash) => parsed_hash,
+            Err(_) => return PasswordConfirmation { is_confirmed: false, need_upgrade: false },
+        };
+
+        // без ключа нужной версии пароль не проверить, перец нельзя выводить из оборота раньше дайджестов
+        let pepper_version = match digest_pepper(&parsed_hash) {
+            DigestPepper::None => None,
+            DigestPepper::Version(version) => Some(version),
+            DigestPepper::Foreign => return PasswordConfirmation { is_confirmed: false, need_upgrade: false },
+        };
+        let password = match self.pepper.apply(pepper_version, &password) {
+            Some(password) => password,
+            None => return PasswordConfirmation { is_confirmed: false, need_upgrade: false },
+        };
 
         let is_confirmed = Argon2::default()
-            .verify_password(password.as_bytes(), &parsed_hash)
+            .verify_password(&password, &parsed_hash)
             .is_ok();
 
         let m_cost_match = match parsed_hash.params.get_decimal("m") { 
@@ -38,11 +77,25 @@ impl HashVerifierProvider for Argon2VerifierProvider {
             Some(p_cost) => p_cost == self.parallelism,
             None => false,
         };
-        let is_hash_params_actual = m_cost_match && t_cost_match && p_cost_match;
-        let need_upgrade = !is_hash_params_actual;
+        let is_argon2id = parsed_hash.algorithm == Algorithm::Argon2id.ident();
+        let is_pepper_actual = pepper_version == self.pepper.current_version();
+        let is_hash_params_actual = is_argon2id && m_cost_match && t_cost_match && p_cost_match && is_pepper_actual;
+        let need_upgrade = is_confirmed && !is_hash_params_actual;
 
         PasswordConfirmation { is_confirmed, need_upgrade }
     }
+
+    fn supports(&self, password_digest: &str) -> bool {
+        PasswordHash::new(password_digest).is_ok_and(|parsed_hash| {
+            let is_argon2 = [Algorithm::Argon2id, Algorithm::Argon2i, Algorithm::Argon2d].iter().any(|algorithm| parsed_hash.algorithm == algorithm.ident());
+            let is_pepper_known = match digest_pepper(&parsed_hash) {
+                DigestPepper::None => true,
+                DigestPepper::Version(version) => self.pepper.has_version(version),
+                DigestPepper::Foreign => false,
+            };
+            is_argon2 && parsed_hash.hash.is_some() && is_pepper_known
+        })
+    }
 }
 
 #[cfg(test)]
@@ -99,6 +152,46 @@ mod tests {
         assert!(hash2_is_valid.is_confirmed);
     }
 
+    #[test]
+    fn verify_password_with_corrupt_hash() {
+        // Given
+        let argon2_verifier = Argon2VerifierProvider::new(8, 1, 1);
+
+        // When
+        let confirmation = argon2_verifier.provide("!Qwerty123".to_string(), "$argon2id$v=19$broken".to_string());
+
+        // Then
+        assert!(!confirmation.is_confirmed);
+        assert!(!confirmation.need_upgrade);
+    }
+
+    #[test]
+    fn verify_password_after_pepper_rotation() {
+        // Given
+        let old_pepper = PasswordPepper::from_secret(1, b"old-pepper").unwrap();
+        let pepper = old_pepper.rotate(2, b"new-pepper");
+        let unpeppered_digest = Argon2HasherProvider::new(8, 1, 1).provide("!Qwerty123".to_string()).unwrap();
+        let old_digest = Argon2HasherProvider::new(8, 1, 1).with_pepper(old_pepper).provide("!Qwerty123".to_string()).unwrap();
+        let new_digest = Argon2HasherProvider::new(8, 1, 1).with_pepper(pepper.clone()).provide("!Qwerty123".to_string()).unwrap();
+        let argon2_verifier = Argon2VerifierProvider::new(8, 1, 1).with_pepper(pepper);
+        let unpeppered_verifier = Argon2VerifierProvider::new(8, 1, 1);
+
+        // When
+        let unpeppered_confirmation = argon2_verifier.provide("!Qwerty123".to_string(), unpeppered_digest);
+        let old_confirmation = argon2_verifier.provide("!Qwerty123".to_string(), old_digest);
+        let new_confirmation = argon2_verifier.provide("!Qwerty123".to_string(), new_digest.clone());
+        let wrong_password_confirmation = argon2_verifier.provide("InvalidPassword".to_string(), new_digest.clone());
+        let missing_pepper_confirmation = unpeppered_verifier.provide("!Qwerty123".to_string(), new_digest.clone());
+
+        // Then
+        assert!(unpeppered_confirmation.is_confirmed && unpeppered_confirmation.need_upgrade);
+        assert!(old_confirmation.is_confirmed && old_confirmation.need_upgrade);
+        assert!(new_confirmation.is_confirmed && !new_confirmation.need_upgrade);
+        assert!(!wrong_password_confirmation.is_confirmed);
+        assert!(!missing_pepper_confirmation.is_confirmed);
+        assert!(!unpeppered_verifier.supports(&new_digest));
+    }
+
     #[test]
     fn verify_invalid_password() {
         // Given
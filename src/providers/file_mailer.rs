@@ -0,0 +1,58 @@
+use tokio::io::AsyncWriteExt;
+use crate::{
+    errors::AppError,
+    providers::MailerProvider,
+};
+
+// локальная доставка почты: письма дописываются в файл; в stdout не пишутся, там они попали бы в логи вместе с кодами
+#[derive(Clone)]
+pub struct FileMailerProvider {
+    outbox_path: std::path::PathBuf,
+}
+
+impl FileMailerProvider {
+    pub fn new(outbox_path: std::path::PathBuf) -> Self {
+        Self { outbox_path }
+    }
+}
+
+impl MailerProvider for FileMailerProvider {
+    async fn send(&self, to: String, subject: String, body: String) -> Result<(), AppError> {
+        let message = format!("To: {to}\nSubject: {subject}\n\n{body}\n\n");
+        let mut outbox = match tokio::fs::OpenOptions::new().create(true).append(true).open(&self.outbox_path).await {
+            Ok(outbox) => outbox,
+            Err(_) => return Err(AppError::UnknownError),
+        };
+        // tokio дописывает файл в фоне, без flush сообщение может потеряться
+        match outbox.write_all(message.as_bytes()).await {
+            Ok(()) => {},
+            Err(_) => return Err(AppError::UnknownError),
+        }
+        match outbox.flush().await {
+            Ok(()) => Ok(()),
+            Err(_) => Err(AppError::UnknownError),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn send_mail_to_outbox() {
+        // Given
+        let outbox_path = std::env::temp_dir().join(format!("auth-file-mailer-{}.txt", std::process::id()));
+        let _ = std::fs::remove_file(&outbox_path);
+        let mailer = FileMailerProvider::new(outbox_path.clone());
+
+        // When
+        mailer.send("user0@example.com".to_owned(), "Hello".to_owned(), "first".to_owned()).await.unwrap();
+        mailer.send("user1@example.com".to_owned(), "Hello".to_owned(), "second".to_owned()).await.unwrap();
+
+        // Then
+        let outbox = std::fs::read_to_string(&outbox_path).unwrap();
+        assert!(outbox.contains("To: user0@example.com\nSubject: Hello\n\nfirst"));
+        assert!(outbox.contains("To: user1@example.com\nSubject: Hello\n\nsecond"));
+    }
+}
@@ -0,0 +1,58 @@
+use tokio::io::AsyncWriteExt;
+use crate::{
+    errors::AppError,
+    providers::SmsProvider,
+};
+
+// локальная доставка SMS: сообщения дописываются в файл; в stdout не пишутся, там они попали бы в логи вместе с кодами
+#[derive(Clone)]
+pub struct FileSmsProvider {
+    outbox_path: std::path::PathBuf,
+}
+
+impl FileSmsProvider {
+    pub fn new(outbox_path: std::path::PathBuf) -> Self {
+        Self { outbox_path }
+    }
+}
+
+impl SmsProvider for FileSmsProvider {
+    async fn send(&self, to: String, text: String) -> Result<(), AppError> {
+        let message = format!("To: {to}\n{text}\n\n");
+        let mut outbox = match tokio::fs::OpenOptions::new().create(true).append(true).open(&self.outbox_path).await {
+            Ok(outbox) => outbox,
+            Err(_) => return Err(AppError::UnknownError),
+        };
+        // tokio дописывает файл в фоне, без flush сообщение может потеряться
+        match outbox.write_all(message.as_bytes()).await {
+            Ok(()) => {},
+            Err(_) => return Err(AppError::UnknownError),
+        }
+        match outbox.flush().await {
+            Ok(()) => Ok(()),
+            Err(_) => Err(AppError::UnknownError),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn send_sms_to_outbox() {
+        // Given
+        let outbox_path = std::env::temp_dir().join(format!("auth-file-sms-{}.txt", std::process::id()));
+        let _ = std::fs::remove_file(&outbox_path);
+        let sms = FileSmsProvider::new(outbox_path.clone());
+
+        // When
+        sms.send("+15550100001".to_owned(), "first".to_owned()).await.unwrap();
+        sms.send("+15550100002".to_owned(), "second".to_owned()).await.unwrap();
+
+        // Then
+        let outbox = std::fs::read_to_string(&outbox_path).unwrap();
+        assert!(outbox.contains("To: +15550100001\nfirst"));
+        assert!(outbox.contains("To: +15550100002\nsecond"));
+    }
+}
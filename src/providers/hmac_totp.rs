@@ -0,0 +1,130 @@
+use hmac::{Hmac, Mac};
+use crate::providers::TotpProvider;
+
+pub const TOTP_DIGITS: u32 = 6;
+pub const TOTP_PERIOD_IN_SECONDS: u64 = 30;
+pub const TOTP_SECRET_LENGTH: usize = 20;
+// допускаем расхождение часов клиента на один шаг в каждую сторону
+pub const TOTP_ALLOWED_SKEW: u64 = 1;
+
+const BASE32: base32::Alphabet = base32::Alphabet::Rfc4648 { padding: false };
+
+#[derive(Clone)]
+pub struct HmacTotpProvider {
+    issuer: String,
+}
+
+impl HmacTotpProvider {
+    pub fn new(issuer: String) -> Self {
+        Self { issuer }
+    }
+
+    // RFC 4226: HMAC-SHA1 от номера шага и динамическое усечение
+    fn code_at_step(key: &[u8], step: u64) -> Option<String> {
+        let mut mac = Hmac::<sha1::Sha1>::new_from_slice(key).ok()?;
+        mac.update(&step.to_be_bytes());
+        let hash = mac.finalize().into_bytes();
+        let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+        let binary = u32::from_be_bytes([hash[offset] & 0x7f, hash[offset + 1], hash[offset + 2], hash[offset + 3]]);
+
+        Some(format!("{:0width$}", binary % 10u32.pow(TOTP_DIGITS), width = TOTP_DIGITS as usize))
+    }
+}
+
+fn percent_encode(value: &str) -> String {
+    value.bytes().map(|byte| match byte {
+        b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (byte as char).to_string(),
+        _ => format!("%{byte:02X}"),
+    }).collect()
+}
+
+impl TotpProvider for HmacTotpProvider {
+    fn generate_secret(&self) -> Option<String> {
+        let mut buffer = [0u8; TOTP_SECRET_LENGTH];
+        getrandom::fill(&mut buffer).ok()?;
+
+        Some(base32::encode(BASE32, &buffer))
+    }
+
+    fn provisioning_uri(&self, secret: String, account_name: String) -> String {
+        let issuer = percent_encode(&self.issuer);
+        format!(
+            "otpauth://totp/{issuer}:{}?secret={secret}&issuer={issuer}&algorithm=SHA1&digits={TOTP_DIGITS}&period={TOTP_PERIOD_IN_SECONDS}",
+            percent_encode(&account_name),
+        )
+    }
+
+    fn generate(&self, secret: String, unix_time: u64) -> Option<String> {
+        let key = base32::decode(BASE32, &secret)?;
+
+        Self::code_at_step(&key, unix_time / TOTP_PERIOD_IN_SECONDS)
+    }
+
+    fn verify(&self, secret: String, code: String, unix_time: u64) -> Option<u64> {
+        let key = base32::decode(BASE32, &secret)?;
+        let code = code.trim();
+        let current_step = unix_time / TOTP_PERIOD_IN_SECONDS;
+
+        (current_step.saturating_sub(TOTP_ALLOWED_SKEW)..=current_step + TOTP_ALLOWED_SKEW).find(|step| {
+            let Some(expected) = Self::code_at_step(&key, *step) else { return false };
+            // сравнение без раннего выхода, чтобы не подсказывать совпавшие цифры временем ответа
+            expected.len() == code.len() && expected.bytes().zip(code.bytes()).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // секрет из приложения B RFC 6238: ASCII "12345678901234567890"
+    const RFC_SECRET: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+
+    #[test]
+    fn generate_rfc6238_codes() {
+        // Given
+        let totp_provider = HmacTotpProvider::new("auth".to_string());
+
+        // When
+        let codes: Vec<_> = [59, 1_111_111_109, 1_234_567_890, 2_000_000_000]
+            .iter()
+            .map(|time| totp_provider.generate(RFC_SECRET.to_string(), *time).unwrap())
+            .collect();
+
+        // Then
+        assert_eq!(codes, ["287082", "081804", "005924", "279037"]);
+    }
+
+    #[test]
+    fn verify_code_within_allowed_skew() {
+        // Given
+        let totp_provider = HmacTotpProvider::new("auth".to_string());
+        let secret = totp_provider.generate_secret().unwrap();
+        let code = totp_provider.generate(secret.clone(), 1_000_000).unwrap();
+
+        // When
+        let previous_step = totp_provider.verify(secret.clone(), code.clone(), 1_000_000 + TOTP_PERIOD_IN_SECONDS);
+        let stale = totp_provider.verify(secret.clone(), code.clone(), 1_000_000 + 3 * TOTP_PERIOD_IN_SECONDS);
+        let garbage = totp_provider.verify("not base32!".to_string(), code, 1_000_000);
+
+        // Then
+        assert_eq!(previous_step, Some(1_000_000 / TOTP_PERIOD_IN_SECONDS));
+        assert_eq!(stale, None);
+        assert_eq!(garbage, None);
+    }
+
+    #[test]
+    fn build_provisioning_uri() {
+        // Given
+        let totp_provider = HmacTotpProvider::new("Acme Inc".to_string());
+
+        // When
+        let uri = totp_provider.provisioning_uri(RFC_SECRET.to_string(), "user0@example.com".to_string());
+
+        // Then
+        assert_eq!(
+            uri,
+            "otpauth://totp/Acme%20Inc:user0%40example.com?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=Acme%20Inc&algorithm=SHA1&digits=6&period=30"
+        );
+    }
+}
@@ -1,44 +1,51 @@
-use crate::providers::TokenEncoderProvider;
+use crate::providers::{Claims, TokenEncoderProvider, jwt_key_ring::JwtKeyRing};
 use std::time::{SystemTime, UNIX_EPOCH};
 
-#[derive(Debug, serde::Serialize, serde::Deserialize)]
-pub struct Claims {
-    sub: String,
-    exp: u64,
-    iat: u64,
-}
+pub const ACCESS_TOKEN_TTL_IN_SECONDS: u64 = 15 * 60;
 
 #[derive(Clone)]
-pub struct JwtEncoderProvider;
+pub struct JwtEncoderProvider {
+    issuer: String,
+    audience: String,
+    key_ring: JwtKeyRing,
+}
+
+impl JwtEncoderProvider {
+    pub fn new(issuer: String, audience: String, key_ring: JwtKeyRing) -> Self {
+        Self { issuer, audience, key_ring }
+    }
+}
 
 impl TokenEncoderProvider for JwtEncoderProvider {
     fn provide(&self, user_id: String) -> Option<String> {
+        let key = self.key_ring.active_key()?;
         let now = SystemTime::now()
             .duration_since(UNIX_EPOCH)
-            .unwrap().as_secs() as u64;
-        let expires_in = now + 15 * 60;
+            .unwrap().as_secs();
+        let expires_in = now + ACCESS_TOKEN_TTL_IN_SECONDS;
         let claims = Claims {
             sub: user_id.clone(),
+            iss: self.issuer.clone(),
+            aud: self.audience.clone(),
             exp: expires_in,
             iat: now,
         };
-        let access_token = jsonwebtoken::encode(
-            &jsonwebtoken::Header::default(), 
-            &claims, 
-            &jsonwebtoken::EncodingKey::from_secret(b"my-super-secret-key"),
-        ).unwrap();
-        Some(access_token)
+        let mut header = jsonwebtoken::Header::new(key.algorithm);
+        header.kid = Some(key.kid);
+        jsonwebtoken::encode(&header, &claims, &key.encoding_key).ok()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::providers::jwt_key::JwtKey;
 
     #[test]
     fn encode_jwt() {
         // Given
-        let jwt_encoder = JwtEncoderProvider;
+        let key = JwtKey::from_secret("hs256-key".to_owned(), b"my-super-secret-key").unwrap();
+        let jwt_encoder = JwtEncoderProvider::new("auth".to_owned(), "auth".to_owned(), JwtKeyRing::new(key));
 
         // When
         let token = jwt_encoder.provide("Qwerty123".to_owned()).unwrap();
@@ -46,5 +53,20 @@ mod tests {
         // Then
         assert_ne!(token, "Qwerty123".to_owned());
     }
-}
 
+    #[test]
+    fn encode_jwt_with_key_id() {
+        // Given
+        let pem = include_bytes!("../../tests/fixtures/es256_test_key.pem");
+        let key = JwtKey::from_pem("es256-key".to_owned(), jsonwebtoken::Algorithm::ES256, pem).unwrap();
+        let jwt_encoder = JwtEncoderProvider::new("auth".to_owned(), "auth".to_owned(), JwtKeyRing::new(key));
+
+        // When
+        let token = jwt_encoder.provide("Qwerty123".to_owned()).unwrap();
+        let header = jsonwebtoken::decode_header(&token).unwrap();
+
+        // Then
+        assert_eq!(header.alg, jsonwebtoken::Algorithm::ES256);
+        assert_eq!(header.kid, Some("es256-key".to_owned()));
+    }
+}
@@ -0,0 +1,231 @@
+use base64::Engine as _;
+use base64::engine::general_purpose;
+use jsonwebtoken::{
+    Algorithm,
+    DecodingKey,
+    EncodingKey,
+    jwk::{
+        AlgorithmParameters,
+        CommonParameters,
+        EllipticCurve,
+        EllipticCurveKeyParameters,
+        EllipticCurveKeyType,
+        Jwk,
+        KeyAlgorithm,
+        OctetKeyPairParameters,
+        OctetKeyPairType,
+        PublicKeyUse,
+        RSAKeyParameters,
+        RSAKeyType,
+    },
+};
+use rsa::{
+    pkcs1::DecodeRsaPrivateKey,
+    pkcs8::DecodePrivateKey,
+    traits::PublicKeyParts,
+};
+use p256::elliptic_curve::sec1::ToEncodedPoint;
+
+#[derive(Clone)]
+pub struct JwtKey {
+    pub kid: String,
+    pub algorithm: Algorithm,
+    pub encoding_key: EncodingKey,
+    pub decoding_key: DecodingKey,
+    // публичная часть ключа, у HMAC её нет
+    pub public_jwk: Option<Jwk>,
+}
+
+impl JwtKey {
+    pub fn new(kid: String, algorithm: Algorithm, secret: &str, private_key_path: &str) -> Option<Self> {
+        match algorithm {
+            Algorithm::HS256 => Self::from_secret(kid, secret.as_bytes()),
+            _ => Self::from_file(kid, algorithm, std::path::Path::new(private_key_path)),
+        }
+    }
+
+    pub fn from_file(kid: String, algorithm: Algorithm, path: &std::path::Path) -> Option<Self> {
+        let content = match std::fs::read(path) {
+            Ok(content) => content,
+            Err(_) => return None,
+        };
+        match algorithm {
+            Algorithm::HS256 => Self::from_secret(kid, content.trim_ascii()),
+            _ => Self::from_pem(kid, algorithm, &content),
+        }
+    }
+
+    pub fn from_secret(kid: String, secret: &[u8]) -> Option<Self> {
+        if secret.is_empty() || kid.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            kid,
+            algorithm: Algorithm::HS256,
+            encoding_key: EncodingKey::from_secret(secret),
+            decoding_key: DecodingKey::from_secret(secret),
+            public_jwk: None,
+        })
+    }
+
+    pub fn from_pem(kid: String, algorithm: Algorithm, pem: &[u8]) -> Option<Self> {
+        if kid.is_empty() {
+            return None;
+        }
+        let pem_str = match std::str::from_utf8(pem) {
+            Ok(pem_str) => pem_str,
+            Err(_) => return None,
+        };
+
+        let (encoding_key, key_algorithm, parameters) = match algorithm {
+            Algorithm::RS256 => {
+                let private_key = match rsa::RsaPrivateKey::from_pkcs8_pem(pem_str) {
+                    Ok(private_key) => private_key,
+                    Err(_) => rsa::RsaPrivateKey::from_pkcs1_pem(pem_str).ok()?,
+                };
+                let parameters = AlgorithmParameters::RSA(RSAKeyParameters {
+                    key_type: RSAKeyType::RSA,
+                    n: encode(&private_key.n().to_bytes_be()),
+                    e: encode(&private_key.e().to_bytes_be()),
+                });
+                (EncodingKey::from_rsa_pem(pem).ok()?, KeyAlgorithm::RS256, parameters)
+            },
+            Algorithm::ES256 => {
+                let secret_key = p256::SecretKey::from_pkcs8_pem(pem_str).ok()?;
+                let point = secret_key.public_key().to_encoded_point(false);
+                let parameters = AlgorithmParameters::EllipticCurve(EllipticCurveKeyParameters {
+                    key_type: EllipticCurveKeyType::EC,
+                    curve: EllipticCurve::P256,
+                    x: encode(point.x()?),
+                    y: encode(point.y()?),
+                });
+                (EncodingKey::from_ec_pem(pem).ok()?, KeyAlgorithm::ES256, parameters)
+            },
+            Algorithm::EdDSA => {
+                let signing_key = ed25519_dalek::SigningKey::from_pkcs8_pem(pem_str).ok()?;
+                let parameters = AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+                    key_type: OctetKeyPairType::OctetKeyPair,
+                    curve: EllipticCurve::Ed25519,
+                    x: encode(signing_key.verifying_key().as_bytes()),
+                });
+                (EncodingKey::from_ed_pem(pem).ok()?, KeyAlgorithm::EdDSA, parameters)
+            },
+            _ => return None,
+        };
+
+        let public_jwk = Jwk {
+            common: CommonParameters {
+                public_key_use: Some(PublicKeyUse::Signature),
+                key_algorithm: Some(key_algorithm),
+                key_id: Some(kid.clone()),
+                ..Default::default()
+            },
+            algorithm: parameters,
+        };
+        let decoding_key = DecodingKey::from_jwk(&public_jwk).ok()?;
+
+        Some(Self {
+            kid,
+            algorithm,
+            encoding_key,
+            decoding_key,
+            public_jwk: Some(public_jwk),
+        })
+    }
+}
+
+fn encode(bytes: &[u8]) -> String {
+    general_purpose::URL_SAFE_NO_PAD.encode(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::Claims;
+
+    const RS256_PEM: &[u8] = include_bytes!("../../tests/fixtures/rs256_test_key.pem");
+    const ES256_PEM: &[u8] = include_bytes!("../../tests/fixtures/es256_test_key.pem");
+    const EDDSA_PEM: &[u8] = include_bytes!("../../tests/fixtures/eddsa_test_key.pem");
+
+    fn sign_and_verify(key: &JwtKey) -> bool {
+        let mut header = jsonwebtoken::Header::new(key.algorithm);
+        header.kid = Some(key.kid.clone());
+        let now = jsonwebtoken::get_current_timestamp();
+        let claims = Claims {
+            sub: "Qwerty123".to_owned(),
+            iss: "auth".to_owned(),
+            aud: "auth".to_owned(),
+            exp: now + 60,
+            iat: now,
+        };
+        let token = jsonwebtoken::encode(&header, &claims, &key.encoding_key).unwrap();
+
+        let mut validation = jsonwebtoken::Validation::new(key.algorithm);
+        validation.set_audience(&["auth"]);
+        jsonwebtoken::decode::<Claims>(&token, &key.decoding_key, &validation).is_ok()
+    }
+
+    #[test]
+    fn load_rs256_key() {
+        // Given
+        let kid = "rs256-key".to_owned();
+
+        // When
+        let key = JwtKey::from_pem(kid, Algorithm::RS256, RS256_PEM).unwrap();
+
+        // Then
+        assert!(key.public_jwk.is_some());
+        assert!(sign_and_verify(&key));
+    }
+
+    #[test]
+    fn load_es256_key() {
+        // Given
+        let kid = "es256-key".to_owned();
+
+        // When
+        let key = JwtKey::from_pem(kid, Algorithm::ES256, ES256_PEM).unwrap();
+
+        // Then
+        assert!(key.public_jwk.is_some());
+        assert!(sign_and_verify(&key));
+    }
+
+    #[test]
+    fn load_eddsa_key() {
+        // Given
+        let kid = "eddsa-key".to_owned();
+
+        // When
+        let key = JwtKey::from_pem(kid, Algorithm::EdDSA, EDDSA_PEM).unwrap();
+
+        // Then
+        assert!(key.public_jwk.is_some());
+        assert!(sign_and_verify(&key));
+    }
+
+    #[test]
+    fn load_key_with_mismatched_algorithm() {
+        // Given
+        let kid = "es256-key".to_owned();
+
+        // When
+        let key = JwtKey::from_pem(kid, Algorithm::RS256, ES256_PEM);
+
+        // Then
+        assert!(key.is_none());
+    }
+
+    #[test]
+    fn load_empty_secret() {
+        // Given
+        let kid = "hs256-key".to_owned();
+
+        // When
+        let key = JwtKey::from_secret(kid, b"");
+
+        // Then
+        assert!(key.is_none());
+    }
+}
@@ -0,0 +1,464 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+};
+use jsonwebtoken::{Algorithm, jwk::Jwk};
+use crate::{
+    errors::AppError,
+    providers::{
+        KeyRingProvider,
+        jwt_key::JwtKey,
+        jwt_encoder::ACCESS_TOKEN_TTL_IN_SECONDS,
+        jwt_decoder::LEEWAY_IN_SECONDS,
+    },
+};
+
+pub const KEY_RING_MANIFEST: &str = "keys.toml";
+
+// сколько живёт снятый с подписи ключ: пока не истекут все выпущенные им токены
+const VERIFY_ONLY_GRACE_IN_SECONDS: u64 = ACCESS_TOKEN_TTL_IN_SECONDS + LEEWAY_IN_SECONDS;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum JwtKeyStatus {
+    Next,
+    Active,
+    VerifyOnly,
+    Retired,
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+struct JwtKeyManifest {
+    keys: Vec<JwtKeyManifestEntry>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+struct JwtKeyManifestEntry {
+    kid: String,
+    algorithm: Algorithm,
+    status: JwtKeyStatus,
+    path: String,
+    // unix-время, до которого verify-only ключ проверяет подписи
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    verify_until: Option<u64>,
+}
+
+#[derive(Clone)]
+struct JwtKeyRingEntry {
+    key: JwtKey,
+    status: JwtKeyStatus,
+    verify_until: Option<u64>,
+}
+
+impl JwtKeyRingEntry {
+    fn is_verifiable(&self, now: u64) -> bool {
+        match self.status {
+            JwtKeyStatus::Retired => false,
+            _ => self.verify_until.is_none_or(|verify_until| verify_until >= now),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct JwtKeyRing {
+    key_dir: Option<PathBuf>,
+    entries: Arc<RwLock<Vec<JwtKeyRingEntry>>>,
+}
+
+impl JwtKeyRing {
+    pub fn new(active_key: JwtKey) -> Self {
+        let entry = JwtKeyRingEntry { key: active_key, status: JwtKeyStatus::Active, verify_until: None };
+        Self { key_dir: None, entries: Arc::new(RwLock::new(vec![entry])) }
+    }
+
+    pub fn load(key_dir: PathBuf) -> Option<Self> {
+        let (entries, _) = read_manifest(&key_dir, &[]).ok()?;
+        Some(Self { key_dir: Some(key_dir), entries: Arc::new(RwLock::new(entries)) })
+    }
+
+    pub fn active_key(&self) -> Option<JwtKey> {
+        let entries = self.entries.read().ok()?;
+        entries.iter()
+            .find(|entry| entry.status == JwtKeyStatus::Active)
+            .map(|entry| entry.key.clone())
+    }
+
+    pub fn verification_key(&self, kid: &str) -> Option<JwtKey> {
+        let now = jsonwebtoken::get_current_timestamp();
+        let entries = self.entries.read().ok()?;
+        entries.iter()
+            .find(|entry| entry.key.kid == kid && entry.is_verifiable(now))
+            .map(|entry| entry.key.clone())
+    }
+
+    pub fn verification_keys(&self) -> Vec<JwtKey> {
+        let now = jsonwebtoken::get_current_timestamp();
+        match self.entries.read() {
+            Ok(entries) => entries.iter()
+                .filter(|entry| entry.is_verifiable(now))
+                .map(|entry| entry.key.clone())
+                .collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    pub fn status(&self, kid: &str) -> Option<JwtKeyStatus> {
+        let now = jsonwebtoken::get_current_timestamp();
+        let entries = self.entries.read().ok()?;
+        entries.iter()
+            .find(|entry| entry.key.kid == kid)
+            .map(|entry| if entry.is_verifiable(now) { entry.status } else { JwtKeyStatus::Retired })
+    }
+}
+
+impl KeyRingProvider for JwtKeyRing {
+    fn reload(&self) -> Result<(), AppError> {
+        let key_dir = match &self.key_dir {
+            Some(key_dir) => key_dir,
+            None => return Err(AppError::InvalidKeyRing),
+        };
+        let mut entries = match self.entries.write() {
+            Ok(entries) => entries,
+            Err(_) => return Err(AppError::UnknownError),
+        };
+        let (mut new_entries, retired) = read_manifest(key_dir, &entries)?;
+
+        let now = jsonwebtoken::get_current_timestamp();
+        // ключ, пропавший из манифеста, продолжает проверять подписи, пока не истекут его токены
+        for entry in entries.iter().filter(|entry| entry.is_verifiable(now)) {
+            let is_listed = new_entries.iter().any(|new_entry| new_entry.key.kid == entry.key.kid);
+            if is_listed || retired.contains(&entry.key.kid) {
+                continue;
+            }
+            new_entries.push(JwtKeyRingEntry {
+                key: entry.key.clone(),
+                status: JwtKeyStatus::VerifyOnly,
+                verify_until: Some(entry.verify_until.unwrap_or(now + VERIFY_ONLY_GRACE_IN_SECONDS)),
+            });
+        }
+        *entries = new_entries;
+
+        Ok(())
+    }
+
+    // повышение записывается в манифест, чтобы его не откатил reload и подхватили остальные реплики
+    fn promote(&self, kid: String) -> Result<(), AppError> {
+        let key_dir = match &self.key_dir {
+            Some(key_dir) => key_dir,
+            None => return Err(AppError::InvalidKeyRing),
+        };
+        let mut manifest = read_manifest_file(key_dir)?;
+
+        let now = jsonwebtoken::get_current_timestamp();
+        let is_promotable = manifest.keys.iter().any(|entry| {
+            entry.kid == kid
+                && matches!(entry.status, JwtKeyStatus::Next | JwtKeyStatus::VerifyOnly)
+                && entry.verify_until.is_none_or(|verify_until| verify_until >= now)
+        });
+        if !is_promotable {
+            return Err(AppError::NotFound);
+        }
+
+        for entry in &mut manifest.keys {
+            if entry.kid == kid {
+                entry.status = JwtKeyStatus::Active;
+                entry.verify_until = None;
+            } else if entry.status == JwtKeyStatus::Active {
+                entry.status = JwtKeyStatus::VerifyOnly;
+                entry.verify_until = Some(now + VERIFY_ONLY_GRACE_IN_SECONDS);
+            }
+        }
+        write_manifest_file(key_dir, &manifest)?;
+
+        self.reload()
+    }
+
+    fn public_keys(&self) -> Vec<Jwk> {
+        let now = jsonwebtoken::get_current_timestamp();
+        match self.entries.read() {
+            Ok(entries) => entries.iter()
+                .filter(|entry| matches!(entry.status, JwtKeyStatus::Active | JwtKeyStatus::VerifyOnly) && entry.is_verifiable(now))
+                .filter_map(|entry| entry.key.public_jwk.clone())
+                .collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+}
+
+fn read_manifest_file(key_dir: &Path) -> Result<JwtKeyManifest, AppError> {
+    config::Config::builder()
+        .add_source(config::File::from(key_dir.join(KEY_RING_MANIFEST)))
+        .build()
+        .and_then(config::Config::try_deserialize::<JwtKeyManifest>)
+        .map_err(|_| AppError::InvalidKeyRing)
+}
+
+// пишет во временный файл и переименовывает, чтобы читатели не увидели манифест наполовину
+fn write_manifest_file(key_dir: &Path, manifest: &JwtKeyManifest) -> Result<(), AppError> {
+    let content = toml::to_string(manifest).map_err(|_| AppError::UnknownError)?;
+    let tmp_path = key_dir.join(format!("{KEY_RING_MANIFEST}.tmp"));
+    std::fs::write(&tmp_path, content)
+        .and_then(|()| std::fs::rename(&tmp_path, key_dir.join(KEY_RING_MANIFEST)))
+        .map_err(|_| AppError::UnknownError)
+}
+
+// возвращает загруженные ключи и kid явно отозванных;
+// verify-only ключ без срока в манифесте сохраняет прежний срок или получает новый от текущего момента
+fn read_manifest(key_dir: &Path, previous_entries: &[JwtKeyRingEntry]) -> Result<(Vec<JwtKeyRingEntry>, Vec<String>), AppError> {
+    let manifest = read_manifest_file(key_dir)?;
+
+    let now = jsonwebtoken::get_current_timestamp();
+    let mut entries: Vec<JwtKeyRingEntry> = Vec::new();
+    let mut retired: Vec<String> = Vec::new();
+    for manifest_entry in manifest.keys {
+        let is_duplicate = entries.iter().any(|entry| entry.key.kid == manifest_entry.kid) || retired.contains(&manifest_entry.kid);
+        if is_duplicate {
+            return Err(AppError::InvalidKeyRing);
+        }
+        let verify_until = match manifest_entry.status {
+            JwtKeyStatus::Retired => {
+                retired.push(manifest_entry.kid);
+                continue;
+            }
+            JwtKeyStatus::VerifyOnly => {
+                let previous_verify_until = previous_entries.iter()
+                    .find(|entry| entry.key.kid == manifest_entry.kid)
+                    .and_then(|entry| entry.verify_until);
+                Some(manifest_entry.verify_until.or(previous_verify_until).unwrap_or(now + VERIFY_ONLY_GRACE_IN_SECONDS))
+            }
+            _ => None,
+        };
+        let key = match JwtKey::from_file(manifest_entry.kid, manifest_entry.algorithm, &key_dir.join(manifest_entry.path)) {
+            Some(key) => key,
+            None => return Err(AppError::InvalidKeyRing),
+        };
+        entries.push(JwtKeyRingEntry { key, status: manifest_entry.status, verify_until });
+    }
+
+    let active_keys_count = entries.iter().filter(|entry| entry.status == JwtKeyStatus::Active).count();
+    if active_keys_count != 1 {
+        return Err(AppError::InvalidKeyRing);
+    }
+
+    Ok((entries, retired))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(kid: &str) -> JwtKey {
+        JwtKey::from_secret(kid.to_owned(), b"my-super-secret-key").unwrap()
+    }
+
+    fn key_dir(name: &str) -> PathBuf {
+        let key_dir = std::env::temp_dir().join(format!("auth-key-ring-{name}-{}", std::process::id()));
+        std::fs::create_dir_all(&key_dir).unwrap();
+        std::fs::copy("tests/fixtures/es256_test_key.pem", key_dir.join("es256.pem")).unwrap();
+        std::fs::copy("tests/fixtures/eddsa_test_key.pem", key_dir.join("eddsa.pem")).unwrap();
+        key_dir
+    }
+
+    fn write_manifest(key_dir: &Path, es256_status: &str, eddsa_status: &str) {
+        let manifest = format!(r#"
+            [[keys]]
+            kid = "es256-key"
+            algorithm = "ES256"
+            status = "{es256_status}"
+            path = "es256.pem"
+
+            [[keys]]
+            kid = "eddsa-key"
+            algorithm = "EdDSA"
+            status = "{eddsa_status}"
+            path = "eddsa.pem"
+        "#);
+        std::fs::write(key_dir.join(KEY_RING_MANIFEST), manifest).unwrap();
+    }
+
+    #[test]
+    fn promote_next_key() {
+        // Given
+        let key_dir = key_dir("promote");
+        write_manifest(&key_dir, "active", "next");
+        let key_ring = JwtKeyRing::load(key_dir.clone()).unwrap();
+
+        // When
+        let res = key_ring.promote("eddsa-key".to_owned());
+
+        // Then
+        assert!(res.is_ok());
+        assert_eq!(key_ring.active_key().unwrap().kid, "eddsa-key".to_owned());
+        assert_eq!(key_ring.status("es256-key"), Some(JwtKeyStatus::VerifyOnly));
+        assert!(key_ring.verification_key("es256-key").is_some());
+        let other_replica = JwtKeyRing::load(key_dir).unwrap();
+        assert_eq!(other_replica.active_key().unwrap().kid, "eddsa-key".to_owned());
+        assert_eq!(other_replica.status("es256-key"), Some(JwtKeyStatus::VerifyOnly));
+    }
+
+    #[test]
+    fn reload_key_ring_keeps_promoted_key() {
+        // Given
+        let key_dir = key_dir("promote-reload");
+        write_manifest(&key_dir, "active", "next");
+        let key_ring = JwtKeyRing::load(key_dir).unwrap();
+        key_ring.promote("eddsa-key".to_owned()).unwrap();
+
+        // When
+        let res = key_ring.reload();
+
+        // Then
+        assert!(res.is_ok());
+        assert_eq!(key_ring.active_key().unwrap().kid, "eddsa-key".to_owned());
+        assert_eq!(key_ring.status("es256-key"), Some(JwtKeyStatus::VerifyOnly));
+    }
+
+    #[test]
+    fn promote_active_key() {
+        // Given
+        let key_dir = key_dir("promote-active");
+        write_manifest(&key_dir, "active", "next");
+        let key_ring = JwtKeyRing::load(key_dir).unwrap();
+
+        // When
+        let res = key_ring.promote("es256-key".to_owned());
+
+        // Then
+        assert!(matches!(res, Err(AppError::NotFound)));
+        assert_eq!(key_ring.active_key().unwrap().kid, "es256-key".to_owned());
+    }
+
+    #[test]
+    fn promote_key_without_key_dir() {
+        // Given
+        let key_ring = JwtKeyRing::new(key("key-1"));
+
+        // When
+        let res = key_ring.promote("key-1".to_owned());
+
+        // Then
+        assert!(matches!(res, Err(AppError::InvalidKeyRing)));
+        assert_eq!(key_ring.active_key().unwrap().kid, "key-1".to_owned());
+    }
+
+    #[test]
+    fn load_expired_verify_only_key() {
+        // Given
+        let key_dir = key_dir("expired");
+        std::fs::write(key_dir.join(KEY_RING_MANIFEST), r#"
+            [[keys]]
+            kid = "es256-key"
+            algorithm = "ES256"
+            status = "verify-only"
+            path = "es256.pem"
+            verify_until = 1
+
+            [[keys]]
+            kid = "eddsa-key"
+            algorithm = "EdDSA"
+            status = "active"
+            path = "eddsa.pem"
+        "#).unwrap();
+
+        // When
+        let key_ring = JwtKeyRing::load(key_dir).unwrap();
+
+        // Then
+        assert!(key_ring.verification_key("es256-key").is_none());
+        assert_eq!(key_ring.status("es256-key"), Some(JwtKeyStatus::Retired));
+    }
+
+    #[test]
+    fn load_verify_only_key_without_deadline() {
+        // Given
+        let key_dir = key_dir("deadline");
+        write_manifest(&key_dir, "verify-only", "active");
+
+        // When
+        let key_ring = JwtKeyRing::load(key_dir).unwrap();
+
+        // Then
+        let entries = key_ring.entries.read().unwrap();
+        let verify_until = entries.iter().find(|entry| entry.key.kid == "es256-key").unwrap().verify_until;
+        let now = jsonwebtoken::get_current_timestamp();
+        assert!(verify_until.is_some_and(|verify_until| verify_until > now && verify_until <= now + VERIFY_ONLY_GRACE_IN_SECONDS));
+    }
+
+    #[test]
+    fn reload_key_ring_keeps_previous_active_key() {
+        // Given
+        let key_dir = key_dir("reload");
+        write_manifest(&key_dir, "active", "next");
+        let key_ring = JwtKeyRing::load(key_dir.clone()).unwrap();
+        std::fs::write(key_dir.join(KEY_RING_MANIFEST), r#"
+            [[keys]]
+            kid = "eddsa-key"
+            algorithm = "EdDSA"
+            status = "active"
+            path = "eddsa.pem"
+        "#).unwrap();
+
+        // When
+        let res = key_ring.reload();
+
+        // Then
+        assert!(res.is_ok());
+        assert_eq!(key_ring.active_key().unwrap().kid, "eddsa-key".to_owned());
+        assert_eq!(key_ring.status("es256-key"), Some(JwtKeyStatus::VerifyOnly));
+        assert_eq!(key_ring.verification_keys().len(), 2);
+    }
+
+    #[test]
+    fn reload_key_ring_drops_retired_key() {
+        // Given
+        let key_dir = key_dir("retire");
+        write_manifest(&key_dir, "active", "next");
+        let key_ring = JwtKeyRing::load(key_dir.clone()).unwrap();
+        write_manifest(&key_dir, "retired", "active");
+
+        // When
+        let res = key_ring.reload();
+
+        // Then
+        assert!(res.is_ok());
+        assert!(key_ring.verification_key("es256-key").is_none());
+        assert_eq!(key_ring.active_key().unwrap().kid, "eddsa-key".to_owned());
+    }
+
+    #[test]
+    fn reload_invalid_key_ring() {
+        // Given
+        let key_dir = key_dir("invalid");
+        write_manifest(&key_dir, "active", "next");
+        let key_ring = JwtKeyRing::load(key_dir.clone()).unwrap();
+        write_manifest(&key_dir, "active", "active");
+
+        // When
+        let res = key_ring.reload();
+
+        // Then
+        assert!(matches!(res, Err(AppError::InvalidKeyRing)));
+        assert_eq!(key_ring.active_key().unwrap().kid, "es256-key".to_owned());
+        assert_eq!(key_ring.status("eddsa-key"), Some(JwtKeyStatus::Next));
+    }
+
+    #[test]
+    fn list_public_keys() {
+        // Given
+        let key_dir = key_dir("public-keys");
+        write_manifest(&key_dir, "active", "next");
+        let key_ring = JwtKeyRing::load(key_dir).unwrap();
+        let hmac_key_ring = JwtKeyRing::new(key("key-1"));
+
+        // When
+        let before_promotion = key_ring.public_keys();
+        key_ring.promote("eddsa-key".to_owned()).unwrap();
+        let after_promotion = key_ring.public_keys();
+
+        // Then
+        let kids = |keys: &[Jwk]| keys.iter().map(|key| key.common.key_id.clone().unwrap()).collect::<Vec<_>>();
+        assert_eq!(kids(&before_promotion), vec!["es256-key".to_owned()]);
+        assert_eq!(kids(&after_promotion), vec!["es256-key".to_owned(), "eddsa-key".to_owned()]);
+        assert!(hmac_key_ring.public_keys().is_empty());
+    }
+}
@@ -0,0 +1,254 @@
+use base64::{Engine, engine::general_purpose::STANDARD};
+use sha2::Digest;
+use scrypt::password_hash::{PasswordHash, PasswordVerifier};
+use crate::providers::{
+    HashVerifierProvider,
+    PasswordConfirmation,
+    argon2_verifier::Argon2VerifierProvider,
+};
+
+const DJANGO_PBKDF2_SHA256_PREFIX: &str = "pbkdf2_sha256$";
+const DJANGO_SALTED_SHA1_PREFIX: &str = "sha1$";
+const LDAP_SALTED_SHA1_PREFIX: &str = "{SSHA}";
+const LDAP_SALTED_SHA256_PREFIX: &str = "{SSHA256}";
+const LDAP_SALTED_SHA512_PREFIX: &str = "{SSHA512}";
+
+const NOT_CONFIRMED: PasswordConfirmation = PasswordConfirmation { is_confirmed: false, need_upgrade: false };
+
+// дайджесты, перенесённые из других систем: алгоритм определяется по формату, после успешного входа пароль перехешируется в Argon2id
+#[derive(Clone)]
+pub struct MultiAlgorithmVerifierProvider {
+    argon2_verifier: Argon2VerifierProvider,
+}
+
+impl MultiAlgorithmVerifierProvider {
+    pub fn new(argon2_verifier: Argon2VerifierProvider) -> Self {
+        Self { argon2_verifier }
+    }
+}
+
+impl HashVerifierProvider for MultiAlgorithmVerifierProvider {
+    fn provide(&self, password: String, password_digest: String) -> PasswordConfirmation {
+        if password_digest.starts_with("$argon2") {
+            return self.argon2_verifier.provide(password, password_digest);
+        }
+
+        let is_confirmed = if password_digest.starts_with("$2") {
+            bcrypt::verify(password.as_bytes(), &password_digest).unwrap_or(false)
+        } else if password_digest.starts_with("$scrypt$") {
+            PasswordHash::new(&password_digest)
+                .is_ok_and(|parsed_hash| scrypt::Scrypt.verify_password(password.as_bytes(), &parsed_hash).is_ok())
+        } else if let Some(encoded) = password_digest.strip_prefix(DJANGO_PBKDF2_SHA256_PREFIX) {
+            parse_django_pbkdf2_sha256(encoded).is_some_and(|(iterations, salt, expected)| {
+                let mut actual = vec![0; expected.len()];
+                pbkdf2::pbkdf2_hmac::<sha2::Sha256>(password.as_bytes(), salt.as_bytes(), iterations, &mut actual);
+                is_same_digest(&expected, &actual)
+            })
+        } else if let Some(encoded) = password_digest.strip_prefix(DJANGO_SALTED_SHA1_PREFIX) {
+            parse_django_salted_sha1(encoded).is_some_and(|(salt, expected)| {
+                let actual = format!("{:x}", sha1::Sha1::digest(format!("{salt}{password}").as_bytes()));
+                is_same_digest(expected.as_bytes(), actual.as_bytes())
+            })
+        } else if let Some(encoded) = password_digest.strip_prefix(LDAP_SALTED_SHA512_PREFIX) {
+            verify_ldap_salted_sha::<sha2::Sha512>(&password, encoded)
+        } else if let Some(encoded) = password_digest.strip_prefix(LDAP_SALTED_SHA256_PREFIX) {
+            verify_ldap_salted_sha::<sha2::Sha256>(&password, encoded)
+        } else if let Some(encoded) = password_digest.strip_prefix(LDAP_SALTED_SHA1_PREFIX) {
+            verify_ldap_salted_sha::<sha1::Sha1>(&password, encoded)
+        } else {
+            return NOT_CONFIRMED;
+        };
+
+        PasswordConfirmation { is_confirmed, need_upgrade: is_confirmed }
+    }
+
+    // только разбор формата, без вычисления хеша: импорт проверяет миллионы строк
+    fn supports(&self, password_digest: &str) -> bool {
+        if password_digest.starts_with("$argon2") {
+            return self.argon2_verifier.supports(password_digest);
+        }
+        if password_digest.starts_with("$2") {
+            return is_bcrypt_digest(password_digest);
+        }
+        if password_digest.starts_with("$scrypt$") {
+            return PasswordHash::new(password_digest).is_ok_and(|parsed_hash| parsed_hash.hash.is_some());
+        }
+        if let Some(encoded) = password_digest.strip_prefix(DJANGO_PBKDF2_SHA256_PREFIX) {
+            return parse_django_pbkdf2_sha256(encoded).is_some();
+        }
+        if let Some(encoded) = password_digest.strip_prefix(DJANGO_SALTED_SHA1_PREFIX) {
+            return parse_django_salted_sha1(encoded).is_some();
+        }
+        if let Some(encoded) = password_digest.strip_prefix(LDAP_SALTED_SHA512_PREFIX) {
+            return parse_ldap_salted_sha::<sha2::Sha512>(encoded).is_some();
+        }
+        if let Some(encoded) = password_digest.strip_prefix(LDAP_SALTED_SHA256_PREFIX) {
+            return parse_ldap_salted_sha::<sha2::Sha256>(encoded).is_some();
+        }
+        if let Some(encoded) = password_digest.strip_prefix(LDAP_SALTED_SHA1_PREFIX) {
+            return parse_ldap_salted_sha::<sha1::Sha1>(encoded).is_some();
+        }
+
+        false
+    }
+}
+
+// $2b$<cost>$<22 символа соли><31 символ хеша>
+fn is_bcrypt_digest(password_digest: &str) -> bool {
+    let is_known_version = ["$2a$", "$2b$", "$2x$", "$2y$"].iter().any(|version| password_digest.starts_with(version));
+    let bytes = password_digest.as_bytes();
+
+    is_known_version
+        && bytes.len() == 60
+        && bytes[4..6].iter().all(u8::is_ascii_digit)
+        && bytes[6] == b'$'
+        && bytes[7..].iter().all(|byte| byte.is_ascii_alphanumeric() || matches!(byte, b'.' | b'/'))
+}
+
+// pbkdf2_sha256$<iterations>$<salt>$<base64 hash>
+fn parse_django_pbkdf2_sha256(encoded: &str) -> Option<(u32, &str, Vec<u8>)> {
+    let mut parts = encoded.splitn(3, '$');
+    let iterations: u32 = parts.next()?.parse().ok()?;
+    let salt = parts.next()?;
+    let expected = STANDARD.decode(parts.next()?).ok()?;
+    if iterations == 0 || expected.is_empty() {
+        return None;
+    }
+
+    Some((iterations, salt, expected))
+}
+
+// sha1$<salt>$<hex sha1(salt + password)>
+fn parse_django_salted_sha1(encoded: &str) -> Option<(&str, String)> {
+    let (salt, expected) = encoded.split_once('$')?;
+    if expected.len() != 40 || !expected.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    Some((salt, expected.to_lowercase()))
+}
+
+// {SSHA*}<base64(hash(password + salt) + salt)>
+fn parse_ldap_salted_sha<D: Digest>(encoded: &str) -> Option<(Vec<u8>, Vec<u8>)> {
+    let mut decoded = STANDARD.decode(encoded).ok()?;
+    let digest_length = <D as Digest>::output_size();
+    if decoded.len() <= digest_length {
+        return None;
+    }
+    let salt = decoded.split_off(digest_length);
+
+    Some((decoded, salt))
+}
+
+fn verify_ldap_salted_sha<D: Digest>(password: &str, encoded: &str) -> bool {
+    let Some((expected, salt)) = parse_ldap_salted_sha::<D>(encoded) else {
+        return false;
+    };
+    let mut hasher = D::new();
+    hasher.update(password.as_bytes());
+    hasher.update(&salt);
+
+    is_same_digest(&expected, &hasher.finalize())
+}
+
+// сравнение за постоянное время, как и для токенов
+fn is_same_digest(expected: &[u8], actual: &[u8]) -> bool {
+    expected.len() == actual.len() && expected.iter().zip(actual).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::HashFuncProvider;
+    use crate::providers::argon2_hasher::Argon2HasherProvider;
+
+    fn verifier() -> MultiAlgorithmVerifierProvider {
+        MultiAlgorithmVerifierProvider::new(Argon2VerifierProvider::new(8, 1, 1))
+    }
+
+    #[test]
+    fn verify_argon2id_password_without_upgrade() {
+        // Given
+        let password_digest = Argon2HasherProvider::new(8, 1, 1).provide("!Qwerty123".to_string()).unwrap();
+
+        // When
+        let confirmation = verifier().provide("!Qwerty123".to_string(), password_digest);
+
+        // Then
+        assert!(confirmation.is_confirmed);
+        assert!(!confirmation.need_upgrade);
+    }
+
+    #[test]
+    fn verify_legacy_passwords_with_upgrade() {
+        // Given
+        let bcrypt_digest = bcrypt::hash("!Qwerty123", 4).unwrap();
+        let scrypt_params = scrypt::Params::new(4, 8, 1, scrypt::Params::RECOMMENDED_LEN).unwrap();
+        let scrypt_salt = scrypt::password_hash::SaltString::from_b64("c2FsdHNhbHRzYWx0").unwrap();
+        let scrypt_digest = scrypt::password_hash::PasswordHasher::hash_password_customized(&scrypt::Scrypt, b"!Qwerty123", None, None, scrypt_params, &scrypt_salt)
+            .unwrap()
+            .to_string();
+        let mut django_hash = [0; 32];
+        pbkdf2::pbkdf2_hmac::<sha2::Sha256>(b"!Qwerty123", b"seasalt", 1000, &mut django_hash);
+        let django_digest = format!("pbkdf2_sha256$1000$seasalt${}", STANDARD.encode(django_hash));
+        let django_sha1_digest = format!("sha1$seasalt${:x}", sha1::Sha1::digest(b"seasalt!Qwerty123"));
+        let mut ssha = sha1::Sha1::digest(b"!Qwerty123seasalt").to_vec();
+        ssha.extend_from_slice(b"seasalt");
+        let ssha_digest = format!("{{SSHA}}{}", STANDARD.encode(ssha));
+        let mut ssha512 = sha2::Sha512::digest(b"!Qwerty123seasalt").to_vec();
+        ssha512.extend_from_slice(b"seasalt");
+        let ssha512_digest = format!("{{SSHA512}}{}", STANDARD.encode(ssha512));
+        let digests = [bcrypt_digest, scrypt_digest, django_digest, django_sha1_digest, ssha_digest, ssha512_digest];
+
+        // When
+        let confirmations: Vec<_> = digests.iter().map(|digest| verifier().provide("!Qwerty123".to_string(), digest.clone())).collect();
+        let rejections: Vec<_> = digests.iter().map(|digest| verifier().provide("InvalidPassword".to_string(), digest.clone())).collect();
+
+        // Then
+        assert!(confirmations.iter().all(|confirmation| confirmation.is_confirmed && confirmation.need_upgrade));
+        assert!(rejections.iter().all(|confirmation| !confirmation.is_confirmed && !confirmation.need_upgrade));
+    }
+
+    #[test]
+    fn recognize_digest_formats_without_hashing() {
+        // Given
+        let verifier = verifier();
+        let argon2_digest = Argon2HasherProvider::new(8, 1, 1).provide("!Qwerty123".to_string()).unwrap();
+        let bcrypt_digest = bcrypt::hash("!Qwerty123", 4).unwrap();
+
+        // When
+        let supported = [argon2_digest.as_str(), bcrypt_digest.as_str(), "sha1$seasalt$2fd4e1c67a2d28fced849ee1bb76e7391b93eb12"]
+            .iter()
+            .all(|digest| verifier.supports(digest));
+        let unsupported = ["", "plaintext", "$2b$04$short", "$argon2id$v=19$broken", "md5$salt$hash", "{SSHA}AAAA"]
+            .iter()
+            .any(|digest| verifier.supports(digest));
+
+        // Then
+        assert!(supported);
+        assert!(!unsupported);
+    }
+
+    #[test]
+    fn reject_unknown_and_corrupt_digests() {
+        // Given
+        let digests = [
+            "",
+            "plaintext",
+            "$argon2id$v=19$m=8,t=1,p=1$broken",
+            "$2b$04$short",
+            "$scrypt$ln=4",
+            "pbkdf2_sha256$many$salt$hash",
+            "pbkdf2_sha256$1000$salt$!!!",
+            "sha1$nosalt",
+            "{SSHA}AAAA",
+            "{SSHA256}not base64",
+        ];
+
+        // When
+        let confirmations: Vec<_> = digests.iter().map(|digest| verifier().provide("!Qwerty123".to_string(), (*digest).to_string())).collect();
+
+        // Then
+        assert!(confirmations.iter().all(|confirmation| !confirmation.is_confirmed));
+    }
+}
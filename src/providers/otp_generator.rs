@@ -0,0 +1,42 @@
+use crate::providers::IdProvider;
+
+pub const OTP_LENGTH: u32 = 6;
+
+#[derive(Clone)]
+pub struct OtpGeneratorProvider;
+
+impl IdProvider for OtpGeneratorProvider {
+    fn provide(&self) -> Option<String> {
+        let modulus = 10u32.pow(OTP_LENGTH);
+        // отбрасываем хвост диапазона u32, чтобы все коды были равновероятны
+        let limit = u32::MAX - u32::MAX % modulus;
+        loop {
+            let mut buffer = [0u8; 4];
+            if getrandom::fill(&mut buffer).is_err() {
+                return None;
+            }
+            let value = u32::from_le_bytes(buffer);
+            if value < limit {
+                return Some(format!("{:0width$}", value % modulus, width = OTP_LENGTH as usize));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_otp() {
+        // Given
+        let otp_provider = OtpGeneratorProvider;
+
+        // When
+        let otp = otp_provider.provide().unwrap();
+
+        // Then
+        assert_eq!(otp.len(), OTP_LENGTH as usize);
+        assert!(otp.chars().all(|c| c.is_ascii_digit()));
+    }
+}
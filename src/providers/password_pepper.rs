@@ -0,0 +1,131 @@
+use hmac::{Hmac, Mac};
+
+// секрет сервера, которого нет в базе: по одному дампу дайджесты не перебрать.
+// Версия текущего ключа пишется в дайджест, выведенные из оборота ключи нужны только для проверки
+#[derive(Clone, Default)]
+pub struct PasswordPepper {
+    current_version: Option<u32>,
+    keys: std::collections::HashMap<u32, Vec<u8>>,
+}
+
+impl PasswordPepper {
+    // без секрета и файла перец выключен; None - нет ключа текущей версии или секрет спорит с файлом о той же версии
+    pub fn new(version: u32, secret: &str, key_path: &str) -> Option<Self> {
+        let mut pepper = if key_path.is_empty() { Self::default() } else { Self::from_file(std::path::Path::new(key_path))? };
+        if !secret.is_empty() && pepper.keys.insert(version, secret.as_bytes().to_vec()).is_some() {
+            return None;
+        }
+        if pepper.keys.is_empty() {
+            return Some(pepper);
+        }
+        if !pepper.keys.contains_key(&version) {
+            return None;
+        }
+        pepper.current_version = Some(version);
+
+        Some(pepper)
+    }
+
+    pub fn from_secret(version: u32, secret: &[u8]) -> Option<Self> {
+        if secret.is_empty() {
+            return None;
+        }
+
+        Some(Self { current_version: Some(version), keys: std::collections::HashMap::from([(version, secret.to_vec())]) })
+    }
+
+    // строки вида VERSION:SECRET, текущую версию выбирает конфигурация
+    pub fn from_file(path: &std::path::Path) -> Option<Self> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(_) => return None,
+        };
+
+        let mut keys = std::collections::HashMap::new();
+        for line in content.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#')) {
+            let (version, secret) = line.split_once(':')?;
+            let version: u32 = version.trim().parse().ok()?;
+            let secret = secret.trim();
+            if secret.is_empty() || keys.insert(version, secret.as_bytes().to_vec()).is_some() {
+                return None;
+            }
+        }
+
+        Some(Self { current_version: None, keys })
+    }
+
+    // новый текущий ключ, прежние остаются для проверки старых дайджестов
+    pub fn rotate(&self, version: u32, secret: &[u8]) -> Self {
+        let mut pepper = self.clone();
+        pepper.keys.insert(version, secret.to_vec());
+        pepper.current_version = Some(version);
+        pepper
+    }
+
+    pub fn current_version(&self) -> Option<u32> {
+        self.current_version
+    }
+
+    pub fn has_version(&self, version: u32) -> bool {
+        self.keys.contains_key(&version)
+    }
+
+    // HMAC-SHA256 от пароля; без версии - пароль как есть, неизвестная версия - None
+    pub fn apply(&self, version: Option<u32>, password: &str) -> Option<Vec<u8>> {
+        let Some(version) = version else {
+            return Some(password.as_bytes().to_vec());
+        };
+        let mut mac = Hmac::<sha2::Sha256>::new_from_slice(self.keys.get(&version)?).ok()?;
+        mac.update(password.as_bytes());
+
+        Some(mac.finalize().into_bytes().to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn load_pepper_keys_from_file() {
+        // Given
+        let key_path = std::env::temp_dir().join(format!("auth-pepper-{}", std::process::id()));
+        std::fs::write(&key_path, "# retired\n1:old-pepper\n\n2:new-pepper\n").unwrap();
+
+        // When
+        let pepper = PasswordPepper::new(2, "", key_path.to_str().unwrap());
+        let unknown_version = PasswordPepper::new(3, "", key_path.to_str().unwrap());
+        let conflicting_secret = PasswordPepper::new(2, "other-pepper", key_path.to_str().unwrap());
+        let secret_for_new_version = PasswordPepper::new(3, "newest-pepper", key_path.to_str().unwrap());
+        let disabled = PasswordPepper::new(1, "", "").unwrap();
+        let _ = std::fs::remove_file(&key_path);
+
+        // Then
+        let pepper = pepper.unwrap();
+        assert_eq!(pepper.current_version(), Some(2));
+        assert!(pepper.has_version(1));
+        assert!(unknown_version.is_none());
+        assert!(conflicting_secret.is_none());
+        assert_eq!(secret_for_new_version.unwrap().current_version(), Some(3));
+        assert_eq!(disabled.current_version(), None);
+        assert_eq!(disabled.apply(None, "!Qwerty123"), Some(b"!Qwerty123".to_vec()));
+    }
+
+    #[test]
+    fn apply_pepper_of_each_version() {
+        // Given
+        let old_pepper = PasswordPepper::from_secret(1, b"old-pepper").unwrap();
+        let pepper = old_pepper.rotate(2, b"new-pepper");
+
+        // When
+        let old_peppered = pepper.apply(Some(1), "!Qwerty123").unwrap();
+        let new_peppered = pepper.apply(Some(2), "!Qwerty123").unwrap();
+        let unknown_peppered = pepper.apply(Some(3), "!Qwerty123");
+
+        // Then
+        assert_eq!(old_peppered, old_pepper.apply(Some(1), "!Qwerty123").unwrap());
+        assert_ne!(old_peppered, new_peppered);
+        assert_eq!(new_peppered.len(), 32);
+        assert!(unknown_peppered.is_none());
+    }
+}
@@ -0,0 +1,58 @@
+use crate::providers::IdProvider;
+
+// без символов, которые легко перепутать при переписывании с бумаги: 0/o, 1/l/i
+pub const RECOVERY_CODE_ALPHABET: &[u8] = b"abcdefghjkmnpqrstuvwxyz23456789";
+pub const RECOVERY_CODE_GROUP_LENGTH: usize = 5;
+
+#[derive(Clone)]
+pub struct RecoveryCodeGeneratorProvider;
+
+impl RecoveryCodeGeneratorProvider {
+    // дефисы и регистр при вводе не важны
+    pub fn normalize(code: &str) -> String {
+        code.chars().filter(char::is_ascii_alphanumeric).collect::<String>().to_lowercase()
+    }
+}
+
+impl IdProvider for RecoveryCodeGeneratorProvider {
+    fn provide(&self) -> Option<String> {
+        let alphabet_length = u8::try_from(RECOVERY_CODE_ALPHABET.len()).ok()?;
+        let limit = u8::MAX - u8::MAX % alphabet_length;
+        let mut code = String::with_capacity(2 * RECOVERY_CODE_GROUP_LENGTH + 1);
+        while code.len() < 2 * RECOVERY_CODE_GROUP_LENGTH + 1 {
+            if code.len() == RECOVERY_CODE_GROUP_LENGTH {
+                code.push('-');
+                continue;
+            }
+            let mut buffer = [0u8; 1];
+            if getrandom::fill(&mut buffer).is_err() {
+                return None;
+            }
+            if buffer[0] < limit {
+                code.push(char::from(RECOVERY_CODE_ALPHABET[usize::from(buffer[0] % alphabet_length)]));
+            }
+        }
+
+        Some(code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_recovery_code() {
+        // Given
+        let recovery_code_generator = RecoveryCodeGeneratorProvider;
+
+        // When
+        let code = recovery_code_generator.provide().unwrap();
+
+        // Then
+        assert_eq!(code.len(), 11);
+        assert_eq!(code.find('-'), Some(RECOVERY_CODE_GROUP_LENGTH));
+        assert!(code.bytes().filter(|c| *c != b'-').all(|c| RECOVERY_CODE_ALPHABET.contains(&c)));
+        assert_eq!(RecoveryCodeGeneratorProvider::normalize(" ABCDE-fghjk "), "abcdefghjk");
+    }
+}
@@ -0,0 +1,298 @@
+use base64::Engine as _;
+use base64::engine::general_purpose;
+use ciborium::Value;
+use p256::ecdsa::{Signature, VerifyingKey, signature::Verifier};
+use sha2::Digest;
+use crate::{
+    errors::AppError,
+    providers::{
+        WebauthnProvider,
+        PasskeyRegistration,
+        PasskeyAssertion,
+    },
+};
+
+pub const COSE_ALGORITHM_ES256: i64 = -7;
+
+const COSE_KEY_TYPE_EC2: i64 = 2;
+const COSE_CURVE_P256: i64 = 1;
+const FLAG_USER_PRESENT: u8 = 0x01;
+const FLAG_USER_VERIFIED: u8 = 0x04;
+const FLAG_ATTESTED_CREDENTIAL_DATA: u8 = 0x40;
+// rpIdHash (32) + flags (1) + signCount (4)
+const AUTHENTICATOR_DATA_LENGTH: usize = 37;
+// aaguid (16) + длина идентификатора ключа (2)
+const ATTESTED_CREDENTIAL_HEADER_LENGTH: usize = 18;
+
+#[derive(Clone)]
+pub struct WebauthnVerifierProvider {
+    rp_id: String,
+    rp_name: String,
+    origin: String,
+}
+
+#[derive(serde::Deserialize)]
+struct CollectedClientData {
+    #[serde(rename = "type")]
+    kind: String,
+    challenge: String,
+    origin: String,
+}
+
+impl WebauthnVerifierProvider {
+    pub fn new(rp_id: String, rp_name: String, origin: String) -> Self {
+        Self { rp_id, rp_name, origin }
+    }
+
+    fn verify_client_data(&self, client_data_json: &[u8], kind: &str) -> Result<String, AppError> {
+        let client_data: CollectedClientData = serde_json::from_slice(client_data_json).map_err(|_| AppError::InvalidPasskey)?;
+        if client_data.kind != kind || client_data.origin != self.origin {
+            return Err(AppError::InvalidPasskey);
+        }
+
+        Ok(client_data.challenge)
+    }
+
+    // возвращает флаги и счётчик подписей
+    fn verify_authenticator_data(&self, authenticator_data: &[u8]) -> Result<(u8, u32), AppError> {
+        if authenticator_data.len() < AUTHENTICATOR_DATA_LENGTH {
+            return Err(AppError::InvalidPasskey);
+        }
+        if authenticator_data[..32] != sha2::Sha256::digest(self.rp_id.as_bytes())[..] {
+            return Err(AppError::InvalidPasskey);
+        }
+        let flags = authenticator_data[32];
+        if flags & FLAG_USER_PRESENT == 0 {
+            return Err(AppError::InvalidPasskey);
+        }
+        let sign_count = u32::from_be_bytes([authenticator_data[33], authenticator_data[34], authenticator_data[35], authenticator_data[36]]);
+
+        Ok((flags, sign_count))
+    }
+}
+
+fn decode(value: &str) -> Result<Vec<u8>, AppError> {
+    general_purpose::URL_SAFE_NO_PAD.decode(value.trim().trim_end_matches('=')).map_err(|_| AppError::InvalidPasskey)
+}
+
+fn cose_field(entries: &[(Value, Value)], label: i64) -> Option<&Value> {
+    entries.iter().find(|(key, _)| key.as_integer() == Some(label.into())).map(|(_, value)| value)
+}
+
+// поддерживаем только ES256, его умеют все платформенные аутентификаторы
+fn cose_key_to_sec1(cose_key: &Value) -> Result<Vec<u8>, AppError> {
+    let Value::Map(entries) = cose_key else { return Err(AppError::InvalidPasskey) };
+    let is_p256 = cose_field(entries, 1).and_then(Value::as_integer) == Some(COSE_KEY_TYPE_EC2.into())
+        && cose_field(entries, 3).and_then(Value::as_integer) == Some(COSE_ALGORITHM_ES256.into())
+        && cose_field(entries, -1).and_then(Value::as_integer) == Some(COSE_CURVE_P256.into());
+    let (Some(x), Some(y)) = (cose_field(entries, -2).and_then(Value::as_bytes), cose_field(entries, -3).and_then(Value::as_bytes)) else {
+        return Err(AppError::InvalidPasskey);
+    };
+    if !is_p256 || x.len() != 32 || y.len() != 32 {
+        return Err(AppError::InvalidPasskey);
+    }
+
+    let public_key = [&[0x04], x.as_slice(), y.as_slice()].concat();
+    VerifyingKey::from_sec1_bytes(&public_key).map_err(|_| AppError::InvalidPasskey)?;
+    Ok(public_key)
+}
+
+impl WebauthnProvider for WebauthnVerifierProvider {
+    fn relying_party_id(&self) -> String {
+        self.rp_id.clone()
+    }
+
+    fn relying_party_name(&self) -> String {
+        self.rp_name.clone()
+    }
+
+    fn verify_registration(&self, client_data_json: String, attestation_object: String) -> Result<PasskeyRegistration, AppError> {
+        let challenge = self.verify_client_data(&decode(&client_data_json)?, "webauthn.create")?;
+
+        let attestation_object: Value = ciborium::de::from_reader(decode(&attestation_object)?.as_slice()).map_err(|_| AppError::InvalidPasskey)?;
+        let Value::Map(entries) = attestation_object else { return Err(AppError::InvalidPasskey) };
+        let field = |name: &str| entries.iter().find(|(key, _)| key.as_text() == Some(name)).map(|(_, value)| value);
+        // запрашиваем attestation "none": происхождение аутентификатора не проверяем
+        if field("fmt").and_then(Value::as_text) != Some("none") {
+            return Err(AppError::InvalidPasskey);
+        }
+        let Some(authenticator_data) = field("authData").and_then(Value::as_bytes) else { return Err(AppError::InvalidPasskey) };
+
+        let (flags, sign_count) = self.verify_authenticator_data(authenticator_data)?;
+        if flags & FLAG_ATTESTED_CREDENTIAL_DATA == 0 {
+            return Err(AppError::InvalidPasskey);
+        }
+        let credential_data = &authenticator_data[AUTHENTICATOR_DATA_LENGTH..];
+        if credential_data.len() < ATTESTED_CREDENTIAL_HEADER_LENGTH {
+            return Err(AppError::InvalidPasskey);
+        }
+        let credential_id_length = u16::from_be_bytes([credential_data[16], credential_data[17]]) as usize;
+        let credential_id_end = ATTESTED_CREDENTIAL_HEADER_LENGTH + credential_id_length;
+        let (Some(credential_id), Some(cose_key)) = (
+            credential_data.get(ATTESTED_CREDENTIAL_HEADER_LENGTH..credential_id_end),
+            credential_data.get(credential_id_end..),
+        ) else {
+            return Err(AppError::InvalidPasskey);
+        };
+        let cose_key: Value = ciborium::de::from_reader(cose_key).map_err(|_| AppError::InvalidPasskey)?;
+
+        Ok(PasskeyRegistration {
+            challenge,
+            credential_id: general_purpose::URL_SAFE_NO_PAD.encode(credential_id),
+            public_key: cose_key_to_sec1(&cose_key)?,
+            sign_count,
+        })
+    }
+
+    fn verify_assertion(&self, public_key: Vec<u8>, client_data_json: String, authenticator_data: String, signature: String) -> Result<PasskeyAssertion, AppError> {
+        let client_data_json = decode(&client_data_json)?;
+        let challenge = self.verify_client_data(&client_data_json, "webauthn.get")?;
+        let authenticator_data = decode(&authenticator_data)?;
+        let (flags, sign_count) = self.verify_authenticator_data(&authenticator_data)?;
+
+        let verifying_key = VerifyingKey::from_sec1_bytes(&public_key).map_err(|_| AppError::InvalidPasskey)?;
+        let signature = Signature::from_der(&decode(&signature)?).map_err(|_| AppError::InvalidPasskey)?;
+        let signed_data = [authenticator_data.as_slice(), sha2::Sha256::digest(&client_data_json).as_slice()].concat();
+        verifying_key.verify(&signed_data, &signature).map_err(|_| AppError::InvalidPasskey)?;
+
+        Ok(PasskeyAssertion { challenge, sign_count, user_verified: flags & FLAG_USER_VERIFIED != 0 })
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use p256::ecdsa::{SigningKey, signature::Signer};
+
+    // программный аутентификатор: создаёт ключ ES256 и подписывает ответы так же, как браузер
+    pub struct SoftwareAuthenticator {
+        signing_key: SigningKey,
+        credential_id: Vec<u8>,
+        rp_id: String,
+        origin: String,
+        pub sign_count: u32,
+    }
+
+    impl SoftwareAuthenticator {
+        pub fn new(rp_id: &str, origin: &str) -> Self {
+            let mut secret = [0u8; 32];
+            getrandom::fill(&mut secret).unwrap();
+            let mut credential_id = vec![0u8; 16];
+            getrandom::fill(&mut credential_id).unwrap();
+
+            Self {
+                signing_key: SigningKey::from_slice(&secret).unwrap(),
+                credential_id,
+                rp_id: rp_id.to_string(),
+                origin: origin.to_string(),
+                sign_count: 0,
+            }
+        }
+
+        pub fn credential_id(&self) -> String {
+            general_purpose::URL_SAFE_NO_PAD.encode(&self.credential_id)
+        }
+
+        fn client_data_json(&self, kind: &str, challenge: &str) -> Vec<u8> {
+            serde_json::json!({ "type": kind, "challenge": challenge, "origin": self.origin }).to_string().into_bytes()
+        }
+
+        fn authenticator_data(&self, flags: u8) -> Vec<u8> {
+            [sha2::Sha256::digest(self.rp_id.as_bytes()).as_slice(), &[flags], &self.sign_count.to_be_bytes()].concat()
+        }
+
+        // возвращает clientDataJSON и attestationObject в base64url
+        pub fn register(&self, challenge: &str) -> (String, String) {
+            let point = self.signing_key.verifying_key().to_encoded_point(false);
+            let cose_key = Value::Map(vec![
+                (Value::Integer(1.into()), Value::Integer(COSE_KEY_TYPE_EC2.into())),
+                (Value::Integer(3.into()), Value::Integer(COSE_ALGORITHM_ES256.into())),
+                (Value::Integer((-1).into()), Value::Integer(COSE_CURVE_P256.into())),
+                (Value::Integer((-2).into()), Value::Bytes(point.x().unwrap().to_vec())),
+                (Value::Integer((-3).into()), Value::Bytes(point.y().unwrap().to_vec())),
+            ]);
+            let mut cose_key_bytes = Vec::new();
+            ciborium::ser::into_writer(&cose_key, &mut cose_key_bytes).unwrap();
+
+            let credential_id_length = u16::try_from(self.credential_id.len()).unwrap().to_be_bytes();
+            let authenticator_data = [
+                self.authenticator_data(FLAG_USER_PRESENT | FLAG_USER_VERIFIED | FLAG_ATTESTED_CREDENTIAL_DATA).as_slice(),
+                &[0u8; 16],
+                &credential_id_length,
+                &self.credential_id,
+                &cose_key_bytes,
+            ].concat();
+            let attestation_object = Value::Map(vec![
+                (Value::Text("fmt".to_string()), Value::Text("none".to_string())),
+                (Value::Text("attStmt".to_string()), Value::Map(vec![])),
+                (Value::Text("authData".to_string()), Value::Bytes(authenticator_data)),
+            ]);
+            let mut attestation_object_bytes = Vec::new();
+            ciborium::ser::into_writer(&attestation_object, &mut attestation_object_bytes).unwrap();
+
+            (
+                general_purpose::URL_SAFE_NO_PAD.encode(self.client_data_json("webauthn.create", challenge)),
+                general_purpose::URL_SAFE_NO_PAD.encode(attestation_object_bytes),
+            )
+        }
+
+        // возвращает clientDataJSON, authenticatorData и подпись в base64url
+        pub fn assert(&mut self, challenge: &str, user_verified: bool) -> (String, String, String) {
+            self.sign_count += 1;
+            let flags = if user_verified { FLAG_USER_PRESENT | FLAG_USER_VERIFIED } else { FLAG_USER_PRESENT };
+            let client_data_json = self.client_data_json("webauthn.get", challenge);
+            let authenticator_data = self.authenticator_data(flags);
+            let signed_data = [authenticator_data.as_slice(), sha2::Sha256::digest(&client_data_json).as_slice()].concat();
+            let signature: Signature = self.signing_key.sign(&signed_data);
+
+            (
+                general_purpose::URL_SAFE_NO_PAD.encode(client_data_json),
+                general_purpose::URL_SAFE_NO_PAD.encode(authenticator_data),
+                general_purpose::URL_SAFE_NO_PAD.encode(signature.to_der().as_bytes()),
+            )
+        }
+    }
+
+    #[test]
+    fn verify_software_authenticator_ceremonies() {
+        // Given
+        let webauthn_provider = WebauthnVerifierProvider::new("localhost".to_string(), "auth".to_string(), "http://localhost:5000".to_string());
+        let mut authenticator = SoftwareAuthenticator::new("localhost", "http://localhost:5000");
+        let (client_data_json, attestation_object) = authenticator.register("registration-challenge");
+
+        // When
+        let registration = webauthn_provider.verify_registration(client_data_json, attestation_object).unwrap();
+        let (client_data_json, authenticator_data, signature) = authenticator.assert("login-challenge", true);
+        let assertion = webauthn_provider.verify_assertion(registration.public_key.clone(), client_data_json.clone(), authenticator_data.clone(), signature.clone()).unwrap();
+        let (_, _, other_signature) = authenticator.assert("other-challenge", true);
+        let forged = webauthn_provider.verify_assertion(registration.public_key, client_data_json, authenticator_data, other_signature);
+
+        // Then
+        assert_eq!(registration.challenge, "registration-challenge");
+        assert_eq!(registration.credential_id, authenticator.credential_id());
+        assert_eq!(assertion.challenge, "login-challenge");
+        assert_eq!(assertion.sign_count, 1);
+        assert!(assertion.user_verified);
+        assert!(matches!(forged, Err(AppError::InvalidPasskey)));
+    }
+
+    #[test]
+    fn reject_foreign_origin_and_relying_party() {
+        // Given
+        let webauthn_provider = WebauthnVerifierProvider::new("localhost".to_string(), "auth".to_string(), "http://localhost:5000".to_string());
+        let phishing = SoftwareAuthenticator::new("localhost", "https://evil.example");
+        let other_rp = SoftwareAuthenticator::new("evil.example", "http://localhost:5000");
+
+        // When
+        let (client_data_json, attestation_object) = phishing.register("challenge");
+        let phishing_res = webauthn_provider.verify_registration(client_data_json, attestation_object);
+        let (client_data_json, attestation_object) = other_rp.register("challenge");
+        let other_rp_res = webauthn_provider.verify_registration(client_data_json, attestation_object);
+        let garbage_res = webauthn_provider.verify_registration("e30".to_string(), "not base64!".to_string());
+
+        // Then
+        assert!(matches!(phishing_res, Err(AppError::InvalidPasskey)));
+        assert!(matches!(other_rp_res, Err(AppError::InvalidPasskey)));
+        assert!(matches!(garbage_res, Err(AppError::InvalidPasskey)));
+    }
+}